@@ -1,9 +1,11 @@
 use avalon_asset::packed;
 use avalon_asset::bundle;
-use avalon_asset::{ shader, texture, text, model };
+use avalon_asset::{ shader, texture, text, model, config, dependency };
 use avalon_asset::asset::{ Type, Unit, Metadata };
 use anyhow::Result;
 
+mod manifest;
+
 trait Operation {
     fn name(&self) -> &str;
     fn execute(&mut self) -> Result<()>;
@@ -15,7 +17,7 @@ struct CreateBundle {
 }
 
 impl CreateBundle {
-    fn add_asset(&self) -> Result<Option<Metadata>> {
+    fn add_asset(&self) -> Result<Option<Vec<Metadata>>> {
         println!("Bundle Asset");
 
         let path_validator = |path: &str| {
@@ -59,11 +61,13 @@ impl CreateBundle {
                 Type::Shader,
                 Type::Model,
                 Type::Text,
+                Type::Config,
             ])
                 .with_help_message("Manually select filetype from options")
                 .prompt()?
         };
 
+        let mut texture_compression = None;
         let unit = match file_type {
             Type::Shader => {
                 let stage = inquire::Select::new("Shader Stage:", vec![
@@ -87,13 +91,12 @@ impl CreateBundle {
                     .with_help_message("The colour space of the texture")
                     .prompt()?;
                 Unit::Texture(texture::Texture {
-                    colour_space
+                    colour_space,
+                    compression: compression_prompt()?
                 })
             },
             Type::Text => {
-                let language = inquire::Select::new("Language:", vec![
-                    text::Language::English
-                ])
+                let language = inquire::Select::new("Language:", text::Language::ALL.to_vec())
                     .with_help_message("The language the text is written in")
                     .prompt()?;
                 Unit::Text(text::Text {
@@ -101,10 +104,27 @@ impl CreateBundle {
                 })
             },
             Type::Model => {
-                Unit::Model(model::Model {})
+                if model::gltf::is_gltf(&path) {
+                    println!("glTF models are split into a model per mesh primitive and a texture per material texture");
+                    texture_compression = compression_prompt()?;
+                }
+                let lods = inquire::CustomType::<u32>::new("Levels of detail:")
+                    .with_default(0)
+                    .with_help_message("Simplified versions to generate, each with about half the triangles of the last")
+                    .prompt()?;
+                Unit::Model(model::Model {
+                    lods: Some(lods)
+                })
+            },
+            Type::Config => {
+                Unit::Config(config::Config {})
             },
         };
 
+        let dependencies = inquire::Text::new("Dependencies:")
+            .with_help_message("Comma separated uuids, tags in this bundle or `bundle/tag` (blank for none)")
+            .prompt()?;
+
         let valid = inquire::Confirm::new("Is the above configuration correct?")
             .with_default(true)
             .prompt()?;
@@ -113,7 +133,11 @@ impl CreateBundle {
             return Ok(None);
         }
 
-        Ok(Some(Metadata::new(tag, path, unit)))
+        let mut metadata = Metadata::new(tag, path, unit);
+        metadata.dependencies = split_list(&dependencies)
+            .map(|dependency| dependency.parse::<dependency::Dependency>().unwrap())
+            .collect();
+        Ok(Some(model::gltf::expand(metadata, texture_compression)?))
     }
 }
 
@@ -127,17 +151,19 @@ impl Operation for CreateBundle {
             .with_help_message("The human-readable tag/name for the bundle")
             .prompt()?;
 
-        let mut bundle = bundle::Bundle {
-            name: bundle_tag,
-            group: Vec::new()
-        };
+        let bundle_dependencies = inquire::Text::new("Bundle Dependencies:")
+            .with_help_message("Comma separated names of bundles that must be loaded first (blank for none)")
+            .prompt()?;
+
+        let mut bundle = bundle::Bundle::new(bundle_tag);
+        bundle.dependencies = split_list(&bundle_dependencies).map(String::from).collect();
 
         let mut continue_adding = true;
         while continue_adding {
             let metadata_result = self.add_asset();
             if let Ok(metadata) = metadata_result {
                 if let Some(metadata) = metadata {
-                    bundle.group.push(metadata);
+                    bundle.group.extend(metadata);
                 }
             } else {
                 println!("Error while adding asset to bundle: {}", metadata_result.err().unwrap());
@@ -147,6 +173,14 @@ impl Operation for CreateBundle {
                 .prompt()?;
         }
 
+        let problems = bundle.validate();
+        if !problems.is_empty() {
+            for problem in problems.iter() {
+                println!("  {problem}");
+            }
+            anyhow::bail!("{} dependency problem(s) in bundle \"{}\"", problems.len(), bundle.name);
+        }
+
         let start_time = std::time::Instant::now();
 
         let bundle_len = bundle.group.len();
@@ -158,7 +192,8 @@ impl Operation for CreateBundle {
         );
 
         let packed = packed::Packed::from(bundle);
-        packed.pack_to_file(&pack_dir)?;
+        let report = packed.pack_to_file(&pack_dir)?;
+        print_report(&report);
 
         println!(
             "Packed {} assets to {} in {:.3} seconds",
@@ -171,6 +206,25 @@ impl Operation for CreateBundle {
     }
 }
 
+fn print_report(report: &packed::write::Report) {
+    for (tag, stats) in report.models.iter() {
+        println!("  {tag}: {stats}");
+    }
+}
+
+fn compression_prompt() -> Result<Option<texture::Compression>> {
+    let mut compressions = vec!["None".to_string()];
+    compressions.extend(texture::Compression::ALL.iter().map(|compression| compression.to_string()));
+    let compression = inquire::Select::new("Compression:", compressions)
+        .with_help_message("Block compression for the GPU. Mipmaps are generated either way")
+        .raw_prompt()?;
+    Ok(compression.index.checked_sub(1).map(|idx| texture::Compression::ALL[idx]))
+}
+
+fn split_list(list: &str) -> impl Iterator<Item = &str> {
+    list.split(',').map(str::trim).filter(|item| !item.is_empty())
+}
+
 struct Op {
     operation: Box<dyn Operation>
 }
@@ -193,7 +247,126 @@ impl std::fmt::Display for Op {
     }
 }
 
+/// Packs the bundle described by a manifest, printing every problem found before giving up
+fn build_manifest(path: &std::path::Path, output: Option<&std::path::PathBuf>) -> Result<usize> {
+    let manifest = manifest::Manifest::read(path)?;
+    let root = path.parent().unwrap_or(std::path::Path::new("."));
+    let bundle = match manifest.to_bundle(root) {
+        Ok(bundle) => bundle,
+        Err(problems) => {
+            for problem in problems.iter() {
+                eprintln!("  [{}] {}", problem.asset, problem.message);
+            }
+            anyhow::bail!("{} problem(s) in manifest for bundle \"{}\"", problems.len(), manifest.name);
+        }
+    };
+
+    let pack_dir = manifest::output_directory(&manifest, path, output);
+    std::fs::create_dir_all(&pack_dir)?;
+    let bundle_path = pack_dir.join(format!("{}.{}", bundle.name, packed::Packed::EXTENSION));
+    // builds replace their previous output
+    if bundle_path.is_file() {
+        std::fs::remove_file(&bundle_path)?;
+    }
+
+    let bundle_len = bundle.group.len();
+    let report = packed::Packed::from(bundle).pack_to_file(&bundle_path)?;
+    print_report(&report);
+    Ok(bundle_len)
+}
+
+const USAGE: &str = "Usage: asset_generator [<manifest.json>... [--output <directory>]]
+       asset_generator --upgrade <bundle>...
+With no arguments the interactive prompts are used instead";
+
+/// Rewrites bundles packed in an older format version in place
+fn upgrade(bundles: &[std::path::PathBuf]) -> i32 {
+    let mut failed = 0;
+    for bundle in bundles {
+        match packed::migrate::upgrade_file(bundle) {
+            Ok(true) => println!("Upgraded {} to format version {}", bundle.display(), packed::Packed::FORMAT_VERSION),
+            Ok(false) => println!("{} is already current", bundle.display()),
+            Err(e) => {
+                eprintln!("Failed to upgrade {}: {e}", bundle.display());
+                failed += 1;
+            }
+        }
+    }
+    if failed > 0 {
+        eprintln!("{failed} of {} bundle(s) failed to upgrade", bundles.len());
+        return 1;
+    }
+    0
+}
+
+/// Exit codes: 0 when every bundle packed, 1 when any failed, 2 for bad arguments
+fn batch(args: Vec<String>) -> i32 {
+    let mut manifests = Vec::new();
+    let mut output = None;
+    let mut upgrading = false;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--output" | "-o" => match args.next() {
+                Some(directory) => output = Some(std::path::PathBuf::from(directory)),
+                None => {
+                    eprintln!("--output needs a directory\n{USAGE}");
+                    return 2;
+                }
+            },
+            "--upgrade" => upgrading = true,
+            "--help" | "-h" => {
+                println!("{USAGE}");
+                return 0;
+            },
+            _ if arg.starts_with('-') => {
+                eprintln!("Unknown option {arg}\n{USAGE}");
+                return 2;
+            },
+            _ => manifests.push(std::path::PathBuf::from(arg)),
+        }
+    }
+    if manifests.is_empty() {
+        eprintln!("{USAGE}");
+        return 2;
+    }
+    if upgrading {
+        return upgrade(&manifests);
+    }
+
+    let mut failures = Vec::new();
+    for manifest in manifests.iter() {
+        let start_time = std::time::Instant::now();
+        match build_manifest(manifest, output.as_ref()) {
+            Ok(count) => println!(
+                "Packed {} assets from {} in {:.3} seconds",
+                count,
+                manifest.display(),
+                start_time.elapsed().as_secs_f32()
+            ),
+            Err(e) => {
+                eprintln!("Failed to pack {}: {e}", manifest.display());
+                failures.push(manifest);
+            }
+        }
+    }
+
+    if failures.is_empty() {
+        return 0;
+    }
+    eprintln!("{} of {} bundle(s) failed:", failures.len(), manifests.len());
+    for manifest in failures {
+        eprintln!("  {}", manifest.display());
+    }
+    1
+}
+
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        std::process::exit(batch(args));
+    }
+
     clearscreen::clear().unwrap();
     let mut operation = inquire::Select::new("Which operation?", vec![
         Op::new::<CreateBundle>()
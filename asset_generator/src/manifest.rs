@@ -0,0 +1,205 @@
+use avalon_asset::asset::{ Type, Unit, Metadata };
+use avalon_asset::{ bundle, shader, texture, text, model, config, dependency };
+use miniserde::{ json, Deserialize };
+use std::path::{ Path, PathBuf };
+use std::str::FromStr;
+
+/// One asset in a manifest. Only `path` and `tag` are required, everything else is
+/// worked out from the file extension when left out
+#[derive(Deserialize)]
+struct Entry {
+    path: String,
+    tag: String,
+    #[serde(rename = "type")]
+    file_type: Option<String>,
+    /// Fixed so rebuilding the bundle keeps the same ids
+    uuid: Option<String>,
+    stage: Option<shader::Stage>,
+    colour_space: Option<texture::ColourSpace>,
+    /// Textures, including those inside glTF models, are stored uncompressed when left out
+    compression: Option<texture::Compression>,
+    language: Option<text::Language>,
+    /// Simplified levels of detail for models, none when left out
+    lods: Option<u32>,
+    /// Uuids, tags in this bundle, or `bundle/tag` for tags in another
+    dependencies: Option<Vec<String>>,
+}
+
+/// A bundle described as json, for building without prompts. glTF models are split into a model per mesh
+/// primitive, tagged `<tag>.<mesh>`, and a texture per material texture, tagged `<tag>.<material>.<role>`
+///
+/// ```json
+/// {
+///     "name": "props",
+///     "dependencies": ["core"],
+///     "assets": [
+///         { "path": "shaders/basic.vert", "tag": "basic" },
+///         { "path": "textures/wall.png", "tag": "wall", "colour_space": "SRGBA", "compression": "BC7" },
+///         { "path": "models/crate.mdl", "tag": "crate", "type": "model", "dependencies": ["wall", "core/lid"] },
+///         { "path": "models/barrel.glb", "tag": "barrel", "compression": "BC7", "lods": 3 }
+///     ]
+/// }
+/// ```
+#[derive(Deserialize)]
+pub struct Manifest {
+    pub name: String,
+    /// Directory the bundle is written to, relative to the manifest
+    pub output: Option<String>,
+    /// Bundles that must be loaded before this one
+    dependencies: Option<Vec<String>>,
+    assets: Vec<Entry>,
+}
+
+/// Something wrong with a single asset in the manifest
+pub struct Problem {
+    pub asset: String,
+    pub message: String,
+}
+
+impl Manifest {
+    pub fn read(path: impl AsRef<Path>) -> anyhow::Result<Manifest> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Cannot read manifest {}: {e}", path.display()))?;
+        json::from_str(&contents)
+            .map_err(|_| anyhow::anyhow!("Manifest {} is not valid json or is missing a name, tag or path", path.display()))
+    }
+
+    /// Resolves every asset against `root`, reporting every problem rather than stopping at the first
+    pub fn to_bundle(&self, root: impl AsRef<Path>) -> Result<bundle::Bundle, Vec<Problem>> {
+        let root = root.as_ref();
+        let mut group = Vec::new();
+        let mut problems = Vec::new();
+        for (idx, entry) in self.assets.iter().enumerate() {
+            let asset = if entry.tag.is_empty() {
+                format!("#{idx}")
+            } else {
+                entry.tag.clone()
+            };
+            let parts = entry.to_metadata(&self.name, root).and_then(|metadata| {
+                model::gltf::expand(metadata, entry.compression).map_err(|e| e.to_string())
+            });
+            match parts {
+                Ok(parts) => for metadata in parts {
+                    if group.iter().any(|other: &Metadata| other.uuid == metadata.uuid) {
+                        problems.push(Problem { asset: asset.clone(), message: format!("Uuid {} is used more than once", metadata.uuid) });
+                    } else {
+                        group.push(metadata);
+                    }
+                },
+                Err(message) => problems.push(Problem { asset, message }),
+            }
+        }
+
+        if !problems.is_empty() {
+            return Err(problems);
+        }
+        let bundle = bundle::Bundle {
+            name: self.name.clone(),
+            group,
+            dependencies: self.dependencies.clone().unwrap_or_default(),
+        };
+
+        let problems: Vec<Problem> = bundle.validate().into_iter()
+            .map(|e| Problem { asset: self.name.clone(), message: e.to_string() })
+            .collect();
+        if !problems.is_empty() {
+            return Err(problems);
+        }
+        Ok(bundle)
+    }
+}
+
+impl Entry {
+    fn to_metadata(&self, bundle: &str, root: &Path) -> Result<Metadata, String> {
+        if self.tag.is_empty() {
+            return Err("Asset needs a tag".to_string());
+        }
+
+        let path = root.join(&self.path);
+        if !path.is_file() {
+            return Err(format!("File [{}] does not exist", path.display()));
+        }
+        let path = std::fs::canonicalize(&path).map_err(|e| format!("Cannot resolve [{}]: {e}", path.display()))?;
+
+        let file_type = match self.file_type.as_deref() {
+            Some(file_type) => parse_type(file_type)?,
+            None => Type::from_path(&path)
+                .ok_or_else(|| format!("Cannot determine the type of [{}], set \"type\"", path.display()))?,
+        };
+
+        let unit = match file_type {
+            Type::Shader => {
+                let stage = self.stage.or_else(|| stage_from_path(&path))
+                    .ok_or_else(|| "Shader needs a \"stage\"".to_string())?;
+                Unit::Shader(shader::Shader { stage })
+            },
+            Type::Texture => Unit::Texture(texture::Texture {
+                colour_space: self.colour_space.unwrap_or(texture::ColourSpace::SRGBA),
+                compression: self.compression
+            }),
+            Type::Text => Unit::Text(text::Text {
+                language: self.language.unwrap_or(text::Language::English)
+            }),
+            Type::Model => Unit::Model(model::Model {
+                lods: self.lods
+            }),
+            Type::Config => Unit::Config(config::Config {}),
+        };
+
+        let mut metadata = Metadata::new(self.tag.clone(), path, unit);
+        metadata.uuid = match self.uuid.as_deref() {
+            Some(uuid) => uuid::Uuid::from_str(uuid).map_err(|e| format!("Invalid uuid {uuid}: {e}"))?,
+            None => stable_uuid(bundle, &self.tag, &self.path),
+        };
+        metadata.dependencies = self.dependencies.iter()
+            .flatten()
+            .map(|dependency| dependency::Dependency::from_str(dependency).unwrap())
+            .collect();
+        Ok(metadata)
+    }
+}
+
+fn parse_type(file_type: &str) -> Result<Type, String> {
+    match file_type.to_ascii_lowercase().as_str() {
+        "shader" => Ok(Type::Shader),
+        "texture" => Ok(Type::Texture),
+        "model" => Ok(Type::Model),
+        "text" => Ok(Type::Text),
+        "config" => Ok(Type::Config),
+        _ => Err(format!("Unknown type \"{file_type}\", expected shader, texture, model, text or config")),
+    }
+}
+
+fn stage_from_path(path: &Path) -> Option<shader::Stage> {
+    match path.extension()?.to_ascii_lowercase().to_str()? {
+        "vert" => Some(shader::Stage::Vertex),
+        "frag" => Some(shader::Stage::Fragment),
+        "comp" => Some(shader::Stage::Compute),
+        _ => None,
+    }
+}
+
+/// FNV-1a, so the same manifest always produces the same ids without a uuid in every entry
+fn stable_uuid(bundle: &str, tag: &str, path: &str) -> uuid::Uuid {
+    let hash = |offset: u64| {
+        let mut hash = offset;
+        for byte in [bundle, tag, path].join("\0").bytes() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+        hash
+    };
+    let bytes = ((hash(0xcbf29ce484222325) as u128) << 64 | hash(0x84222325cbf29ce4) as u128).to_be_bytes();
+    uuid::Builder::from_random_bytes(bytes).into_uuid()
+}
+
+/// The directory the bundle for this manifest goes in
+pub fn output_directory(manifest: &Manifest, manifest_path: &Path, output: Option<&PathBuf>) -> PathBuf {
+    let root = manifest_path.parent().unwrap_or(Path::new("."));
+    match (output, manifest.output.as_ref()) {
+        (Some(output), _) => output.clone(),
+        (None, Some(output)) => root.join(output),
+        (None, None) => root.to_path_buf(),
+    }
+}
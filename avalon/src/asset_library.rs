@@ -1,23 +1,137 @@
 mod load;
+pub mod error;
+pub mod stream;
 
+use std::cell::{ Cell, RefCell };
 use std::collections::{ HashMap, HashSet };
-use avalon_asset::{ asset, bundle, packed };
+use std::rc::Rc;
+use avalon_asset::{ asset, bundle, dependency, text };
+use crate::model;
 use glob;
 
-pub trait Asset: std::fmt::Debug {}
+/// Anything the library can store. Views check the concrete type before handing it out
+pub trait Asset: std::fmt::Debug + std::any::Any {
+    fn type_name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
 
-#[derive(Debug, Clone)]
-pub struct AssetView<'v, T: Asset> {
-    // we never refer to _asset, it just exists as a reference counter
-    // i guess we could use the metadata if needed, but generally nah
-    _asset: asset::AssetReference<'v>,
-    resource: &'v T
+    /// An estimate, used for budgets and reporting
+    fn resident_size(&self) -> Size {
+        Size::default()
+    }
+}
+
+/// Bytes held in main memory and on the GPU
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct Size {
+    pub cpu: usize,
+    pub gpu: usize,
+}
+
+/// Limits for `Residency::Lru`. `None` is unlimited
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct Budget {
+    pub cpu: Option<usize>,
+    pub gpu: Option<usize>,
+}
+
+/// What happens to assets once nothing refers to them
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum Residency {
+    /// Unloaded straight away
+    #[default]
+    Immediate,
+    /// Kept in case they are wanted again, until the budget is exceeded. The least recently used go first
+    Lru(Budget),
+}
+
+impl std::ops::Add for Size {
+    type Output = Size;
+    fn add(self, rhs: Size) -> Size {
+        Size {
+            cpu: self.cpu + rhs.cpu,
+            gpu: self.gpu + rhs.gpu,
+        }
+    }
+}
+
+impl std::ops::AddAssign for Size {
+    fn add_assign(&mut self, rhs: Size) {
+        *self = *self + rhs;
+    }
+}
+
+impl Slot {
+    fn resident_size(&self) -> Size {
+        self.resource.borrow().as_ref().map_or(Size::default(), |resource| resource.resident_size())
+    }
+}
+
+impl Budget {
+    fn exceeded_by(&self, size: Size) -> bool {
+        self.cpu.is_some_and(|cpu| size.cpu > cpu) || self.gpu.is_some_and(|gpu| size.gpu > gpu)
+    }
+}
+
+impl Asset for avalon_asset::config::Table {
+    fn resident_size(&self) -> Size {
+        Size { cpu: self.to_string().len(), gpu: 0 }
+    }
+}
+
+impl Asset for avalon_asset::text::Strings {
+    fn resident_size(&self) -> Size {
+        let cpu = self.languages()
+            .filter_map(|language| self.table(language))
+            .map(|table| table.to_string().len())
+            .sum();
+        Size { cpu, gpu: 0 }
+    }
+}
+
+/// Decoded parts of one asset that share a tag, keyed by the first part's metadata
+type Parts<T> = Vec<(asset::Metadata, Vec<(T, Vec<u8>)>)>;
+
+/// Where a loaded asset lives, shared between the library and every view of it
+#[derive(Debug)]
+struct Slot {
+    tag: RefCell<String>,
+    resource: RefCell<Option<Box<dyn Asset>>>
+}
+
+/// Keeps an asset loaded for as long as it is alive, independent of any borrow of the library
+#[derive(Debug)]
+pub struct AssetView<T: Asset> {
+    slot: Rc<Slot>,
+    asset: std::marker::PhantomData<fn() -> T>
+}
+
+impl<T: Asset> Clone for AssetView<T> {
+    fn clone(&self) -> AssetView<T> {
+        AssetView {
+            slot: self.slot.clone(),
+            asset: std::marker::PhantomData
+        }
+    }
 }
 
-impl<'v, T: Asset> std::ops::Deref for AssetView<'v, T> {
-    type Target = T;
-    fn deref(&self) -> &'v T {
-        self.resource
+impl<T: Asset> AssetView<T> {
+    /// The asset as currently loaded, so reloads are seen on the next call. Fails if it has since been
+    /// dropped from its bundle, or replaced with a different type. Not to be held across `Library::update`,
+    /// which writes reloads into place
+    pub fn get(&self) -> Result<std::cell::Ref<'_, T>, error::View> {
+        let resource = self.slot.resource.borrow();
+        let found = match resource.as_deref() {
+            Some(asset) => asset.type_name(),
+            None => return Err(error::View::NotLoaded(self.slot.tag.borrow().clone())),
+        };
+        std::cell::Ref::filter_map(resource, |resource| {
+            resource.as_deref().and_then(|asset| (asset as &dyn std::any::Any).downcast_ref::<T>())
+        }).map_err(|_| error::View::WrongType {
+            tag: self.slot.tag.borrow().clone(),
+            expected: std::any::type_name::<T>(),
+            found
+        })
     }
 }
 
@@ -35,86 +149,630 @@ impl std::fmt::Debug for BundleView<'_> {
     }
 }
 
-impl<'r, 'v: 'r> BundleView<'v> {
-    pub fn tag<T: Asset>(&self, asset_tag: impl Into<String>) -> Option<AssetView<'r, T>> {
-        let asset_meta = self.bundle.asset(asset_tag)?;
-        let (asset_reference, asset) = self.library.asset_library.get_key_value(&asset_meta.into())?;
-        Some(AssetView {
-            _asset: asset_reference.refer(),
-            resource: {
-                let ref_asset = asset.as_ref();
-                let asset_ptr = ref_asset as *const dyn Asset;
-                let asset_cvoid = asset_ptr as *const std::ffi::c_void;
-                /* Proof this fuckery is safe:
-                 *  We assume the programmer is competent, and that the asset_tag refers
-                 *  to the type we are casting to. We have no guarantees that this will
-                 *  work, but it will crash at runtime
-                 */
-                unsafe {
-                    let ptr_asset: *const T = std::mem::transmute(asset_cvoid);
-                    &*ptr_asset
-                }
+impl<'v> BundleView<'v> {
+    /// Text in the library's active language, falling back to the asset's default language
+    pub fn text(&self, asset_tag: impl Into<String>, key: &str) -> Option<String> {
+        self.text_with(asset_tag, key, &[])
+    }
+
+    /// Like `text`, with each `{name}` replaced by the matching argument
+    pub fn text_with(&self, asset_tag: impl Into<String>, key: &str, arguments: &[(&str, &dyn std::fmt::Display)]) -> Option<String> {
+        let strings = self.tag::<text::Strings>(asset_tag).ok()?;
+        let strings = strings.get().ok()?;
+        strings.format(key, self.library.language, arguments)
+    }
+
+    pub fn tag<T: Asset>(&self, asset_tag: impl Into<String>) -> Result<AssetView<T>, error::View> {
+        let asset_tag = asset_tag.into();
+        let asset_meta = self.bundle.asset(asset_tag.clone())
+            .ok_or_else(|| error::View::NoAssetWithTag(asset_tag.clone()))?;
+        self.library.touch(&asset_meta);
+        let slot = self.library.asset_library.get(&asset_meta.into())
+            .ok_or_else(|| error::View::NotLoaded(asset_tag.clone()))?;
+
+        let view = AssetView {
+            slot: slot.clone(),
+            asset: std::marker::PhantomData
+        };
+        view.get()?;
+        Ok(view)
+    }
+
+    /// Every asset in the bundle, with the type it was loaded as
+    pub fn assets(&self) -> impl Iterator<Item = Entry<'v>> + 'v {
+        let library = self.library;
+        self.bundle.group.iter().map(move |metadata| {
+            let stored = library.asset_library.get(&metadata.clone().into())
+                .map(|slot| slot.resource.borrow());
+            let stored = stored.as_ref().and_then(|resource| resource.as_deref());
+            Entry {
+                metadata,
+                type_id: stored.map(|asset| (asset as &dyn std::any::Any).type_id()),
+                type_name: stored.map(|asset| asset.type_name()),
             }
         })
     }
 }
 
+/// One asset listed by `BundleView::assets`
+#[derive(Debug, Clone)]
+pub struct Entry<'v> {
+    pub metadata: &'v asset::Metadata,
+    /// None when it failed to load, or for stages and languages grouped under an earlier asset's tag
+    pub type_id: Option<std::any::TypeId>,
+    pub type_name: Option<&'static str>,
+}
+
+impl Entry<'_> {
+    pub fn is<T: Asset>(&self) -> bool {
+        self.type_id == Some(std::any::TypeId::of::<T>())
+    }
+}
+
+#[derive(Debug)]
+struct Request {
+    path: std::path::PathBuf,
+    status: stream::Status,
+    /// Whether the bundle gains a reference once it is uploaded. Reloads keep the count they had
+    retain: bool,
+}
+
 #[derive(Debug)]
 pub struct Library {
-    asset_library: HashMap<asset::Asset, Box<dyn Asset>>,
-    bundle_library: HashSet<bundle::Bundle>
+    /// Views hold the other references to each slot
+    asset_library: HashMap<asset::Asset, Rc<Slot>>,
+    bundle_library: HashSet<bundle::Bundle>,
+    language: text::Language,
+    dependencies: dependency::Graph,
+    scan_directory: Option<std::path::PathBuf>,
+    /// Bundles read so far and when they were last modified
+    watched: HashMap<std::path::PathBuf, std::time::SystemTime>,
+    loader: stream::Loader,
+    /// Indexed by handle
+    requests: Vec<Request>,
+    /// Requests before this were finished when the current batch started
+    batch_start: usize,
+    /// Decoded off the GL thread, held until the batch is complete so dependencies upload first
+    decoded: Vec<(stream::Handle, stream::DecodedBundle)>,
+    /// Assets in a bundle stay loaded while it has references
+    bundle_references: HashMap<String, usize>,
+    /// Content hash each uploaded asset was read with, so reloads can skip what has not changed
+    content_hashes: HashMap<asset::Metadata, u64>,
+    residency: Residency,
+    clock: Cell<u64>,
+    /// When each asset was last viewed, by `clock`
+    last_used: RefCell<HashMap<asset::Metadata, u64>>
 }
 
 impl Library {
+    fn new(scan_directory: Option<std::path::PathBuf>) -> Library {
+        Library {
+            asset_library: HashMap::new(),
+            bundle_library: HashSet::new(),
+            language: text::Language::English,
+            dependencies: dependency::Graph::default(),
+            scan_directory,
+            watched: HashMap::new(),
+            loader: stream::Loader::new(),
+            requests: Vec::new(),
+            batch_start: 0,
+            decoded: Vec::new(),
+            bundle_references: HashMap::new(),
+            content_hashes: HashMap::new(),
+            residency: Residency::default(),
+            clock: Cell::new(0),
+            last_used: RefCell::new(HashMap::new())
+        }
+    }
+
+    pub fn language(&self) -> text::Language {
+        self.language
+    }
+
+    /// The language text is looked up in from now on
+    pub fn set_language(&mut self, language: text::Language) {
+        self.language = language;
+    }
+
     pub fn bundle(&self, tag: impl Into<String>) -> Option<BundleView> {
         Some(BundleView {
             library: self,
-            bundle: self.bundle_library.get(&bundle::Bundle {
-                name: tag.into(),
-                group: Vec::new()
-            })?
+            bundle: self.bundle_library.get(&bundle::Bundle::new(tag))?
         })
     }
 
-    pub fn new_with_scan(scan_directory: impl AsRef<std::path::Path>) -> Library {
-        let scan_directory = std::path::Path::canonicalize(scan_directory.as_ref()).unwrap();
-        let bundles = glob::glob(scan_directory.join("*.bundle").to_str().unwrap())
+    /// How every loaded asset and bundle refers to the others
+    pub fn dependencies(&self) -> &dependency::Graph {
+        &self.dependencies
+    }
+
+    fn scan(&self) -> Vec<std::path::PathBuf> {
+        let Some(scan_directory) = self.scan_directory.as_ref() else {
+            return Vec::new();
+        };
+        glob::glob(scan_directory.join("*.bundle").to_str().unwrap())
             .unwrap()
             .filter_map(|f| f.ok())
-            .map(packed::Packed::read_from_file);
+            .collect()
+    }
 
-        let mut library = Library {
-            asset_library: HashMap::new(),
-            bundle_library: HashSet::new()
+    /// Loads every bundle in the directory before returning
+    pub fn new_with_scan(scan_directory: impl AsRef<std::path::Path>) -> Library {
+        let mut library = Library::new_with_scan_async(scan_directory);
+        library.wait();
+        library
+    }
+
+    /// Queues every bundle in the directory and returns straight away. Call `update` each frame to finish loading
+    pub fn new_with_scan_async(scan_directory: impl AsRef<std::path::Path>) -> Library {
+        let scan_directory = std::path::Path::canonicalize(scan_directory.as_ref()).unwrap();
+        let mut library = Library::new(Some(scan_directory));
+        for path in library.scan() {
+            library.load(path);
+        }
+        library
+    }
+
+    /// Reads the bundle on a worker thread. It is uploaded by a later `update`, and holds a reference
+    /// until `release_bundle`
+    pub fn load(&mut self, path: impl Into<std::path::PathBuf>) -> stream::Handle {
+        self.request(path.into(), true)
+    }
+
+    fn request(&mut self, path: std::path::PathBuf, retain: bool) -> stream::Handle {
+        if self.progress().is_done() {
+            self.batch_start = self.requests.len();
+        }
+        if let Ok(modified) = std::fs::metadata(&path).and_then(|metadata| metadata.modified()) {
+            self.watched.insert(path.clone(), modified);
+        }
+
+        let handle = stream::Handle(self.requests.len());
+        self.requests.push(Request {
+            path: path.clone(),
+            status: stream::Status::Pending,
+            retain
+        });
+        // bundles are named after their file
+        let name = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+        let resident = self.resident_hashes(&name);
+        self.loader.queue(handle, path, resident);
+        handle
+    }
+
+    fn resident_hashes(&self, name: &str) -> HashMap<asset::Metadata, u64> {
+        let Some(bundle) = self.bundle_library.get(&bundle::Bundle::new(name)) else {
+            return HashMap::new();
         };
+        bundle.group.iter()
+            .filter_map(|asset| self.content_hashes.get_key_value(asset))
+            .map(|(asset, hash)| (asset.clone(), *hash))
+            .collect()
+    }
+
+    pub fn status(&self, handle: stream::Handle) -> &stream::Status {
+        &self.requests[handle.0].status
+    }
+
+    /// Bundles in the current batch, for a loading screen
+    pub fn progress(&self) -> stream::Progress {
+        let mut progress = stream::Progress::default();
+        for request in self.requests[self.batch_start..].iter() {
+            progress.total += 1;
+            match request.status {
+                stream::Status::Pending => {},
+                stream::Status::Ready => progress.ready += 1,
+                stream::Status::Failed(_) => progress.failed += 1,
+            }
+        }
+        progress
+    }
+
+    pub fn is_loading(&self) -> bool {
+        !self.progress().is_done()
+    }
 
-        // todo! multithread this
-        for packed in bundles {
-            let Ok(packed) = packed else {
-                println!("Error loading bundle: {}", packed.err().unwrap());
+    /// Queues bundles rebuilt since they were last read, and any new ones in the scan directory. Bundles that
+    /// failed to read are only retried once their file changes again. Assets are matched by uuid and replaced
+    /// in place, so existing views see the new data on their next access. Only assets whose metadata or
+    /// content changed are decoded again
+    pub fn reload_changed(&mut self) -> Vec<stream::Handle> {
+        let mut queued = Vec::new();
+        for path in self.scan() {
+            let pending = self.requests.iter().any(|request| request.path == path && request.status.is_pending());
+            let modified = std::fs::metadata(&path).and_then(|metadata| metadata.modified()).ok();
+            if pending || (modified.is_some() && self.watched.get(&path) == modified.as_ref()) {
                 continue;
+            }
+            // bundles are named after their file
+            let name = path.file_stem().unwrap_or_default().to_string_lossy();
+            let loaded = self.bundle_library.contains(&bundle::Bundle::new(name));
+            queued.push(self.request(path, !loaded));
+        }
+        queued
+    }
+
+    /// Requests the workers have not finished with yet
+    fn decoding(&self) -> usize {
+        let pending = self.requests[self.batch_start..].iter()
+            .filter(|request| request.status.is_pending())
+            .count();
+        pending - self.decoded.len()
+    }
+
+    fn receive(&mut self, handle: stream::Handle, result: Result<stream::DecodedBundle, String>) {
+        match result {
+            Ok(decoded) => self.decoded.push((handle, decoded)),
+            Err(e) => {
+                // the file stays watched as it was when read, so a bundle still being written is retried
+                // once it is finished rather than on every reload
+                let request = &mut self.requests[handle.0];
+                println!("Error loading bundle {}: {}", request.path.display(), e);
+                request.status = stream::Status::Failed(e);
+            }
+        }
+    }
+
+    /// Collects bundles the workers have finished and, once the batch is decoded, uploads them on this
+    /// thread. Then unloads whatever is no longer used. Returns what was uploaded
+    pub fn update(&mut self) -> Vec<asset::Metadata> {
+        let finished: Vec<_> = self.loader.finished().collect();
+        for (handle, result) in finished {
+            self.receive(handle, result);
+        }
+        let loaded = if self.decoding() > 0 {
+            Vec::new()
+        } else {
+            self.upload_decoded()
+        };
+        self.collect();
+        loaded
+    }
+
+    /// Blocks until everything queued is uploaded
+    pub fn wait(&mut self) -> Vec<asset::Metadata> {
+        while self.decoding() > 0 {
+            let Some((handle, result)) = self.loader.wait() else {
+                break;
             };
+            self.receive(handle, result);
+        }
+        self.upload_decoded()
+    }
+
+    /// Rebuilds the dependency graph with the decoded bundles replacing any loaded under the same name,
+    /// then uploads them so every bundle and asset comes after what it depends on
+    fn upload_decoded(&mut self) -> Vec<asset::Metadata> {
+        if self.decoded.is_empty() {
+            return Vec::new();
+        }
+        let mut decoded = std::mem::take(&mut self.decoded);
+
+        let incoming: Vec<&bundle::Bundle> = decoded.iter().map(|(_, decoded)| &decoded.bundle).collect();
+        let existing = self.bundle_library.iter().filter(|bundle| !incoming.contains(bundle));
+        self.dependencies = dependency::Graph::new(existing.chain(incoming.iter().copied()));
+        for e in self.dependencies.errors() {
+            println!("Dependency error: {}", e);
+        }
 
-            let bundle = packed.bundle;
-            for asset in bundle.group.iter() {
-                let data = packed.data_map.get(asset).unwrap();
-                match asset.unit {
-                    asset::Unit::Model(model) => {
-                        let model = library.load_model(model, data);
-                        library.asset_library.insert(asset::Asset::from(asset.clone()), Box::new(model));
-                    },
-                    asset::Unit::Text(_text) => todo!(),
-                    asset::Unit::Shader(_shader) => todo!(),
-                    asset::Unit::Texture(texture) => {
-                        let texture = library.load_texture(asset, texture, data);
-                        library.asset_library.insert(asset::Asset::from(asset.clone()), Box::new(texture));
-                    },
+        let mut loaded = Vec::new();
+        for name in self.dependencies.bundle_order() {
+            if let Some(idx) = decoded.iter().position(|(_, decoded)| decoded.bundle.name == name) {
+                let (handle, next) = decoded.swap_remove(idx);
+                loaded.extend(self.upload_bundle(next));
+                let request = &mut self.requests[handle.0];
+                request.status = stream::Status::Ready;
+                if request.retain {
+                    *self.bundle_references.entry(name).or_default() += 1;
                 }
             }
+        }
+        loaded
+    }
+
+    /// Replaces an asset already loaded in its slot, so existing views see the new data
+    fn store(&mut self, metadata: &asset::Metadata, resource: Box<dyn Asset>) {
+        self.touch(metadata);
+        // removed first so the key holds the new metadata
+        let slot = self.asset_library.remove(&asset::Asset::from(metadata.clone()))
+            .unwrap_or_else(|| Rc::new(Slot {
+                tag: RefCell::new(String::new()),
+                resource: RefCell::new(None)
+            }));
+        slot.tag.replace(metadata.tag.clone());
+        slot.resource.replace(Some(resource));
+        self.asset_library.insert(asset::Asset::from(metadata.clone()), slot);
+    }
 
-            library.bundle_library.insert(bundle);
+    /// Frees the asset now, rather than when the last view of it goes
+    fn unload(&mut self, metadata: &asset::Metadata) {
+        if let Some(slot) = self.asset_library.remove(&asset::Asset::from(metadata.clone())) {
+            slot.resource.replace(None);
         }
+    }
 
-        library
+    fn touch(&self, metadata: &asset::Metadata) {
+        let now = self.clock.get() + 1;
+        self.clock.set(now);
+        self.last_used.borrow_mut().insert(metadata.clone(), now);
+    }
+
+    /// Adds a reference to a loaded bundle, so it survives a matching `release_bundle`
+    pub fn retain_bundle(&mut self, name: impl Into<String>) -> bool {
+        let name = name.into();
+        if !self.bundle_library.contains(&bundle::Bundle::new(name.clone())) {
+            return false;
+        }
+        *self.bundle_references.entry(name).or_default() += 1;
+        true
+    }
+
+    /// Drops a reference taken by `load` or `retain_bundle`. At zero its assets are unloaded, apart from
+    /// any still viewed or depended on by another bundle. Returns the references left
+    pub fn release_bundle(&mut self, name: impl Into<String>) -> usize {
+        let name = name.into();
+        let remaining = match self.bundle_references.get_mut(&name) {
+            Some(references) => {
+                *references = references.saturating_sub(1);
+                *references
+            },
+            None => 0,
+        };
+        self.collect();
+        remaining
+    }
+
+    pub fn residency(&self) -> Residency {
+        self.residency
+    }
+
+    pub fn set_residency(&mut self, residency: Residency) {
+        self.residency = residency;
+        self.collect();
+    }
+
+    /// Every loaded asset and its estimated size
+    pub fn resident(&self) -> Vec<(asset::Metadata, Size)> {
+        self.asset_library.iter()
+            .map(|(asset, slot)| (asset.metadata(), slot.resident_size()))
+            .collect()
+    }
+
+    pub fn resident_size(&self) -> Size {
+        self.asset_library.values()
+            .fold(Size::default(), |total, slot| total + slot.resident_size())
+    }
+
+    /// How many `AssetView`s of the asset are alive
+    pub fn references(&self, metadata: &asset::Metadata) -> usize {
+        self.asset_library.get(&asset::Asset::from(metadata.clone()))
+            .map_or(0, |slot| Rc::strong_count(slot) - 1)
+    }
+
+    /// Assets in referenced bundles, assets with a live `AssetView`, and everything they depend on
+    fn in_use(&self) -> HashSet<asset::Metadata> {
+        let by_uuid: HashMap<_, &asset::Metadata> = self.bundle_library.iter()
+            .flat_map(|bundle| bundle.group.iter())
+            .map(|metadata| (metadata.uuid, metadata))
+            .collect();
+
+        let mut stack: Vec<asset::Metadata> = self.bundle_library.iter()
+            .filter(|bundle| self.bundle_references.get(&bundle.name).copied().unwrap_or(0) > 0)
+            .flat_map(|bundle| bundle.group.iter().cloned())
+            .collect();
+        stack.extend(self.asset_library.iter()
+            .filter(|(_, slot)| Rc::strong_count(slot) > 1)
+            .map(|(asset, _)| asset.metadata()));
+
+        let mut used = HashSet::new();
+        while let Some(metadata) = stack.pop() {
+            for uuid in self.dependencies.dependencies(&metadata) {
+                if let Some(dependency) = by_uuid.get(uuid) {
+                    if !used.contains(*dependency) {
+                        stack.push((*dependency).clone());
+                    }
+                }
+            }
+            used.insert(metadata);
+        }
+        used
+    }
+
+    /// Unloads assets nothing uses, following the residency policy, then any bundle left with nothing
+    /// loaded. Returns what was unloaded
+    pub fn collect(&mut self) -> Vec<asset::Metadata> {
+        let used = self.in_use();
+        let mut unused: Vec<asset::Metadata> = self.asset_library.keys()
+            .map(|asset| asset.metadata())
+            .filter(|metadata| !used.contains(metadata))
+            .collect();
+
+        let unloading = match self.residency {
+            Residency::Immediate => unused,
+            Residency::Lru(budget) => {
+                let last_used = self.last_used.borrow();
+                unused.sort_by_key(|metadata| last_used.get(metadata).copied().unwrap_or(0));
+                drop(last_used);
+
+                let mut size = self.resident_size();
+                let mut unloading = Vec::new();
+                for metadata in unused {
+                    if !budget.exceeded_by(size) {
+                        break;
+                    }
+                    if let Some(slot) = self.asset_library.get(&asset::Asset::from(metadata.clone())) {
+                        let freed = slot.resident_size();
+                        size.cpu = size.cpu.saturating_sub(freed.cpu);
+                        size.gpu = size.gpu.saturating_sub(freed.gpu);
+                    }
+                    unloading.push(metadata);
+                }
+                unloading
+            },
+        };
+
+        for metadata in unloading.iter() {
+            self.unload(metadata);
+            self.content_hashes.remove(metadata);
+            self.last_used.borrow_mut().remove(metadata);
+        }
+
+        let empty: Vec<String> = self.bundle_library.iter()
+            .filter(|bundle| self.bundle_references.get(&bundle.name).copied().unwrap_or(0) == 0)
+            .filter(|bundle| !bundle.group.iter().any(|metadata| {
+                self.asset_library.contains_key(&asset::Asset::from(metadata.clone()))
+            }))
+            .map(|bundle| bundle.name.clone())
+            .collect();
+        if !empty.is_empty() {
+            for name in empty {
+                if let Some(bundle) = self.bundle_library.take(&bundle::Bundle::new(name.clone())) {
+                    for asset in bundle.group.iter() {
+                        self.content_hashes.remove(asset);
+                    }
+                }
+                self.bundle_references.remove(&name);
+            }
+            self.dependencies = dependency::Graph::new(self.bundle_library.iter());
+        }
+        unloading
+    }
+
+    fn upload_bundle(&mut self, decoded: stream::DecodedBundle) -> Vec<asset::Metadata> {
+        let stream::DecodedBundle { bundle, mut assets, hashes } = decoded;
+        let loaded: Vec<asset::Metadata> = bundle.group.iter()
+            .filter(|asset| assets.contains_key(*asset))
+            .cloned()
+            .collect();
+        for asset in loaded.iter() {
+            // removed first so the key holds the new metadata
+            self.content_hashes.remove(asset);
+            if let Some(hash) = hashes.get(asset) {
+                self.content_hashes.insert(asset.clone(), *hash);
+            }
+        }
+        // stages sharing a tag are linked into one program, found through the first stage's tag
+        let mut programs: Parts<avalon_asset::shader::Shader> = Vec::new();
+        // likewise every language of a text asset shares its tag
+        let mut texts: Parts<text::Text> = Vec::new();
+        let order: HashMap<_, _> = self.dependencies.load_order().into_iter()
+            .enumerate()
+            .map(|(idx, uuid)| (uuid, idx))
+            .collect();
+        let mut group: Vec<&asset::Metadata> = bundle.group.iter().collect();
+        group.sort_by_key(|asset| order.get(&asset.uuid).copied());
+        for asset in group {
+            let Some(data) = assets.remove(asset) else {
+                continue;
+            };
+            match data {
+                stream::Decoded::Model(packed) => {
+                    self.store(asset, Box::new(model::Model::from(packed)));
+                },
+                stream::Decoded::Config(config) => {
+                    match config {
+                        Ok(config) => self.store(asset, Box::new(config)),
+                        Err(e) => println!("Error loading config {}: {}", asset.tag, e),
+                    }
+                },
+                stream::Decoded::Text(text, data) => {
+                    match texts.iter_mut().find(|(first, _)| first.tag == asset.tag) {
+                        Some((_, languages)) => languages.push((text, data)),
+                        None => texts.push((bundle.asset(&asset.tag).unwrap(), vec![(text, data)])),
+                    }
+                },
+                stream::Decoded::Shader(shader, data) => {
+                    match programs.iter_mut().find(|(first, _)| first.tag == asset.tag) {
+                        Some((_, stages)) => stages.push((shader, data)),
+                        None => programs.push((bundle.asset(&asset.tag).unwrap(), vec![(shader, data)])),
+                    }
+                },
+                stream::Decoded::Texture(texture, image, dimensions) => {
+                    let texture = self.upload_texture(asset, texture, image, dimensions);
+                    self.store(asset, Box::new(texture));
+                },
+                stream::Decoded::PackedTexture(texture, packed) => {
+                    let texture = self.upload_packed_texture(asset, texture, &packed);
+                    self.store(asset, Box::new(texture));
+                },
+            }
+        }
+
+        for (first, languages) in texts {
+            let languages: Vec<_> = languages.iter().map(|(text, data)| (*text, data.as_slice())).collect();
+            match self.load_text(&languages) {
+                Ok(strings) => self.store(&first, Box::new(strings)),
+                Err(e) => println!("Error loading text {}: {}", first.tag, e),
+            }
+        }
+
+        for (first, stages) in programs {
+            let stages: Vec<_> = stages.iter().map(|(shader, data)| (*shader, data.as_slice())).collect();
+            match self.load_program(&first.tag, &stages) {
+                Ok(program) => self.store(&first, Box::new(program)),
+                Err(e) => println!("Error loading shader program {}: {}", first.tag, e),
+            }
+        }
+
+        // assets dropped from a rebuilt bundle go with it
+        if let Some(previous) = self.bundle_library.take(&bundle) {
+            for asset in previous.group {
+                if !bundle.group.contains(&asset) {
+                    self.content_hashes.remove(&asset);
+                    self.unload(&asset);
+                }
+            }
+        }
+
+        self.bundle_library.insert(bundle);
+        loaded
+    }
+}
+
+#[cfg(test)]
+mod library_tests {
+    use super::{ AssetView, Library };
+    use avalon_asset::{ asset, bundle, config };
+
+    fn library_with(table: &str) -> (Library, asset::Metadata) {
+        let mut library = Library::new(None);
+        let metadata = asset::Metadata::new("settings", "settings.toml", asset::Unit::Config(config::Config {}));
+        library.bundle_library.insert(bundle::Bundle {
+            name: "core".to_string(),
+            group: vec![metadata.clone()],
+            dependencies: Vec::new()
+        });
+        library.store(&metadata, Box::new(table.parse::<config::Table>().unwrap()));
+        (library, metadata)
+    }
+
+    #[test]
+    fn test_views_keep_assets_loaded() {
+        let (mut library, metadata) = library_with("speed = 1.0");
+        let view: AssetView<config::Table> = library.bundle("core").unwrap().tag("settings").unwrap();
+        assert_eq!(library.references(&metadata), 1);
+        assert!(library.collect().is_empty());
+        assert_eq!(view.get().unwrap().number("speed"), Some(1.0));
+
+        let copy = view.clone();
+        drop(view);
+        assert!(library.collect().is_empty());
+        drop(copy);
+        assert_eq!(library.references(&metadata), 0);
+        assert_eq!(library.collect(), vec![metadata]);
+        assert!(library.bundle("core").is_none());
+    }
+
+    #[test]
+    fn test_views_see_reloads() {
+        let (mut library, metadata) = library_with("speed = 1.0");
+        let view: AssetView<config::Table> = library.bundle("core").unwrap().tag("settings").unwrap();
+
+        library.store(&metadata, Box::new("speed = 2.0".parse::<config::Table>().unwrap()));
+        assert_eq!(view.get().unwrap().number("speed"), Some(2.0));
+        assert_eq!(library.references(&metadata), 1);
+
+        library.unload(&metadata);
+        assert_eq!(view.get().unwrap_err(), super::error::View::NotLoaded("settings".to_string()));
     }
 }
@@ -0,0 +1,15 @@
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum View {
+    #[error("Bundle does not have an asset tagged \"{0}\"")]
+    NoAssetWithTag(String),
+    #[error("Asset \"{0}\" is in the bundle but has not been loaded")]
+    NotLoaded(String),
+    #[error("Asset \"{tag}\" is a {found}, not a {expected}")]
+    WrongType {
+        tag: String,
+        expected: &'static str,
+        found: &'static str,
+    },
+}
@@ -4,28 +4,47 @@ use crate::texture;
 use crate::texture::data;
 use crate::texture::gpu::{ self, UniqueTexture, Arguments2d, Texture2d, ManagedTexture };
 use crate::model;
+use crate::shader::{ self, Source };
 
 impl Library {
+    /// Bundles packed before textures were processed hold the source image, which is decoded here instead
     pub fn load_texture(&self, metadata: &assets::asset::Metadata, texture_info: assets::texture::Texture, data: &[u8]) -> ManagedTexture<Texture2d> {
-        let (image, dimensions) = data::Data::from_buffer(data.to_vec());
-        let (components, size) = match texture_info.colour_space {
-            assets::texture::ColourSpace::RGBA => (
-                texture::Component::RGBA,
-                gpu::SizedComponent::RGBA8,
-            ),
-            assets::texture::ColourSpace::SRGBA => (
-                texture::Component::RGBA,
-                gpu::SizedComponent::SRGB8A8,
-            ),
-            assets::texture::ColourSpace::RGB => (
-                texture::Component::RGB,
-                gpu::SizedComponent::RGB8,
-            ),
-            assets::texture::ColourSpace::SRGB => (
-                texture::Component::RGB,
-                gpu::SizedComponent::SRGB8,
-            ),
-        };
+        if let Some(packed) = assets::texture::packed::PackedTexture::from_buffer(data) {
+            return self.upload_packed_texture(metadata, texture_info, &packed);
+        }
+        let (image, dimensions) = data::Data::from_buffer(data);
+        self.upload_texture(metadata, texture_info, image, dimensions)
+    }
+
+    /// Uploads the mip chain as packed, compressed or not
+    pub fn upload_packed_texture(
+        &self,
+        metadata: &assets::asset::Metadata,
+        texture_info: assets::texture::Texture,
+        packed: &assets::texture::packed::PackedTexture
+    ) -> ManagedTexture<Texture2d> {
+        let (components, size) = packed_format(packed.compression, texture_info.colour_space);
+
+        let levels: Vec<&[u8]> = packed.levels.iter().map(Vec::as_slice).collect();
+        let mut texture = Texture2d::generate_with_levels(
+            nalgebra_glm::IVec2::new(packed.width as i32, packed.height as i32),
+            components,
+            size,
+            &levels
+        );
+        texture.bind().set_name(metadata.tag.clone());
+        texture.as_managed()
+    }
+
+    /// The GL half of `load_texture`, for images already decoded
+    pub fn upload_texture(
+        &self,
+        metadata: &assets::asset::Metadata,
+        texture_info: assets::texture::Texture,
+        image: data::Data,
+        dimensions: nalgebra_glm::IVec2
+    ) -> ManagedTexture<Texture2d> {
+        let (components, size) = colour_space_format(texture_info.colour_space);
 
         let arguments = Arguments2d {
             dimensions,
@@ -40,8 +59,130 @@ impl Library {
         texture.as_managed()
     }
 
+    /// Compiles and links the stages sharing a tag. Either a single compute stage, or a vertex and a fragment stage
+    pub fn load_program(&self, tag: &str, stages: &[(assets::shader::Shader, &[u8])]) -> Result<shader::Program, shader::error::Creation> {
+        let invalid = |reason| shader::error::Creation::InvalidStages { id: tag.to_string(), reason };
+        let named = |e| match e {
+            shader::error::Creation::FailedToCompile { reason, .. } => shader::error::Creation::FailedToCompile {
+                id: tag.to_string(),
+                reason
+            },
+            _ => e
+        };
+        let source = |data: &[u8]| String::from_utf8(data.to_vec()).map_err(shader::error::Creation::FileParseError);
+        let single = |is_stage: fn(&assets::shader::Stage) -> bool| {
+            let mut matching = stages.iter().filter(|(info, _)| is_stage(&info.stage));
+            match (matching.next(), matching.next()) {
+                (Some((_, data)), None) => Ok(Some(*data)),
+                (None, _) => Ok(None),
+                (Some(_), Some(_)) => Err(invalid("a stage is declared more than once")),
+            }
+        };
+
+        let compute = single(|stage| matches!(stage, assets::shader::Stage::Compute))?;
+        let vertex = single(|stage| matches!(stage, assets::shader::Stage::Vertex))?;
+        let fragment = single(|stage| matches!(stage, assets::shader::Stage::Fragment))?;
+        let program = match (compute, vertex, fragment) {
+            (Some(compute), None, None) => shader::Program::new()
+                .compute(shader::Compute::load_from_source(source(compute)?).map_err(named)?),
+            (None, Some(vertex), Some(fragment)) => shader::Program::new()
+                .vertex(shader::Vertex::load_from_source(source(vertex)?).map_err(named)?)
+                .fragment(shader::Fragment::load_from_source(source(fragment)?).map_err(named)?),
+            (Some(_), _, _) => return Err(invalid("compute stages cannot be linked with other stages")),
+            (None, _, _) => return Err(invalid("needs both a vertex and a fragment stage")),
+        };
+        program.build()
+    }
+
+    /// Merges each language's table. English is the fallback when present, otherwise the first language
+    pub fn load_text(&self, languages: &[(assets::text::Text, &[u8])]) -> Result<assets::text::Strings, assets::error::TextError> {
+        let default = languages.iter()
+            .map(|(info, _)| info.language)
+            .find(|language| *language == assets::text::Language::English)
+            .or_else(|| languages.first().map(|(info, _)| info.language))
+            .unwrap_or(assets::text::Language::English);
+
+        let mut strings = assets::text::Strings::new(default);
+        for (info, data) in languages {
+            strings.insert(info.language, assets::text::StringTable::from_bytes(data)?);
+        }
+        Ok(strings)
+    }
+
+    pub fn load_config(&self, _config_info: assets::config::Config, data: &[u8]) -> Result<assets::config::Table, assets::error::ConfigError> {
+        assets::config::Table::from_bytes(data)
+    }
+
     pub fn load_model(&self, _model_info: assets::model::Model, data: &[u8]) -> model::Model {
         let packed = assets::model::packed::PackedModel::from_buffer(data);
         model::Model::from(packed)
     }
 }
+
+/// Uncompressed levels are always stored as RGBA8, whatever the colour space, so RGB textures gain an opaque alpha
+fn packed_format(
+    compression: Option<assets::texture::Compression>,
+    colour_space: assets::texture::ColourSpace
+) -> (texture::Component, gpu::SizedComponent) {
+    use assets::texture::Compression;
+    match (compression, colour_space.is_srgb()) {
+        (None, false) => (texture::Component::RGBA, gpu::SizedComponent::RGBA8),
+        (None, true) => (texture::Component::RGBA, gpu::SizedComponent::SRGB8A8),
+        (Some(Compression::BC1), false) => (texture::Component::RGBA, gpu::SizedComponent::Bc1RGBA),
+        (Some(Compression::BC1), true) => (texture::Component::RGBA, gpu::SizedComponent::Bc1SRGBA),
+        (Some(Compression::BC3), false) => (texture::Component::RGBA, gpu::SizedComponent::Bc3RGBA),
+        (Some(Compression::BC3), true) => (texture::Component::RGBA, gpu::SizedComponent::Bc3SRGBA),
+        (Some(Compression::BC5), _) => (texture::Component::RG, gpu::SizedComponent::Bc5RG),
+        (Some(Compression::BC7), false) => (texture::Component::RGBA, gpu::SizedComponent::Bc7RGBA),
+        (Some(Compression::BC7), true) => (texture::Component::RGBA, gpu::SizedComponent::Bc7SRGBA),
+    }
+}
+
+fn colour_space_format(colour_space: assets::texture::ColourSpace) -> (texture::Component, gpu::SizedComponent) {
+    match colour_space {
+        assets::texture::ColourSpace::RGBA => (
+            texture::Component::RGBA,
+            gpu::SizedComponent::RGBA8,
+        ),
+        assets::texture::ColourSpace::SRGBA => (
+            texture::Component::RGBA,
+            gpu::SizedComponent::SRGB8A8,
+        ),
+        assets::texture::ColourSpace::RGB => (
+            texture::Component::RGB,
+            gpu::SizedComponent::RGB8,
+        ),
+        assets::texture::ColourSpace::SRGB => (
+            texture::Component::RGB,
+            gpu::SizedComponent::SRGB8,
+        ),
+    }
+}
+
+#[cfg(test)]
+mod load_tests {
+    use super::packed_format;
+    use avalon_asset::texture::{ packed::PackedTexture, ColourSpace, Compression, Texture };
+
+    #[test]
+    fn test_packed_level_sizes() {
+        let (width, height) = (5, 3);
+        let rgba: Vec<u8> = (0..width * height * 4).map(|byte| byte as u8).collect();
+        let spaces = [ColourSpace::RGB, ColourSpace::SRGB, ColourSpace::RGBA, ColourSpace::SRGBA];
+        let compressions = [None, Some(Compression::BC1), Some(Compression::BC3), Some(Compression::BC5), Some(Compression::BC7)];
+        for colour_space in spaces {
+            for compression in compressions {
+                let packed = PackedTexture::new(&rgba, width, height, Texture { colour_space, compression });
+                let (_, size) = packed_format(compression, colour_space);
+                for (level, data) in packed.levels.iter().enumerate() {
+                    let (level_width, level_height) = packed.level_dimensions(level);
+                    assert_eq!(
+                        data.len(),
+                        size.level_bytes(level_width as i32, level_height as i32),
+                        "{colour_space} {compression:?} level {level}"
+                    );
+                }
+            }
+        }
+    }
+}
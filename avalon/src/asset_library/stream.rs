@@ -0,0 +1,228 @@
+use avalon_asset as assets;
+use crate::texture::data;
+use std::borrow::Cow;
+use std::collections::{ HashMap, HashSet };
+use std::sync::{ mpsc, Arc, Mutex };
+use nalgebra_glm::IVec2;
+
+/// Refers to one bundle requested from the library
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Handle(pub(super) usize);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Status {
+    /// Still being read, or waiting on the GL thread
+    Pending,
+    Ready,
+    Failed(String),
+}
+
+/// Bundles requested since the library was last idle
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct Progress {
+    pub ready: usize,
+    pub failed: usize,
+    pub total: usize,
+}
+
+/// An asset after the CPU side of loading, waiting to be uploaded on the GL thread
+pub(super) enum Decoded {
+    Model(assets::model::packed::PackedModel),
+    Texture(assets::texture::Texture, data::Data, IVec2),
+    PackedTexture(assets::texture::Texture, assets::texture::packed::PackedTexture),
+    Config(Result<assets::config::Table, assets::error::ConfigError>),
+    Text(assets::text::Text, Vec<u8>),
+    Shader(assets::shader::Shader, Vec<u8>),
+}
+
+#[derive(Debug)]
+pub(super) struct DecodedBundle {
+    pub bundle: assets::bundle::Bundle,
+    /// Only what changed since the bundle was last uploaded
+    pub assets: HashMap<assets::asset::Metadata, Decoded>,
+    /// Content hash of every asset, when the bundle lists them
+    pub hashes: HashMap<assets::asset::Metadata, u64>,
+}
+
+struct Job {
+    handle: Handle,
+    path: std::path::PathBuf,
+    /// Content hashes of the bundle's assets already uploaded
+    resident: HashMap<assets::asset::Metadata, u64>,
+}
+
+/// Worker threads reading and decompressing bundles
+pub(super) struct Loader {
+    jobs: Option<mpsc::Sender<Job>>,
+    results: mpsc::Receiver<(Handle, Result<DecodedBundle, String>)>,
+    workers: Vec<std::thread::JoinHandle<()>>,
+}
+
+impl Status {
+    pub fn is_pending(&self) -> bool {
+        matches!(self, Status::Pending)
+    }
+
+    pub fn is_ready(&self) -> bool {
+        matches!(self, Status::Ready)
+    }
+
+    pub fn is_failed(&self) -> bool {
+        matches!(self, Status::Failed(_))
+    }
+}
+
+impl Progress {
+    /// From 0 to 1, counting failures as finished
+    pub fn fraction(&self) -> f32 {
+        if self.total == 0 {
+            return 1.0;
+        }
+        (self.ready + self.failed) as f32 / self.total as f32
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.ready + self.failed == self.total
+    }
+}
+
+fn decode_asset(metadata: &assets::asset::Metadata, data: Cow<[u8]>) -> Decoded {
+    match metadata.unit {
+        assets::asset::Unit::Model(_) => Decoded::Model(assets::model::packed::PackedModel::from_buffer(&data)),
+        assets::asset::Unit::Texture(texture) => match assets::texture::packed::PackedTexture::from_buffer(&data) {
+            Some(packed) => Decoded::PackedTexture(texture, packed),
+            // bundles packed before textures were processed hold the source image
+            None => {
+                let (image, dimensions) = data::Data::from_buffer(&data);
+                Decoded::Texture(texture, image, dimensions)
+            },
+        },
+        assets::asset::Unit::Config(_) => Decoded::Config(assets::config::Table::from_bytes(&data)),
+        assets::asset::Unit::Text(text) => Decoded::Text(text, data.into_owned()),
+        assets::asset::Unit::Shader(shader) => Decoded::Shader(shader, data.into_owned()),
+    }
+}
+
+/// Reads one asset at a time, skipping any already uploaded with the same metadata and content
+fn decode(path: &std::path::Path, resident: &HashMap<assets::asset::Metadata, u64>) -> Result<DecodedBundle, String> {
+    let mut reader = assets::packed::read::Reader::open(path).map_err(|e| e.to_string())?;
+    let group = reader.bundle().group.clone();
+    let hashes: HashMap<_, _> = group.iter()
+        .filter_map(|metadata| Some((metadata.clone(), reader.hash(&metadata.uuid)?)))
+        .collect();
+
+    let unchanged = |metadata: &assets::asset::Metadata| {
+        resident.get_key_value(metadata)
+            .is_some_and(|(previous, hash)| previous.unit == metadata.unit && hashes.get(metadata) == Some(hash))
+    };
+    // stages and languages sharing a tag are uploaded together, so are only skipped together
+    let skipped: HashSet<&assets::asset::Metadata> = group.iter()
+        .filter(|metadata| match metadata.unit {
+            assets::asset::Unit::Shader(_) | assets::asset::Unit::Text(_) => group.iter()
+                .filter(|other| other.tag == metadata.tag)
+                .filter(|other| assets::asset::Type::from(other.unit) == assets::asset::Type::from(metadata.unit))
+                .all(unchanged),
+            _ => unchanged(metadata),
+        })
+        .collect();
+
+    let mut assets = HashMap::new();
+    for metadata in group.iter().filter(|metadata| !skipped.contains(metadata)) {
+        let data = reader.read(&metadata.uuid).map_err(|e| e.to_string())?;
+        // decoders panic on malformed data, which should fail the bundle rather than the worker
+        let decoded = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| decode_asset(metadata, data)))
+            .map_err(|_| format!("Malformed data for {} in {}", metadata.tag, path.display()))?;
+        assets.insert(metadata.clone(), decoded);
+    }
+
+    Ok(DecodedBundle {
+        bundle: reader.into_bundle(),
+        assets,
+        hashes,
+    })
+}
+
+impl Loader {
+    pub fn new() -> Loader {
+        let (jobs, job_receiver) = mpsc::channel::<Job>();
+        let (result_sender, results) = mpsc::channel();
+        let job_receiver = Arc::new(Mutex::new(job_receiver));
+        let count = std::thread::available_parallelism()
+            .map(|count| count.get())
+            .unwrap_or(2)
+            .min(4);
+
+        let workers = (0..count)
+            .map(|idx| {
+                let job_receiver = job_receiver.clone();
+                let result_sender = result_sender.clone();
+                std::thread::Builder::new()
+                    .name(format!("asset loader {idx}"))
+                    .spawn(move || loop {
+                        let job = job_receiver.lock().unwrap().recv();
+                        let Ok(job) = job else {
+                            return;
+                        };
+                        if result_sender.send((job.handle, decode(&job.path, &job.resident))).is_err() {
+                            return;
+                        }
+                    })
+                    .expect("asset loader thread should spawn")
+            })
+            .collect();
+
+        Loader {
+            jobs: Some(jobs),
+            results,
+            workers,
+        }
+    }
+
+    pub fn queue(&self, handle: Handle, path: std::path::PathBuf, resident: HashMap<assets::asset::Metadata, u64>) {
+        if let Some(jobs) = self.jobs.as_ref() {
+            jobs.send(Job { handle, path, resident }).expect("asset loader threads should be running");
+        }
+    }
+
+    pub fn finished(&self) -> impl Iterator<Item = (Handle, Result<DecodedBundle, String>)> + '_ {
+        self.results.try_iter()
+    }
+
+    /// Blocks until the next bundle is decoded
+    pub fn wait(&self) -> Option<(Handle, Result<DecodedBundle, String>)> {
+        self.results.recv().ok()
+    }
+}
+
+impl Drop for Loader {
+    fn drop(&mut self) {
+        // closing the queue stops each worker once its current bundle is done
+        self.jobs.take();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// Names the kind of asset, leaving out the decoded data
+impl std::fmt::Debug for Decoded {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let kind = match self {
+            Decoded::Model(_) => "Model",
+            Decoded::Texture(..) => "Texture",
+            Decoded::PackedTexture(..) => "PackedTexture",
+            Decoded::Config(_) => "Config",
+            Decoded::Text(..) => "Text",
+            Decoded::Shader(..) => "Shader",
+        };
+        fmt.debug_tuple(kind).finish_non_exhaustive()
+    }
+}
+
+impl std::fmt::Debug for Loader {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        fmt.debug_struct("Loader")
+            .field("workers", &self.workers.len())
+            .finish_non_exhaustive()
+    }
+}
@@ -5,7 +5,8 @@ use crate::shader;
 #[derive(Debug, Copy, Clone)]
 pub enum Kind {
     None,
-    Indexed { index_count: usize },
+    /// Drawn from `first_index` in the bound element buffer
+    Indexed { index_count: usize, first_index: usize },
     Counted { vertex_count: usize }
 }
 
@@ -82,12 +83,12 @@ impl StateBind<'_> {
                     vertex_count as i32
                 );
             },
-            Kind::Indexed { index_count } => unsafe {
+            Kind::Indexed { index_count, first_index } => unsafe {
                 gl::DrawElements(
                     gl::TRIANGLES,
                     index_count as i32,
                     gl::UNSIGNED_INT,
-                    std::ptr::null(),
+                    (first_index * std::mem::size_of::<u32>()) as *const std::ffi::c_void,
                 );
             },
         }
@@ -104,12 +105,12 @@ impl StateBind<'_> {
                     instance_count as i32
                 );
             },
-            Kind::Indexed { index_count } => unsafe {
+            Kind::Indexed { index_count, first_index } => unsafe {
                 gl::DrawElementsInstanced(
                     gl::TRIANGLES,
                     index_count as i32,
                     gl::UNSIGNED_INT,
-                    std::ptr::null(),
+                    (first_index * std::mem::size_of::<u32>()) as *const std::ffi::c_void,
                     instance_count as i32,
                 );
             },
@@ -6,200 +6,1245 @@ use crate::event as engine_event;
 pub mod action;
 pub mod context;
 pub mod controller;
+pub mod device;
+pub mod error;
 pub mod event;
 pub mod keyboard;
+pub mod label;
 pub mod layer;
 pub mod mouse;
+pub mod output;
+pub mod player;
 
 pub type Id = u64;
 pub type Timestamp = u64;
 
 struct Controller {
-    device: sdl2::controller::GameController,
+    device: Option<Box<dyn output::Backend>>,
     controller: controller::Controller,
 }
 
 impl Controller {
-    fn new(device: sdl2::controller::GameController) -> Controller {
+    fn new(device: Option<Box<dyn output::Backend>>, settings: controller::Settings) -> Controller {
         Controller {
             device,
-            controller: controller::Controller::new()
+            controller: controller::Controller::with_settings(settings)
         }
     }
 }
 
 pub struct Engine {
-    event_channel: engine_event::Channel<sdl2::event::Event, ()>,
+    event_channel: Option<engine_event::Channel<sdl2::event::Event, ()>>,
+    virtual_devices: engine_event::Dispatcher<device::Input, ()>,
+    virtual_channel: engine_event::Channel<device::Input, ()>,
+    next_virtual_controller: Id,
     action_map: action::Map,
-    events: Vec<event::Event>,
-    controller_subsystem: sdl2::GameControllerSubsystem,
+    events: Vec<(player::Device, event::Event)>,
+    controller_subsystem: Option<sdl2::GameControllerSubsystem>,
+    /// Output devices for controllers that have been opened but not yet connected
+    pending_outputs: HashMap<Id, Box<dyn output::Backend>>,
     controllers: HashMap<Id, Controller>,
+    controller_settings: controller::Settings,
+    families: HashMap<Id, controller::Family>,
     mouse: mouse::Mouse,
     keyboard: keyboard::Keyboard,
     timestamp: Timestamp,
-    last_controller_timestamp: Timestamp,
-    last_kbm_timestamp: Timestamp,
-    layers: Vec<layer::Layer>,
-    pop_layer: bool
+    last_used: HashMap<player::Device, Timestamp>,
+    players: Vec<player::Player>,
+    next_player: player::Id,
+    hotplug: player::Hotplug,
+    awaiting_reconnect: Vec<player::Id>,
+    text: Vec<action::Action>,
+    text_input: Option<sdl2::keyboard::TextInputUtil>,
+    mouse_util: Option<sdl2::mouse::MouseUtil>,
 }
 
 impl Engine {
     pub fn new(engine: &mut engine::Engine, action_map: action::Map) -> Engine {
+        label::use_positional_buttons();
         let controller_subsystem = engine.sdl.game_controller().unwrap();
         controller_subsystem.set_event_state(true);
 
-        let mut controllers = HashMap::new();
+        let mut input = Engine::headless(action_map);
         for idx in 0..controller_subsystem.num_joysticks().unwrap() {
             if controller_subsystem.is_game_controller(idx) {
                 if let Ok(device) = controller_subsystem.open(idx) {
-                    controllers.insert(idx.into(), Controller::new(device));
+                    let id = device.instance_id();
+                    input.families.insert(id.into(), controller::Family::from_name(&device.name()));
+                    input.pending_outputs.insert(id.into(), Box::new(device));
+                    input.connect_controller(id.into());
+                    input.assign_device(player::PRIMARY, player::Device::Controller(id.into()));
                 }
             }
         }
 
+        input.event_channel = Some(engine.event_listener());
+        input.text_input = engine.sdl.video().ok().map(|video| video.text_input());
+        input.mouse_util = Some(engine.sdl.mouse());
+        input.controller_subsystem = Some(controller_subsystem);
+        input
+    }
+
+    /// An input engine with no window or SDL devices, driven entirely through virtual devices
+    pub fn headless(action_map: action::Map) -> Engine {
+        let mut primary = player::Player::new(player::PRIMARY);
+        primary.devices.insert(player::Device::KeyboardMouse);
+
+        let mut virtual_devices = engine_event::Dispatcher::new();
+        let virtual_channel = virtual_devices.receiver();
+
         Engine {
-            event_channel: engine.event_listener(),
+            event_channel: None,
+            virtual_devices,
+            virtual_channel,
+            next_virtual_controller: device::VIRTUAL_CONTROLLER_BASE,
             action_map,
-            controller_subsystem,
+            controller_subsystem: None,
+            pending_outputs: HashMap::new(),
             events: Vec::new(),
-            controllers,
+            controllers: HashMap::new(),
+            controller_settings: controller::Settings::default(),
+            families: HashMap::new(),
             mouse: mouse::Mouse::new(),
             keyboard: keyboard::Keyboard::new(),
             timestamp: 0,
-            last_controller_timestamp: 0,
-            last_kbm_timestamp: 0,
-            layers: Vec::new(),
-            pop_layer: false,
+            last_used: HashMap::new(),
+            players: vec![primary],
+            next_player: player::PRIMARY + 1,
+            hotplug: player::Hotplug::Reassign,
+            awaiting_reconnect: Vec::new(),
+            text: Vec::new(),
+            text_input: None,
+            mouse_util: None,
         }
     }
 
-    fn controller_from_id(&mut self, id: u32) -> &mut Controller {
-        self.controllers.get_mut(&id.into()).unwrap()
+    pub fn virtual_keyboard(&mut self) -> device::VirtualKeyboard {
+        device::VirtualKeyboard::new(self.virtual_devices.producer())
     }
 
-    pub fn poll(&mut self) {
-        self.timestamp += 1;
-        self.mouse.direction = nalgebra_glm::IVec2::zeros();
-        self.mouse.scroll = 0.0;
-        while let Some(event) = self.event_channel.pop() {
-            match event.id {
-                sdl2::event::Event::KeyDown { keycode, scancode, .. } => {
-                    if let Some(keycode) = keycode {
-                        self.events.push(self.keyboard.press_keycode(keycode).into());
-                    }
-                    if let Some(scancode) = scancode {
-                        self.events.push(self.keyboard.press_scancode(scancode).into());
-                    }
-                    self.last_kbm_timestamp = self.timestamp;
-                },
-                sdl2::event::Event::KeyUp { keycode, scancode, .. } => {
-                    if let Some(keycode) = keycode {
-                        self.events.push(self.keyboard.release_keycode(keycode).into());
-                    }
-                    if let Some(scancode) = scancode {
-                        self.events.push(self.keyboard.release_scancode(scancode).into());
-                    }
-                },
-                sdl2::event::Event::MouseWheel { direction, precise_y, .. } => {
-                    self.events.push(self.mouse.scroll(precise_y, direction).into());
-                },
-                sdl2::event::Event::MouseMotion { x, y, xrel, yrel, .. } => {
-                    self.events.push(self.mouse.motion(x, y, xrel, yrel).into());
-                },
-                sdl2::event::Event::MouseButtonDown { mouse_btn, .. } => {
-                    self.events.push(self.mouse.press(mouse_btn).into());
-                    self.last_kbm_timestamp = self.timestamp;
-                },
-                sdl2::event::Event::MouseButtonUp { mouse_btn, .. } => {
-                    self.events.push(self.mouse.release(mouse_btn).into());
-                },
-                sdl2::event::Event::ControllerButtonDown { which, button, .. } => {
-                    let event = self.controller_from_id(which).controller.press(button);
-                    self.events.push(event.into());
-                    self.last_controller_timestamp = self.timestamp;
-                },
-                sdl2::event::Event::ControllerButtonUp { which, button, .. } => {
-                    let event = self.controller_from_id(which).controller.release(button);
-                    self.events.push(event.into());
-                },
-                sdl2::event::Event::ControllerAxisMotion { which, axis, value, .. } => {
-                    let event = self.controller_from_id(which).controller.axis(axis, value);
-                    self.events.push(event.into());
-                    self.last_controller_timestamp = self.timestamp;
-                },
-                sdl2::event::Event::ControllerDeviceAdded { which, .. } => {
-                    if self.controller_subsystem.is_game_controller(which) {
-                        if let Ok(device) = self.controller_subsystem.open(which) {
-                            self.controllers.insert(which.into(), Controller::new(device));
-                        }
-                    }
-                },
-                sdl2::event::Event::ControllerDeviceRemoved { which, .. } => {
-                    self.controllers.remove(&which.into());
-                },
-                _ => {}
+    pub fn virtual_mouse(&mut self) -> device::VirtualMouse {
+        device::VirtualMouse::new(self.virtual_devices.producer())
+    }
+
+    /// The gamepad connects on the next poll and is assigned like any hot-plugged controller
+    pub fn virtual_gamepad(&mut self) -> device::VirtualGamepad {
+        let id = self.next_virtual_controller;
+        self.next_virtual_controller += 1;
+        device::VirtualGamepad::new(self.virtual_devices.producer(), id)
+    }
+
+    /// A virtual gamepad whose rumble and LED commands are sent to `backend`
+    pub fn virtual_gamepad_with_output(&mut self, backend: impl output::Backend + 'static) -> device::VirtualGamepad {
+        let gamepad = self.virtual_gamepad();
+        self.pending_outputs.insert(gamepad.id(), Box::new(backend));
+        gamepad
+    }
+
+    fn output(&mut self, id: Id) -> Result<&mut dyn output::Backend, error::Output> {
+        let controller = self.controllers.get_mut(&id).ok_or(error::Output::NoController(id))?;
+        match controller.device.as_mut() {
+            Some(device) => Ok(device.as_mut()),
+            None => Err(error::Output::NoDevice(id)),
+        }
+    }
+
+    pub fn rumble(&mut self, controller: Id, rumble: output::Rumble) -> Result<(), error::Output> {
+        self.output(controller)?.rumble(rumble)
+    }
+
+    pub fn rumble_triggers(&mut self, controller: Id, rumble: output::TriggerRumble) -> Result<(), error::Output> {
+        self.output(controller)?.rumble_triggers(rumble)
+    }
+
+    pub fn set_led(&mut self, controller: Id, colour: output::Colour) -> Result<(), error::Output> {
+        self.output(controller)?.set_led(colour)
+    }
+
+    /// Rumbles every controller the player owns, returning the first failure
+    pub fn rumble_player(&mut self, player: player::Id, rumble: output::Rumble) -> Result<(), error::Output> {
+        let controllers: Vec<Id> = self.player(player)
+            .map(|player| player.devices().filter_map(|device| match device {
+                player::Device::Controller(id) => Some(*id),
+                player::Device::KeyboardMouse => None,
+            }).collect())
+            .unwrap_or_default();
+
+        let mut result = Ok(());
+        for controller in controllers {
+            let rumbled = self.rumble(controller, rumble);
+            if result.is_ok() {
+                result = rumbled;
             }
         }
+        result
     }
 
-    pub fn dispatch(&mut self) {
-        if self.pop_layer {
-            self.layers.pop();
-            self.pop_layer = false;
+    pub fn controller_settings(&self) -> controller::Settings {
+        self.controller_settings
+    }
+
+    /// Applies dead zones, curves and thresholds to every connected controller and any connected later
+    pub fn set_controller_settings(&mut self, settings: controller::Settings) {
+        self.controller_settings = settings;
+        for controller in self.controllers.values_mut() {
+            controller.controller.set_settings(settings);
+        }
+    }
+
+    pub fn controller_family(&self, controller: Id) -> controller::Family {
+        self.families.get(&controller).copied().unwrap_or_default()
+    }
+
+    /// Overrides the detected family, for virtual controllers or a player's own preference
+    pub fn set_controller_family(&mut self, controller: Id, family: controller::Family) {
+        self.families.insert(controller, family);
+    }
+
+    /// Labels for each way the player can trigger the action with the device they last used.
+    /// Read from the player's current bindings, so they follow any rebinding
+    pub fn labels(&self, player: player::Id, action: &str) -> Vec<Vec<label::Label>> {
+        let Some(player) = self.player(player) else {
+            return Vec::new();
+        };
+        let action_map = player.active_layer()
+            .and_then(|layer| layer.action_map.as_ref())
+            .unwrap_or(&self.action_map);
+
+        let uses_controller = player.has_controller()
+            && (!player.owns(player::Device::KeyboardMouse) || player.prefers_controller(&self.last_used));
+        let family = if uses_controller {
+            player.devices()
+                .filter_map(|device| match device {
+                    player::Device::Controller(id) => Some(*id),
+                    player::Device::KeyboardMouse => None,
+                })
+                .max_by_key(|id| self.last_used.get(&player::Device::Controller(*id)).copied().unwrap_or(0))
+                .map(|id| self.controller_family(id))
+                .unwrap_or_default()
+        } else {
+            controller::Family::Generic
+        };
+
+        action_map.mappings(action)
+            .filter(|mapping| mapping.bindings().iter().all(|binding| binding.is_controller() == uses_controller))
+            .map(|mapping| label::mapping(mapping, family))
+            .collect()
+    }
+
+    fn touch(&mut self, device: player::Device) {
+        self.last_used.insert(device, self.timestamp);
+    }
+
+    fn connect_controller(&mut self, id: Id) {
+        let device = self.pending_outputs.remove(&id);
+        self.controllers.insert(id, Controller::new(device, self.controller_settings));
+
+        let device = player::Device::Controller(id);
+        if self.owner_of(device).is_some() {
+            return;
         }
 
-        for (_, controller) in self.controllers.iter() {
-            self.events.extend(controller.controller.held().iter().copied().map(Into::<event::Event>::into));
+        if let player::Hotplug::Manual = self.hotplug {
+            return;
         }
-        self.events.extend(self.keyboard.held().iter().copied().map(Into::<event::Event>::into));
-        self.events.extend(self.mouse.held().iter().copied().map(Into::<event::Event>::into));
 
-        if self.last_controller_timestamp > self.last_kbm_timestamp {
-            // use controller
-            self.events.retain(|e| matches!(e, event::Event::Controller(_)));
+        let reconnecting = if self.awaiting_reconnect.is_empty() {
+            None
         } else {
-            // use keyboard
-            self.events.retain(|e| !matches!(e, event::Event::Controller(_)));
+            Some(self.awaiting_reconnect.remove(0))
+        };
+        let target = reconnecting.or_else(|| {
+            self.players.iter()
+                .find(|player| !player.has_controller())
+                .map(|player| player.id)
+        });
+
+        if let Some(target) = target {
+            self.assign_device(target, device);
         }
+    }
 
-        let events: HashSet<event::Event> = HashSet::from_iter(self.events.iter().copied());
+    fn disconnect_controller(&mut self, id: Id) {
+        let device = player::Device::Controller(id);
+        self.controllers.remove(&id);
+        self.last_used.remove(&device);
+        if let Some(owner) = self.owner_of(device) {
+            self.unassign_device(device);
+            if !self.awaiting_reconnect.contains(&owner) {
+                self.awaiting_reconnect.push(owner);
+            }
+        }
+    }
 
-        let mut actions: Vec<(action::Action, Vec<&event::Event>)> = Vec::new();
-        for action in self.action_map.mappings.iter() {
-            if !action.required_events.is_empty() && action.required_events.is_subset(&events) {
-                let mut triggered_events: Vec<&event::Event> = Vec::new();
-                for required in action.required_events.iter() {
-                    if events.contains(required) {
-                        triggered_events.push(events.get(required).unwrap());
+    fn translate(&mut self, event: sdl2::event::Event) -> Vec<device::Input> {
+        match event {
+            sdl2::event::Event::KeyDown { keycode, scancode, .. } => {
+                keycode.and_then(keyboard::Scancode::from_keycode).into_iter()
+                    .chain(scancode)
+                    .map(device::Input::KeyPress)
+                    .collect()
+            },
+            sdl2::event::Event::KeyUp { keycode, scancode, .. } => {
+                keycode.and_then(keyboard::Scancode::from_keycode).into_iter()
+                    .chain(scancode)
+                    .map(device::Input::KeyRelease)
+                    .collect()
+            },
+            sdl2::event::Event::MouseWheel { direction, precise_y, .. } => {
+                vec![device::Input::MouseScroll(precise_y * mouse::wheel_scale(direction))]
+            },
+            sdl2::event::Event::MouseMotion { x, y, xrel, yrel, .. } => {
+                vec![device::Input::MouseMotion {
+                    position: nalgebra_glm::vec2(x, y),
+                    delta: nalgebra_glm::vec2(xrel, yrel)
+                }]
+            },
+            sdl2::event::Event::MouseButtonDown { mouse_btn, .. } => {
+                vec![device::Input::MousePress(mouse_btn.into())]
+            },
+            sdl2::event::Event::TextInput { text, .. } => {
+                vec![device::Input::Text(text)]
+            },
+            sdl2::event::Event::TextEditing { text, start, length, .. } => {
+                vec![device::Input::Composition { text, start, length }]
+            },
+            sdl2::event::Event::MouseButtonUp { mouse_btn, .. } => {
+                vec![device::Input::MouseRelease(mouse_btn.into())]
+            },
+            sdl2::event::Event::ControllerButtonDown { which, button, .. } => {
+                vec![device::Input::ControllerPress(which.into(), button.into())]
+            },
+            sdl2::event::Event::ControllerButtonUp { which, button, .. } => {
+                vec![device::Input::ControllerRelease(which.into(), button.into())]
+            },
+            sdl2::event::Event::ControllerAxisMotion { which, axis, value, .. } => {
+                let amount = (value as f32) / (i16::MAX as f32);
+                vec![device::Input::ControllerAxis(which.into(), axis.into(), amount)]
+            },
+            sdl2::event::Event::ControllerDeviceAdded { which, .. } => {
+                let Some(subsystem) = self.controller_subsystem.as_ref() else {
+                    return Vec::new();
+                };
+                if subsystem.is_game_controller(which) {
+                    if let Ok(device) = subsystem.open(which) {
+                        let id = device.instance_id();
+                        self.families.insert(id.into(), controller::Family::from_name(&device.name()));
+                        self.pending_outputs.insert(id.into(), Box::new(device));
+                        return vec![device::Input::ControllerConnect(id.into())];
                     }
                 }
-                actions.push((action.into(), triggered_events));
+                Vec::new()
+            },
+            sdl2::event::Event::ControllerDeviceRemoved { which, .. } => {
+                vec![device::Input::ControllerDisconnect(which.into())]
+            },
+            _ => Vec::new()
+        }
+    }
+
+    fn process(&mut self, input: device::Input) {
+        let kbm = player::Device::KeyboardMouse;
+        match input {
+            device::Input::KeyPress(key) => {
+                self.events.push((kbm, self.keyboard.press_scancode(key).into()));
+                self.touch(kbm);
+            },
+            device::Input::KeyRelease(key) => {
+                self.events.push((kbm, self.keyboard.release_scancode(key).into()));
+            },
+            device::Input::MouseScroll(amount) => {
+                self.events.push((kbm, self.mouse.scroll(amount).into()));
+            },
+            device::Input::MouseMotion { position, delta } => {
+                self.events.push((kbm, self.mouse.motion(position.x, position.y, delta.x, delta.y).into()));
+            },
+            device::Input::MousePress(button) => {
+                self.events.push((kbm, self.mouse.press(button).into()));
+                self.touch(kbm);
+            },
+            device::Input::MouseRelease(button) => {
+                self.events.push((kbm, self.mouse.release(button).into()));
+            },
+            device::Input::Text(text) => {
+                self.text.push(action::Action {
+                    name: action::TEXT_INPUT.into(),
+                    value: action::Value::Text(text)
+                });
+            },
+            device::Input::Composition { text, start, length } => {
+                self.text.push(action::Action {
+                    name: action::TEXT_COMPOSITION.into(),
+                    value: action::Value::Composition { text, start, length }
+                });
+            },
+            device::Input::ControllerPress(id, button) => {
+                if let Some(controller) = self.controllers.get_mut(&id) {
+                    let event = controller.controller.press(button);
+                    self.events.push((player::Device::Controller(id), event.into()));
+                    self.touch(player::Device::Controller(id));
+                }
+            },
+            device::Input::ControllerRelease(id, button) => {
+                if let Some(controller) = self.controllers.get_mut(&id) {
+                    let event = controller.controller.release(button);
+                    self.events.push((player::Device::Controller(id), event.into()));
+                }
+            },
+            device::Input::ControllerAxis(id, axis, amount) => {
+                if let Some(controller) = self.controllers.get_mut(&id) {
+                    let event = controller.controller.axis(axis, amount);
+                    self.events.push((player::Device::Controller(id), event.into()));
+                    self.touch(player::Device::Controller(id));
+                }
+            },
+            device::Input::ControllerConnect(id) => self.connect_controller(id),
+            device::Input::ControllerDisconnect(id) => self.disconnect_controller(id),
+        }
+    }
+
+    pub fn mouse_settings(&self) -> mouse::Settings {
+        self.mouse.settings
+    }
+
+    pub fn set_mouse_settings(&mut self, settings: mouse::Settings) {
+        self.mouse.settings = settings;
+    }
+
+    /// Hides the cursor and reports unbounded relative motion, for mouse look
+    pub fn set_relative_mouse(&mut self, relative: bool) {
+        if let Some(mouse) = self.mouse_util.as_ref() {
+            mouse.set_relative_mouse_mode(relative);
+        }
+    }
+
+    pub fn relative_mouse(&self) -> bool {
+        self.mouse_util.as_ref().is_some_and(|mouse| mouse.relative_mouse_mode())
+    }
+
+    /// Keeps receiving mouse events while buttons are held outside the window
+    pub fn capture_mouse(&mut self, capture: bool) {
+        if let Some(mouse) = self.mouse_util.as_ref() {
+            mouse.capture(capture);
+        }
+    }
+
+    pub fn poll(&mut self) {
+        self.timestamp += 1;
+        self.mouse.reset_motion();
+
+        let mut inputs = Vec::new();
+        while let Some(event) = self.event_channel.as_mut().and_then(|channel| channel.pop()) {
+            inputs.extend(self.translate(event.id));
+        }
+
+        self.virtual_devices.tick();
+        while let Some(event) = self.virtual_channel.pop() {
+            inputs.push(event.id);
+        }
+
+        for input in inputs {
+            self.process(input);
+        }
+    }
+
+    pub fn dispatch(&mut self) {
+        for (id, controller) in self.controllers.iter() {
+            let device = player::Device::Controller(*id);
+            self.events.extend(controller.controller.held().iter().copied().map(|e| (device, e.into())));
+        }
+        let kbm = player::Device::KeyboardMouse;
+        self.events.extend(self.keyboard.held().iter().copied().map(|e| (kbm, e.into())));
+        self.events.extend(self.mouse.held().iter().copied().map(|e| (kbm, e.into())));
+
+        let mut text_wanted = false;
+        for player in self.players.iter_mut() {
+            if player.pop_layer {
+                player.layers.pop();
+                player.pop_layer = false;
+            }
+
+            // each player uses whichever of their devices they touched last
+            let use_controller = player.prefers_controller(&self.last_used);
+            // analog events compare equal regardless of value, so walk backwards to keep the latest
+            let events: HashSet<event::Event> = self.events.iter()
+                .rev()
+                .filter(|(device, _)| player.owns(*device))
+                .filter(|(_, e)| use_controller == matches!(e, event::Event::Controller(_)))
+                .map(|(_, e)| *e)
+                .collect();
+
+            let owns_kbm = player.owns(kbm);
+            let Some((layer, inactive)) = player.layers.split_last_mut() else {
+                continue;
+            };
+            for inactive in inactive.iter_mut() {
+                inactive.idle();
             }
+
+            let text_mode = layer.wants_text();
+            let text = if owns_kbm {
+                std::mem::take(&mut self.text)
+            } else {
+                Vec::new()
+            };
+            let events_without_keyboard: HashSet<event::Event> = if text_mode {
+                events.iter()
+                    .filter(|e| !matches!(e, event::Event::Keyboard(_)))
+                    .copied()
+                    .collect()
+            } else {
+                HashSet::new()
+            };
+            let action_map = layer.action_map.as_ref().unwrap_or(&self.action_map);
+            let actions = action_map.triggered(&events);
+            let without_keyboard = action_map.triggered(&events_without_keyboard);
+
+            text_wanted |= text_mode && owns_kbm;
+            layer.process_actions(layer::Frame {
+                actions,
+                without_keyboard,
+                text,
+                text_mode
+            });
         }
 
-        if let Some(layer) = self.layers.last_mut() {
-            layer.process_actions(actions);
+        if let Some(text_input) = self.text_input.as_ref() {
+            if text_wanted && !text_input.is_active() {
+                text_input.start();
+            } else if !text_wanted && text_input.is_active() {
+                text_input.stop();
+            }
         }
 
         self.events.clear();
+        self.text.clear();
+    }
+
+    pub fn set_hotplug(&mut self, hotplug: player::Hotplug) {
+        self.hotplug = hotplug;
+    }
+
+    pub fn add_player(&mut self) -> player::Id {
+        let id = self.next_player;
+        self.next_player += 1;
+        self.players.push(player::Player::new(id));
+        id
+    }
+
+    /// Removes the player, leaving any devices they owned unassigned
+    pub fn remove_player(&mut self, id: player::Id) -> Option<player::Player> {
+        self.awaiting_reconnect.retain(|waiting| *waiting != id);
+        let idx = self.players.iter().position(|player| player.id == id)?;
+        Some(self.players.remove(idx))
+    }
+
+    pub fn player(&self, id: player::Id) -> Option<&player::Player> {
+        self.players.iter().find(|player| player.id == id)
+    }
+
+    pub fn player_mut(&mut self, id: player::Id) -> Option<&mut player::Player> {
+        self.players.iter_mut().find(|player| player.id == id)
+    }
+
+    pub fn players(&self) -> impl Iterator<Item = &player::Player> {
+        self.players.iter()
+    }
+
+    pub fn owner_of(&self, device: player::Device) -> Option<player::Id> {
+        self.players.iter()
+            .find(|player| player.owns(device))
+            .map(|player| player.id)
+    }
+
+    /// Gives the device to the player, taking it away from any previous owner
+    pub fn assign_device(&mut self, id: player::Id, device: player::Device) -> bool {
+        if self.player(id).is_none() {
+            return false;
+        }
+        if let player::Device::Controller(controller) = device {
+            if !self.controllers.contains_key(&controller) {
+                return false;
+            }
+        }
+
+        self.unassign_device(device);
+        self.player_mut(id).unwrap().devices.insert(device);
+        true
+    }
+
+    pub fn unassign_device(&mut self, device: player::Device) {
+        for player in self.players.iter_mut() {
+            player.devices.remove(&device);
+        }
+    }
+
+    pub fn unassigned_devices(&self) -> Vec<player::Device> {
+        let mut devices = Vec::new();
+        if self.owner_of(player::Device::KeyboardMouse).is_none() {
+            devices.push(player::Device::KeyboardMouse);
+        }
+        for id in self.controllers.keys() {
+            let device = player::Device::Controller(*id);
+            if self.owner_of(device).is_none() {
+                devices.push(device);
+            }
+        }
+        devices
     }
 
     pub fn active_layer(&self) -> Option<&layer::Layer> {
-        self.layers.last()
+        self.player(player::PRIMARY)?.active_layer()
     }
 
     pub fn active_layer_mut(&mut self) -> Option<&mut layer::Layer> {
-        self.layers.last_mut()
+        self.player_mut(player::PRIMARY)?.active_layer_mut()
     }
 
     pub fn push_layer(&mut self, name: impl Into<String>) {
-        self.layers.push(layer::Layer {
-            name: name.into(),
-            context_stack: Vec::new()
-        });
+        if let Some(player) = self.player_mut(player::PRIMARY) {
+            player.push_layer(name);
+        }
+    }
+
+    pub fn push_layer_with_map(&mut self, name: impl Into<String>, action_map: action::Map) {
+        if let Some(player) = self.player_mut(player::PRIMARY) {
+            player.push_layer_with_map(name, action_map);
+        }
     }
 
     pub fn pop_layer(&mut self) {
-        self.pop_layer = true;
+        if let Some(player) = self.player_mut(player::PRIMARY) {
+            player.pop_layer();
+        }
+    }
+
+    pub fn action_map(&self) -> &action::Map {
+        &self.action_map
+    }
+
+    pub fn set_action_map(&mut self, action_map: action::Map) {
+        self.action_map = action_map;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn map() -> action::Map {
+        action::Map::new()
+            .map("forward")
+                .key(action::Keyboard::Hold(action::Key::Scancode(keyboard::Scancode::W)))
+                .finish()
+            .map("jump")
+                .key(action::Keyboard::Press(action::Key::Scancode(keyboard::Scancode::Space)))
+                .finish()
+            .map("jump")
+                .controller(action::Controller::Press(controller::Button::A))
+                .finish()
+            .map("fire")
+                .mouse(action::Mouse::Press(mouse::Button::Left))
+                .finish()
+            .map("pause")
+                .key(action::Keyboard::Press(action::Key::Scancode(keyboard::Scancode::Escape)))
+                .finish()
+            .build()
+    }
+
+    fn move_map() -> action::Map {
+        let key = |scancode| action::Source::Key(action::Key::Scancode(scancode));
+        action::Map::new()
+            .map("move")
+                .axis_2d(action::Axis2d::Buttons {
+                    up: key(keyboard::Scancode::W),
+                    down: key(keyboard::Scancode::S),
+                    left: key(keyboard::Scancode::A),
+                    right: key(keyboard::Scancode::D),
+                })
+                .finish()
+            .map("move")
+                .axis_2d(action::Axis2d::LeftStick)
+                .finish()
+            .build()
+    }
+
+    fn drain_values(channel: &mut engine_event::Channel<action::Action, &'static str>) -> Vec<action::Value> {
+        let mut values = Vec::new();
+        while let Some(action) = channel.pop() {
+            values.push(action.id.value);
+        }
+        values
+    }
+
+    fn drain(channel: &mut engine_event::Channel<action::Action, &'static str>) -> Vec<String> {
+        let mut names = Vec::new();
+        while let Some(action) = channel.pop() {
+            names.push(action.id.name);
+        }
+        names
+    }
+
+    #[test]
+    fn test_keyboard_binding() {
+        let mut input = Engine::headless(map());
+        input.push_layer("game");
+        let mut gameplay = input.active_layer_mut().unwrap().context_handler()
+            .action("forward")
+            .action("jump")
+            .build();
+        let mut keyboard = input.virtual_keyboard();
+
+        keyboard.press(keyboard::Scancode::W);
+        input.poll();
+        input.dispatch();
+        assert_eq!(drain(&mut gameplay), vec!["forward"]);
+
+        // held keys keep firing until released
+        input.poll();
+        input.dispatch();
+        assert_eq!(drain(&mut gameplay), vec!["forward"]);
+
+        keyboard.release(keyboard::Scancode::W);
+        input.poll();
+        input.dispatch();
+        assert!(drain(&mut gameplay).is_empty());
+    }
+
+    #[test]
+    fn test_mouse_binding() {
+        let mut input = Engine::headless(map());
+        input.push_layer("game");
+        let mut gameplay = input.active_layer_mut().unwrap().context_handler()
+            .action("fire")
+            .build();
+        let mut mouse = input.virtual_mouse();
+
+        mouse.press(mouse::Button::Left);
+        input.poll();
+        input.dispatch();
+        assert_eq!(drain(&mut gameplay), vec!["fire"]);
+    }
+
+    #[test]
+    fn test_unlisted_actions_ignored() {
+        let mut input = Engine::headless(map());
+        input.push_layer("game");
+        let mut gameplay = input.active_layer_mut().unwrap().context_handler()
+            .action("forward")
+            .build();
+        let mut keyboard = input.virtual_keyboard();
+
+        keyboard.press(keyboard::Scancode::Space);
+        input.poll();
+        input.dispatch();
+        assert!(drain(&mut gameplay).is_empty());
+    }
+
+    #[test]
+    fn test_block_all() {
+        let mut input = Engine::headless(map());
+        input.push_layer("game");
+        let mut gameplay = input.active_layer_mut().unwrap().context_handler()
+            .action("forward")
+            .action("pause")
+            .build();
+        let mut menu = input.active_layer_mut().unwrap().context_handler()
+            .block(context::Block::All)
+            .action("pause")
+            .build();
+        let mut keyboard = input.virtual_keyboard();
+
+        keyboard.press(keyboard::Scancode::W);
+        keyboard.press(keyboard::Scancode::Escape);
+        input.poll();
+        input.dispatch();
+        assert_eq!(drain(&mut menu), vec!["pause"]);
+        assert!(drain(&mut gameplay).is_empty());
+    }
+
+    #[test]
+    fn test_block_non_critical() {
+        let mut input = Engine::headless(map());
+        input.push_layer("game");
+        let mut low = input.active_layer_mut().unwrap().context_handler()
+            .action("forward")
+            .build();
+        let mut high = input.active_layer_mut().unwrap().context_handler()
+            .priority(context::Priority::HIGH)
+            .action("pause")
+            .build();
+        let mut blocking = input.active_layer_mut().unwrap().context_handler()
+            .block(context::Block::NonCritical)
+            .action("jump")
+            .build();
+        let mut keyboard = input.virtual_keyboard();
+
+        keyboard.press(keyboard::Scancode::W);
+        keyboard.press(keyboard::Scancode::Escape);
+        keyboard.press(keyboard::Scancode::Space);
+        input.poll();
+        input.dispatch();
+        assert_eq!(drain(&mut blocking), vec!["jump"]);
+        assert_eq!(drain(&mut high), vec!["pause"]);
+        assert!(drain(&mut low).is_empty());
+    }
+
+    #[test]
+    fn test_only_active_layer_receives() {
+        let mut input = Engine::headless(map());
+        input.push_layer("game");
+        let mut gameplay = input.active_layer_mut().unwrap().context_handler()
+            .action("forward")
+            .build();
+        input.push_layer("menu");
+        let mut keyboard = input.virtual_keyboard();
+
+        keyboard.press(keyboard::Scancode::W);
+        input.poll();
+        input.dispatch();
+        assert!(drain(&mut gameplay).is_empty());
+
+        input.pop_layer();
+        input.poll();
+        input.dispatch();
+        assert_eq!(drain(&mut gameplay), vec!["forward"]);
+    }
+
+    #[test]
+    fn test_gamepad_binding() {
+        let mut input = Engine::headless(map());
+        input.push_layer("game");
+        let mut gameplay = input.active_layer_mut().unwrap().context_handler()
+            .action("jump")
+            .build();
+        let mut gamepad = input.virtual_gamepad();
+
+        input.poll();
+        assert_eq!(input.owner_of(player::Device::Controller(gamepad.id())), Some(player::PRIMARY));
+
+        gamepad.press(controller::Button::A);
+        input.poll();
+        input.dispatch();
+        assert_eq!(drain(&mut gameplay), vec!["jump"]);
+    }
+
+    #[test]
+    fn test_last_used_device_wins() {
+        let mut input = Engine::headless(map());
+        input.push_layer("game");
+        let mut gameplay = input.active_layer_mut().unwrap().context_handler()
+            .action("forward")
+            .build();
+        let mut keyboard = input.virtual_keyboard();
+        let mut gamepad = input.virtual_gamepad();
+
+        keyboard.press(keyboard::Scancode::W);
+        input.poll();
+        input.dispatch();
+        assert_eq!(drain(&mut gameplay), vec!["forward"]);
+
+        // touching the gamepad drops keyboard events until the keyboard is used again
+        gamepad.press(controller::Button::B);
+        input.poll();
+        input.dispatch();
+        assert!(drain(&mut gameplay).is_empty());
+    }
+
+    #[test]
+    fn test_players_are_separate() {
+        let mut input = Engine::headless(map());
+        let second = input.add_player();
+        let mut gamepad = input.virtual_gamepad();
+        input.poll();
+        assert_eq!(input.owner_of(player::Device::Controller(gamepad.id())), Some(player::PRIMARY));
+        assert!(input.assign_device(second, player::Device::Controller(gamepad.id())));
+
+        input.push_layer("game");
+        let mut first_channel = input.active_layer_mut().unwrap().context_handler()
+            .action("jump")
+            .build();
+        let player = input.player_mut(second).unwrap();
+        player.push_layer("game");
+        let mut second_channel = player.active_layer_mut().unwrap().context_handler()
+            .action("jump")
+            .build();
+
+        gamepad.press(controller::Button::A);
+        input.poll();
+        input.dispatch();
+        assert!(drain(&mut first_channel).is_empty());
+        assert_eq!(drain(&mut second_channel), vec!["jump"]);
+    }
+
+    #[test]
+    fn test_hotplug_reassigns() {
+        let mut input = Engine::headless(map());
+        let second = input.add_player();
+        let first_pad = input.virtual_gamepad();
+        let second_pad = input.virtual_gamepad();
+        input.poll();
+        assert_eq!(input.owner_of(player::Device::Controller(first_pad.id())), Some(player::PRIMARY));
+        assert_eq!(input.owner_of(player::Device::Controller(second_pad.id())), Some(second));
+
+        second_pad.disconnect();
+        input.poll();
+        assert!(!input.player(second).unwrap().has_controller());
+
+        let replacement = input.virtual_gamepad();
+        input.poll();
+        assert_eq!(input.owner_of(player::Device::Controller(replacement.id())), Some(second));
+
+        let unassigned: HashSet<player::Device> = HashSet::from_iter(input.unassigned_devices());
+        assert!(unassigned.is_empty());
+    }
+
+    #[test]
+    fn test_composite_buttons() {
+        let mut input = Engine::headless(move_map());
+        input.push_layer("game");
+        let mut gameplay = input.active_layer_mut().unwrap().context_handler()
+            .action("move")
+            .build();
+        let mut keyboard = input.virtual_keyboard();
+
+        keyboard.press(keyboard::Scancode::W);
+        input.poll();
+        input.dispatch();
+        assert_eq!(drain_values(&mut gameplay), vec![action::Value::Axis2d(nalgebra_glm::vec2(0.0, 1.0))]);
+
+        keyboard.press(keyboard::Scancode::D);
+        input.poll();
+        input.dispatch();
+        let values = drain_values(&mut gameplay);
+        assert_eq!(values.len(), 1);
+        let direction = values[0].axis_2d();
+        assert!((direction.magnitude() - 1.0).abs() < 0.0001);
+        assert!((direction.x - direction.y).abs() < 0.0001);
+
+        // opposing keys cancel out and the action is not sent
+        keyboard.release(keyboard::Scancode::D);
+        keyboard.press(keyboard::Scancode::S);
+        input.poll();
+        input.dispatch();
+        assert!(drain_values(&mut gameplay).is_empty());
+    }
+
+    #[test]
+    fn test_composite_stick() {
+        let mut input = Engine::headless(move_map());
+        input.set_controller_settings(controller::Settings {
+            left_stick: controller::StickSettings {
+                dead_zone: controller::DeadZone { radial: 0.0, axial: 0.0, outer: 1.0 },
+                curve: controller::Curve::Linear
+            },
+            ..controller::Settings::default()
+        });
+        input.push_layer("game");
+        let mut gameplay = input.active_layer_mut().unwrap().context_handler()
+            .action("move")
+            .build();
+        let mut gamepad = input.virtual_gamepad();
+
+        gamepad.move_left_stick(nalgebra_glm::vec2(0.0, -0.5));
+        input.poll();
+        input.dispatch();
+        assert_eq!(drain_values(&mut gameplay), vec![action::Value::Axis2d(nalgebra_glm::vec2(0.0, 0.5))]);
+
+        // sticks held away from rest keep reporting without new motion
+        input.poll();
+        input.dispatch();
+        assert_eq!(drain_values(&mut gameplay), vec![action::Value::Axis2d(nalgebra_glm::vec2(0.0, 0.5))]);
+    }
+
+    #[test]
+    fn test_output_recorded() {
+        let mut input = Engine::headless(map());
+        let recorder = output::Recorder::new();
+        let gamepad = input.virtual_gamepad_with_output(recorder.clone());
+        let plain = input.virtual_gamepad();
+
+        let rumble = output::Rumble {
+            low_frequency: 0.25,
+            high_frequency: 1.0,
+            duration: std::time::Duration::from_millis(200)
+        };
+        assert_eq!(input.rumble(gamepad.id(), rumble), Err(error::Output::NoController(gamepad.id())));
+
+        input.poll();
+        let colour = output::Colour { red: 255, green: 0, blue: 64 };
+        input.rumble(gamepad.id(), rumble).unwrap();
+        input.set_led(gamepad.id(), colour).unwrap();
+        assert_eq!(recorder.commands(), vec![output::Command::Rumble(rumble), output::Command::Led(colour)]);
+        assert_eq!(input.rumble(plain.id(), rumble), Err(error::Output::NoDevice(plain.id())));
+
+        recorder.clear();
+        input.assign_device(player::PRIMARY, player::Device::Controller(plain.id()));
+        assert!(input.rumble_player(player::PRIMARY, rumble).is_err());
+        assert_eq!(recorder.commands(), vec![output::Command::Rumble(rumble)]);
+    }
+
+    #[test]
+    fn test_text_input() {
+        let mut input = Engine::headless(map());
+        input.push_layer("game");
+        let mut gameplay = input.active_layer_mut().unwrap().context_handler()
+            .action("forward")
+            .action("fire")
+            .build();
+        let mut console = input.active_layer_mut().unwrap().context_handler()
+            .name("console")
+            .text_input()
+            .action("pause")
+            .build();
+        let mut keyboard = input.virtual_keyboard();
+        let mut mouse = input.virtual_mouse();
+
+        keyboard.press(keyboard::Scancode::W);
+        keyboard.type_text("w");
+        keyboard.compose("ka", 0, 2);
+        keyboard.press(keyboard::Scancode::Escape);
+        mouse.press(mouse::Button::Left);
+        input.poll();
+        input.dispatch();
+
+        let mut received = Vec::new();
+        while let Some(action) = console.pop() {
+            received.push((action.id.name, action.id.value));
+        }
+        assert_eq!(received, vec![
+            (action::TEXT_INPUT.to_string(), action::Value::Text("w".into())),
+            (action::TEXT_COMPOSITION.to_string(), action::Value::Composition { text: "ka".into(), start: 0, length: 2 }),
+            ("pause".to_string(), action::Value::Button),
+        ]);
+        // keyboard bindings are held back from gameplay, other devices still go through
+        assert_eq!(drain(&mut gameplay), vec!["fire"]);
+
+        input.active_layer_mut().unwrap().set_text_input("console", false);
+        input.poll();
+        input.dispatch();
+        assert_eq!(drain(&mut gameplay), vec!["forward"]);
+    }
+
+    #[test]
+    fn test_mouse_motion_keeps_speed() {
+        let look_map = action::Map::new()
+            .map("look")
+                .mouse(action::Mouse::Move)
+                .finish()
+            .build();
+        let mut input = Engine::headless(look_map);
+        input.set_mouse_settings(mouse::Settings {
+            sensitivity: nalgebra_glm::vec2(0.5, 0.25),
+            invert_y: true,
+            ..mouse::Settings::default()
+        });
+        input.push_layer("game");
+        let mut gameplay = input.active_layer_mut().unwrap().context_handler()
+            .action("look")
+            .build();
+        let mut mouse = input.virtual_mouse();
+
+        // motion within one poll accumulates
+        mouse.move_by(nalgebra_glm::vec2(4, 4));
+        mouse.move_by(nalgebra_glm::vec2(4, 4));
+        input.poll();
+        input.dispatch();
+        assert_eq!(drain_values(&mut gameplay), vec![action::Value::Delta(nalgebra_glm::vec2(4.0, -2.0))]);
+
+        mouse.move_by(nalgebra_glm::vec2(2, 0));
+        input.poll();
+        input.dispatch();
+        assert_eq!(drain_values(&mut gameplay), vec![action::Value::Delta(nalgebra_glm::vec2(1.0, 0.0))]);
+
+        input.poll();
+        input.dispatch();
+        assert!(drain_values(&mut gameplay).is_empty());
+    }
+
+    #[test]
+    fn test_polled_state() {
+        let mut input = Engine::headless(map());
+        input.push_layer("game");
+        let state = input.active_layer_mut().unwrap().context_handler()
+            .action("forward")
+            .action("jump")
+            .build_polled();
+        let mut keyboard = input.virtual_keyboard();
+
+        keyboard.press(keyboard::Scancode::W);
+        input.poll();
+        input.dispatch();
+        assert!(state.pressed("forward"));
+        assert!(state.held("forward"));
+        assert!(!state.released("forward"));
+        assert_eq!(state.value("forward"), Some(action::Value::Button));
+
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        input.poll();
+        input.dispatch();
+        assert!(!state.pressed("forward"));
+        assert!(state.held("forward"));
+        assert!(state.held_duration("forward") >= std::time::Duration::from_millis(5));
+
+        keyboard.release(keyboard::Scancode::W);
+        input.poll();
+        input.dispatch();
+        assert!(!state.held("forward"));
+        assert!(state.released("forward"));
+        assert_eq!(state.held_duration("forward"), std::time::Duration::ZERO);
+        assert_eq!(state.value("forward"), None);
+
+        input.poll();
+        input.dispatch();
+        assert!(!state.released("forward"));
+        assert!(!state.held("jump"));
+    }
+
+    #[test]
+    fn test_polled_state_blocked() {
+        let mut input = Engine::headless(map());
+        input.push_layer("game");
+        let gameplay = input.active_layer_mut().unwrap().context_handler()
+            .name("gameplay")
+            .action("forward")
+            .build_polled();
+        let mut keyboard = input.virtual_keyboard();
+
+        keyboard.press(keyboard::Scancode::W);
+        input.poll();
+        input.dispatch();
+        assert!(gameplay.held("forward"));
+
+        // a layer on top stops the state from going stale
+        input.push_layer("menu");
+        input.poll();
+        input.dispatch();
+        assert!(!gameplay.held("forward"));
+        assert!(gameplay.released("forward"));
+
+        input.pop_layer();
+        input.poll();
+        input.dispatch();
+        let layer = input.active_layer().unwrap();
+        assert!(layer.state("gameplay").unwrap().pressed("forward"));
+    }
+
+    #[test]
+    fn test_numeric_priority_order() {
+        let mut input = Engine::headless(map());
+        input.push_layer("game");
+        let mut overlay = input.active_layer_mut().unwrap().context_handler()
+            .priority(context::Priority(50))
+            .block(context::Block::All)
+            .action("pause")
+            .build();
+        // pushed later but lower priority, so the overlay still sees input first
+        let mut gameplay = input.active_layer_mut().unwrap().context_handler()
+            .priority(context::Priority(10))
+            .action("pause")
+            .build();
+        let mut keyboard = input.virtual_keyboard();
+
+        keyboard.press(keyboard::Scancode::Escape);
+        input.poll();
+        input.dispatch();
+        assert_eq!(drain(&mut overlay), vec!["pause"]);
+        assert!(drain(&mut gameplay).is_empty());
+    }
+
+    #[test]
+    fn test_enable_disable_by_name() {
+        let mut input = Engine::headless(map());
+        input.push_layer("game");
+        let mut gameplay = input.active_layer_mut().unwrap().context_handler()
+            .action("forward")
+            .build();
+        let mut menu = input.active_layer_mut().unwrap().context_handler()
+            .name("menu")
+            .block(context::Block::All)
+            .disabled()
+            .action("forward")
+            .build();
+        let mut keyboard = input.virtual_keyboard();
+
+        keyboard.press(keyboard::Scancode::W);
+        input.poll();
+        input.dispatch();
+        assert_eq!(drain(&mut gameplay), vec!["forward"]);
+        assert!(drain(&mut menu).is_empty());
+
+        let layer = input.active_layer_mut().unwrap();
+        assert!(layer.enable("menu"));
+        assert_eq!(layer.is_enabled("menu"), Some(true));
+        input.poll();
+        input.dispatch();
+        assert_eq!(drain(&mut menu), vec!["forward"]);
+        assert!(drain(&mut gameplay).is_empty());
+
+        let layer = input.active_layer_mut().unwrap();
+        assert!(layer.remove("menu"));
+        assert!(!layer.enable("menu"));
+        input.poll();
+        input.dispatch();
+        assert_eq!(drain(&mut gameplay), vec!["forward"]);
+    }
+
+    #[test]
+    fn test_layer_action_map() {
+        let mut input = Engine::headless(map());
+        input.push_layer("game");
+        let mut gameplay = input.active_layer_mut().unwrap().context_handler()
+            .action("forward")
+            .build();
+        let menu_map = action::Map::new()
+            .map("confirm")
+                .key(action::Keyboard::Hold(action::Key::Scancode(keyboard::Scancode::W)))
+                .finish()
+            .build();
+        input.push_layer_with_map("menu", menu_map);
+        let mut menu = input.active_layer_mut().unwrap().context_handler()
+            .action("confirm")
+            .action("forward")
+            .build();
+        let mut keyboard = input.virtual_keyboard();
+
+        keyboard.press(keyboard::Scancode::W);
+        input.poll();
+        input.dispatch();
+        assert_eq!(drain(&mut menu), vec!["confirm"]);
+
+        input.pop_layer();
+        input.poll();
+        input.dispatch();
+        assert_eq!(drain(&mut gameplay), vec!["forward"]);
+    }
+
+    #[test]
+    fn test_labels_follow_family_and_rebinding() {
+        let mut input = Engine::headless(map());
+        input.push_layer("game");
+        let mut gamepad = input.virtual_gamepad();
+        input.set_controller_family(gamepad.id(), controller::Family::PlayStation);
+
+        gamepad.press(controller::Button::A);
+        input.poll();
+        input.dispatch();
+        let labels = input.labels(player::PRIMARY, "jump");
+        assert_eq!(labels, vec![vec![label::Label { text: "Cross".into(), glyph: "playstation_cross".into() }]]);
+
+        let rebound = input.action_map().clone()
+            .rebind("jump")
+                .controller(action::Controller::Press(controller::Button::Y))
+                .finish()
+            .build();
+        input.set_action_map(rebound);
+        let labels = input.labels(player::PRIMARY, "jump");
+        assert_eq!(labels, vec![vec![label::Label { text: "Triangle".into(), glyph: "playstation_triangle".into() }]]);
+
+        input.set_controller_family(gamepad.id(), controller::Family::Switch);
+        let labels = input.labels(player::PRIMARY, "jump");
+        assert_eq!(labels[0][0].text, "X");
     }
 }
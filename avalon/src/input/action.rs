@@ -1,19 +1,28 @@
 use std::collections::HashSet;
+use nalgebra_glm::{ Vec2, vec2 };
 use crate::input::{ self, event };
 
 pub type ActionId = String;
 
+/// Committed text, sent to contexts with text input enabled
+pub const TEXT_INPUT: &str = "text_input";
+/// In-progress IME composition, sent to contexts with text input enabled
+pub const TEXT_COMPOSITION: &str = "text_composition";
+
+#[derive(Debug, Copy, Clone)]
 pub enum Key {
     Scancode(input::keyboard::Scancode),
     Keycode(input::keyboard::Keycode),
 }
 
+#[derive(Debug, Copy, Clone)]
 pub enum Keyboard {
     Press(Key),
     Release(Key),
     Hold(Key),
 }
 
+#[derive(Debug, Copy, Clone)]
 pub enum Mouse {
     Press(input::mouse::Button),
     Release(input::mouse::Button),
@@ -22,6 +31,7 @@ pub enum Mouse {
     Move,
 }
 
+#[derive(Debug, Copy, Clone)]
 pub enum Controller {
     Press(input::controller::Button),
     Release(input::controller::Button),
@@ -32,14 +42,75 @@ pub enum Controller {
     RightTrigger
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// A digital input that can make up part of a composite axis
+#[derive(Debug, Copy, Clone)]
+pub enum Source {
+    Key(Key),
+    Mouse(input::mouse::Button),
+    Controller(input::controller::Button),
+}
+
+#[derive(Debug, Copy, Clone)]
+pub enum Axis1d {
+    Buttons { negative: Source, positive: Source },
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+    LeftTrigger,
+    RightTrigger,
+}
+
+/// Positive y is up, so stick axes are flipped from their device orientation
+#[derive(Debug, Copy, Clone)]
+pub enum Axis2d {
+    Buttons { up: Source, down: Source, left: Source, right: Source },
+    LeftStick,
+    RightStick,
+}
+
+/// A single input as it was bound, kept so bindings can be listed and labelled
+#[derive(Debug, Copy, Clone)]
+pub enum Binding {
+    Key(Keyboard),
+    Mouse(Mouse),
+    Controller(Controller),
+    Axis1d(Axis1d),
+    Axis2d(Axis2d),
+}
+
+#[derive(Debug, Copy, Clone)]
+enum Composite {
+    Axis1d(Axis1d),
+    Axis2d(Axis2d),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Button,
+    /// Clamped to [-1, 1]
+    Axis1d(f32),
+    /// Clamped to the unit circle
+    Axis2d(Vec2),
+    /// Unbounded relative motion, such as the mouse
+    Delta(Vec2),
+    /// UTF-8 text
+    Text(String),
+    /// `start` and `length` select the part of `text` being edited
+    Composition { text: String, start: i32, length: i32 },
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Action {
     pub name: ActionId,
+    pub value: Value,
 }
 
 #[derive(Debug, Clone)]
 pub struct Mapping {
     pub(crate) required_events: HashSet<event::Event>,
+    composites: Vec<Composite>,
+    bindings: Vec<Binding>,
     action: ActionId
 }
 
@@ -51,10 +122,25 @@ pub struct Map {
 pub struct MappingBuilder {
     map_builder: MapBuilder,
     action: ActionId,
-    required_events: HashSet<event::Event>
+    required_events: HashSet<event::Event>,
+    composites: Vec<Composite>,
+    bindings: Vec<Binding>
 }
 
 impl MappingBuilder {
+    /// Composites on the same mapping are summed, and any other events become modifiers
+    pub fn axis(mut self, axis: Axis1d) -> MappingBuilder {
+        self.composites.push(Composite::Axis1d(axis));
+        self.bindings.push(Binding::Axis1d(axis));
+        self
+    }
+
+    pub fn axis_2d(mut self, axis: Axis2d) -> MappingBuilder {
+        self.composites.push(Composite::Axis2d(axis));
+        self.bindings.push(Binding::Axis2d(axis));
+        self
+    }
+
     pub fn key(mut self, key: Keyboard) -> MappingBuilder {
         let event = match key {
             Keyboard::Press(key) => event::Keyboard::Button {
@@ -80,6 +166,7 @@ impl MappingBuilder {
             },
         };
         self.required_events.insert(event.into());
+        self.bindings.push(Binding::Key(key));
         self
     }
 
@@ -97,10 +184,15 @@ impl MappingBuilder {
                 state: event::Binary::Hold,
                 button
             },
-            Mouse::Move => event::Mouse::Move { position: nalgebra_glm::IVec2::zeros(), direction: nalgebra_glm::IVec2::zeros() },
+            Mouse::Move => event::Mouse::Move {
+                position: nalgebra_glm::IVec2::zeros(),
+                direction: nalgebra_glm::IVec2::zeros(),
+                delta: Vec2::zeros()
+            },
             Mouse::Scroll => event::Mouse::Scroll { scroll: f32::NAN },
         };
         self.required_events.insert(event.into());
+        self.bindings.push(Binding::Mouse(mouse));
         self
     }
 
@@ -132,13 +224,16 @@ impl MappingBuilder {
             ),
         };
         self.required_events.insert(event.into());
+        self.bindings.push(Binding::Controller(controller));
         self
     }
 
     pub fn finish(mut self) -> MapBuilder {
         self.map_builder.mappings.push(Mapping {
             action: self.action,
-            required_events: self.required_events
+            required_events: self.required_events,
+            composites: self.composites,
+            bindings: self.bindings
         });
         self.map_builder
     }
@@ -153,7 +248,9 @@ impl MapBuilder {
         MappingBuilder {
             map_builder: self,
             action: action.into(),
-            required_events: HashSet::new()
+            required_events: HashSet::new(),
+            composites: Vec::new(),
+            bindings: Vec::new()
         }
     }
 
@@ -171,12 +268,240 @@ impl Map {
             mappings: Vec::new()
         }
     }
+
+    /// Every mapping for the action, in the order they were added
+    pub fn mappings<'a>(&'a self, action: &'a str) -> impl Iterator<Item = &'a Mapping> + 'a {
+        self.mappings.iter().filter(move |mapping| mapping.action == action)
+    }
+
+    /// Removes every mapping for the action
+    pub fn unbind(&mut self, action: &str) {
+        self.mappings.retain(|mapping| mapping.action != action);
+    }
+
+    /// Replaces every mapping for the action with the one being built
+    pub fn rebind(mut self, action: impl Into<String>) -> MappingBuilder {
+        let action = action.into();
+        self.unbind(&action);
+        MapBuilder { mappings: self.mappings }.map(action)
+    }
+
+    pub(crate) fn triggered<'e>(&self, events: &'e HashSet<event::Event>) -> Vec<(Action, Vec<&'e event::Event>)> {
+        let mut actions: Vec<(Action, Vec<&event::Event>)> = Vec::new();
+        for action in self.mappings.iter() {
+            if !action.composites.is_empty() {
+                if !action.required_events.is_subset(events) {
+                    continue;
+                }
+                if let Some(value) = action.evaluate(events) {
+                    let triggered_events = action.required_events.iter()
+                        .filter_map(|required| events.get(required))
+                        .collect();
+                    actions.push((Action { name: action.action.clone(), value }, triggered_events));
+                }
+            } else if !action.required_events.is_empty() && action.required_events.is_subset(events) {
+                let mut triggered_events: Vec<&event::Event> = Vec::new();
+                for required in action.required_events.iter() {
+                    if let Some(event) = events.get(required) {
+                        triggered_events.push(event);
+                    }
+                }
+                let mut triggered: Action = action.into();
+                for event in triggered_events.iter() {
+                    if let event::Event::Mouse(event::Mouse::Move { delta, .. }) = event {
+                        triggered.value = Value::Delta(*delta);
+                    }
+                }
+                actions.push((triggered, triggered_events));
+            }
+        }
+        actions
+    }
+}
+
+impl Mapping {
+    pub fn action(&self) -> &str {
+        &self.action
+    }
+
+    /// The inputs this mapping was built from. All of them are needed for it to trigger
+    pub fn bindings(&self) -> &[Binding] {
+        &self.bindings
+    }
+
+    /// The combined composite value, or nothing if every composite is at rest
+    fn evaluate(&self, events: &HashSet<event::Event>) -> Option<Value> {
+        let mut axis = 0.0;
+        let mut axis_2d = Vec2::zeros();
+        let mut is_2d = false;
+        for composite in self.composites.iter() {
+            match composite {
+                Composite::Axis1d(composite) => axis += composite.evaluate(events),
+                Composite::Axis2d(composite) => {
+                    axis_2d += composite.evaluate(events);
+                    is_2d = true;
+                },
+            }
+        }
+
+        if is_2d {
+            let magnitude = axis_2d.magnitude();
+            if magnitude == 0.0 {
+                None
+            } else if magnitude > 1.0 {
+                Some(Value::Axis2d(axis_2d / magnitude))
+            } else {
+                Some(Value::Axis2d(axis_2d))
+            }
+        } else if axis == 0.0 {
+            None
+        } else {
+            Some(Value::Axis1d(axis.clamp(-1.0, 1.0)))
+        }
+    }
+}
+
+impl Binding {
+    /// Whether this binding is driven by a controller rather than the keyboard and mouse
+    pub fn is_controller(&self) -> bool {
+        match self {
+            Binding::Key(_) | Binding::Mouse(_) => false,
+            Binding::Controller(_) => true,
+            Binding::Axis1d(Axis1d::Buttons { negative, positive }) => {
+                negative.is_controller() || positive.is_controller()
+            },
+            Binding::Axis2d(Axis2d::Buttons { up, down, left, right }) => {
+                up.is_controller() || down.is_controller() || left.is_controller() || right.is_controller()
+            },
+            Binding::Axis1d(_) | Binding::Axis2d(_) => true,
+        }
+    }
+}
+
+impl Source {
+    pub fn is_controller(&self) -> bool {
+        matches!(self, Source::Controller(_))
+    }
+
+    fn held(&self, events: &HashSet<event::Event>) -> bool {
+        let event: event::Event = match *self {
+            Source::Key(key) => {
+                let key = match key {
+                    Key::Scancode(scancode) => Some(scancode),
+                    Key::Keycode(keycode) => input::keyboard::Scancode::from_keycode(keycode),
+                };
+                let Some(key) = key else {
+                    return false;
+                };
+                event::Keyboard::Button { state: event::Binary::Hold, key }.into()
+            },
+            Source::Mouse(button) => event::Mouse::Button { state: event::Binary::Hold, button }.into(),
+            Source::Controller(button) => event::Controller::Button { state: event::Binary::Hold, button }.into(),
+        };
+        events.contains(&event)
+    }
+
+    fn amount(&self, events: &HashSet<event::Event>) -> f32 {
+        if self.held(events) { 1.0 } else { 0.0 }
+    }
+}
+
+fn left_stick(events: &HashSet<event::Event>) -> Vec2 {
+    match events.get(&event::Controller::LeftStick(input::controller::Stick::new()).into()) {
+        Some(event::Event::Controller(event::Controller::LeftStick(stick))) => stick.direction,
+        _ => Vec2::zeros()
+    }
+}
+
+fn right_stick(events: &HashSet<event::Event>) -> Vec2 {
+    match events.get(&event::Controller::RightStick(input::controller::Stick::new()).into()) {
+        Some(event::Event::Controller(event::Controller::RightStick(stick))) => stick.direction,
+        _ => Vec2::zeros()
+    }
+}
+
+fn left_trigger(events: &HashSet<event::Event>) -> f32 {
+    match events.get(&event::Controller::LeftTrigger(input::controller::Trigger::new()).into()) {
+        Some(event::Event::Controller(event::Controller::LeftTrigger(trigger))) => trigger.amount,
+        _ => 0.0
+    }
+}
+
+fn right_trigger(events: &HashSet<event::Event>) -> f32 {
+    match events.get(&event::Controller::RightTrigger(input::controller::Trigger::new()).into()) {
+        Some(event::Event::Controller(event::Controller::RightTrigger(trigger))) => trigger.amount,
+        _ => 0.0
+    }
+}
+
+impl Axis1d {
+    fn evaluate(&self, events: &HashSet<event::Event>) -> f32 {
+        match self {
+            Axis1d::Buttons { negative, positive } => positive.amount(events) - negative.amount(events),
+            Axis1d::LeftStickX => left_stick(events).x,
+            Axis1d::LeftStickY => -left_stick(events).y,
+            Axis1d::RightStickX => right_stick(events).x,
+            Axis1d::RightStickY => -right_stick(events).y,
+            Axis1d::LeftTrigger => left_trigger(events),
+            Axis1d::RightTrigger => right_trigger(events),
+        }
+    }
+}
+
+impl Axis2d {
+    fn evaluate(&self, events: &HashSet<event::Event>) -> Vec2 {
+        match self {
+            Axis2d::Buttons { up, down, left, right } => vec2(
+                right.amount(events) - left.amount(events),
+                up.amount(events) - down.amount(events)
+            ),
+            Axis2d::LeftStick => {
+                let stick = left_stick(events);
+                vec2(stick.x, -stick.y)
+            },
+            Axis2d::RightStick => {
+                let stick = right_stick(events);
+                vec2(stick.x, -stick.y)
+            },
+        }
+    }
+}
+
+impl Value {
+    pub fn axis(&self) -> f32 {
+        match self {
+            Value::Button => 1.0,
+            Value::Axis1d(axis) => *axis,
+            Value::Axis2d(axis) => axis.magnitude(),
+            Value::Delta(delta) => delta.magnitude(),
+            Value::Text(_) | Value::Composition { .. } => 0.0,
+        }
+    }
+
+    pub fn axis_2d(&self) -> Vec2 {
+        match self {
+            Value::Button => Vec2::zeros(),
+            Value::Axis1d(axis) => vec2(*axis, 0.0),
+            Value::Axis2d(axis) => *axis,
+            Value::Delta(delta) => *delta,
+            Value::Text(_) | Value::Composition { .. } => Vec2::zeros(),
+        }
+    }
+
+    pub fn text(&self) -> Option<&str> {
+        match self {
+            Value::Text(text) => Some(text),
+            Value::Composition { text, .. } => Some(text),
+            _ => None,
+        }
+    }
 }
 
 impl From<&Mapping> for Action {
     fn from(mapping: &Mapping) -> Action {
         Action {
-            name: mapping.action.clone()
+            name: mapping.action.clone(),
+            value: Value::Button
         }
     }
 }
@@ -184,7 +509,8 @@ impl From<&Mapping> for Action {
 impl From<Mapping> for Action {
     fn from(mapping: Mapping) -> Action {
         Action {
-            name: mapping.action
+            name: mapping.action,
+            value: Value::Button
         }
     }
 }
@@ -1,19 +1,36 @@
 use crate::input::event;
 use crate::input::action;
 use crate::event::{ Dispatcher, Channel, Event };
-use std::collections::HashSet;
+use std::collections::{ HashSet, HashMap };
+use std::sync::{ Arc, RwLock };
+use std::time::{ Instant, Duration };
 
-pub enum Priority {
-    Low,
-    High
-}
+/// Higher priorities see input first. Anything at or above `HIGH` is critical and
+/// gets past `Block::NonCritical`
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Priority(pub i32);
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum Block {
     None,
     NonCritical,
     All
 }
 
+#[derive(Debug, Clone)]
+struct ActionState {
+    active: bool,
+    previous: bool,
+    since: Instant,
+    value: Option<action::Value>,
+}
+
+/// Polled view of a context's actions as of the last dispatch. Clones share the same state
+#[derive(Debug, Clone, Default)]
+pub struct State {
+    actions: Arc<RwLock<HashMap<action::ActionId, ActionState>>>,
+}
+
 pub struct Context {
     pub(super) name: Option<String>,
     allowed_actions: HashSet<action::ActionId>,
@@ -21,6 +38,9 @@ pub struct Context {
     channel: Channel<action::Action, &'static str>,
     pub(super) block: Block,
     pub(super) priority: Priority,
+    pub(super) text_input: bool,
+    pub(super) enabled: bool,
+    state: State,
 }
 
 impl Context {
@@ -33,11 +53,25 @@ impl Context {
             dispatcher,
             channel,
             block,
-            priority
+            priority,
+            text_input: false,
+            enabled: true,
+            state: State::default()
         }
     }
 
-    pub(super) fn process(&mut self, actions: &[(action::Action, Vec<&event::Event>)]) {
+    pub(super) fn process(&mut self, actions: &[(action::Action, Vec<&event::Event>)], text: &[action::Action]) {
+        if self.text_input {
+            for action in text {
+                self.channel.push(Event::new(action.clone()));
+            }
+        }
+
+        self.state.update(actions.iter()
+            .map(|(action, _)| action)
+            .filter(|action| self.allowed_actions.contains(&action.name))
+        );
+
         for (action, events) in actions {
             if self.allowed_actions.contains(&action.name) {
                 // report up
@@ -46,19 +80,16 @@ impl Context {
                     match event {
                         event::Event::Mouse(mouse_event) => {
                             match mouse_event {
-                                event::Mouse::Move { position, direction } => {
+                                event::Mouse::Move { position, direction, delta } => {
                                     action.data.store("cursor_x", position.x).unwrap();
                                     action.data.store("cursor_y", position.y).unwrap();
+                                    action.data.store("delta_x", direction.x).unwrap();
+                                    action.data.store("delta_y", direction.y).unwrap();
 
-                                    let magnitude = direction.cast::<f32>().magnitude();
-                                    let normal_direction: nalgebra_glm::Vec2 = if magnitude == 0.0 {
-                                        nalgebra_glm::vec2(0.0, 0.0)
-                                    } else {
-                                        direction.cast() / magnitude
-                                    };
-                                    action.data.store("axis_x", normal_direction.x).unwrap();
-                                    action.data.store("axis_y", normal_direction.y).unwrap();
-                                    action.data.store("axis_magnitude", magnitude).unwrap();
+                                    // mouse motion keeps its speed rather than being normalised
+                                    action.data.store("axis_x", delta.x).unwrap();
+                                    action.data.store("axis_y", delta.y).unwrap();
+                                    action.data.store("axis_magnitude", delta.magnitude()).unwrap();
                                 },
                                 event::Mouse::Button { .. } => {
 
@@ -115,7 +146,95 @@ impl Context {
         self.dispatcher.tick();
     }
 
+    /// Called instead of `process` when the context is blocked or its layer is inactive
+    pub(super) fn idle(&mut self) {
+        self.state.update(std::iter::empty());
+    }
+
     pub fn context_handler(&mut self) -> Channel<action::Action, &'static str> {
         self.dispatcher.receiver()
     }
+
+    pub fn state(&self) -> State {
+        self.state.clone()
+    }
+}
+
+impl Priority {
+    pub const LOW: Priority = Priority(0);
+    pub const HIGH: Priority = Priority(100);
+
+    pub fn is_critical(&self) -> bool {
+        *self >= Priority::HIGH
+    }
+}
+
+impl Default for Priority {
+    fn default() -> Priority {
+        Priority::LOW
+    }
+}
+
+impl State {
+    fn update<'a>(&self, triggered: impl Iterator<Item = &'a action::Action>) {
+        let now = Instant::now();
+        let mut actions = self.actions.write().unwrap();
+        for state in actions.values_mut() {
+            state.previous = state.active;
+            state.active = false;
+        }
+
+        for action in triggered {
+            let state = actions.entry(action.name.clone()).or_insert(ActionState {
+                active: false,
+                previous: false,
+                since: now,
+                value: None,
+            });
+            if !state.active && !state.previous {
+                state.since = now;
+            }
+            state.active = true;
+            state.value = Some(action.value.clone());
+        }
+
+        for state in actions.values_mut() {
+            if !state.active {
+                state.value = None;
+            }
+        }
+    }
+
+    fn with<T>(&self, action: &str, default: T, f: impl FnOnce(&ActionState) -> T) -> T {
+        self.actions.read().unwrap().get(action).map(f).unwrap_or(default)
+    }
+
+    /// Triggered this dispatch but not the one before
+    pub fn pressed(&self, action: &str) -> bool {
+        self.with(action, false, |state| state.active && !state.previous)
+    }
+
+    /// Triggered this dispatch
+    pub fn held(&self, action: &str) -> bool {
+        self.with(action, false, |state| state.active)
+    }
+
+    /// Triggered the dispatch before but not this one
+    pub fn released(&self, action: &str) -> bool {
+        self.with(action, false, |state| !state.active && state.previous)
+    }
+
+    /// How long the action has been triggered on consecutive dispatches
+    pub fn held_duration(&self, action: &str) -> Duration {
+        self.with(action, Duration::ZERO, |state| if state.active {
+            state.since.elapsed()
+        } else {
+            Duration::ZERO
+        })
+    }
+
+    /// The latest value, if the action was triggered this dispatch
+    pub fn value(&self, action: &str) -> Option<action::Value> {
+        self.with(action, None, |state| state.value.clone())
+    }
 }
@@ -27,6 +27,26 @@ pub enum Button {
     Touchpad
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Axis {
+    LeftX,
+    LeftY,
+    RightX,
+    RightY,
+    LeftTrigger,
+    RightTrigger,
+}
+
+/// Which manufacturer's button names and glyphs to show
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum Family {
+    #[default]
+    Generic,
+    Xbox,
+    PlayStation,
+    Switch,
+}
+
 #[derive(Debug, Copy, Clone)]
 pub struct Stick {
     pub direction: Vec2,
@@ -38,6 +58,46 @@ pub struct Trigger {
     pub amount: f32,
 }
 
+/// Maps a normalised input in [0, 1] onto the output range [0, 1]
+#[derive(Debug, Copy, Clone)]
+pub enum Curve {
+    Linear,
+    Quadratic,
+    Custom(fn(f32) -> f32),
+}
+
+/// All values are in normalised stick units. Anything inside `radial` (by magnitude) or
+/// `axial` (per axis) is ignored, and anything past `outer` is treated as fully deflected
+#[derive(Debug, Copy, Clone)]
+pub struct DeadZone {
+    pub radial: f32,
+    pub axial: f32,
+    pub outer: f32,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct StickSettings {
+    pub dead_zone: DeadZone,
+    pub curve: Curve,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct TriggerSettings {
+    pub dead_zone: f32,
+    pub outer: f32,
+    pub curve: Curve,
+    /// Processed amount at which the trigger counts as a pressed button
+    pub threshold: f32,
+}
+
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Settings {
+    pub left_stick: StickSettings,
+    pub right_stick: StickSettings,
+    pub left_trigger: TriggerSettings,
+    pub right_trigger: TriggerSettings,
+}
+
 #[derive(Debug, Clone)]
 pub struct Controller {
     pub pressed_buttons: HashMap<Button, Instant>,
@@ -45,78 +105,133 @@ pub struct Controller {
     pub right_stick: Stick,
     pub left_trigger: Trigger,
     pub right_trigger: Trigger,
+    settings: Settings,
+    raw_left_stick: Vec2,
+    raw_right_stick: Vec2,
+    raw_left_trigger: f32,
+    raw_right_trigger: f32,
 }
 
 impl Controller {
     pub fn new() -> Controller {
+        Controller::with_settings(Settings::default())
+    }
+
+    pub fn with_settings(settings: Settings) -> Controller {
         Controller {
             pressed_buttons: HashMap::new(),
             left_stick: Stick::new(),
             right_stick: Stick::new(),
             left_trigger: Trigger::new(),
             right_trigger: Trigger::new(),
+            settings,
+            raw_left_stick: Vec2::zeros(),
+            raw_right_stick: Vec2::zeros(),
+            raw_left_trigger: 0.0,
+            raw_right_trigger: 0.0,
         }
     }
 
+    pub fn settings(&self) -> Settings {
+        self.settings
+    }
+
+    /// Replace the processing settings, reprocessing the last raw values so state stays consistent
+    pub fn set_settings(&mut self, settings: Settings) {
+        self.settings = settings;
+        self.left_stick = self.settings.left_stick.process(self.raw_left_stick);
+        self.right_stick = self.settings.right_stick.process(self.raw_right_stick);
+        self.left_trigger = self.settings.left_trigger.process(self.raw_left_trigger);
+        self.right_trigger = self.settings.right_trigger.process(self.raw_right_trigger);
+        self.update_trigger_button(Button::LeftTrigger);
+        self.update_trigger_button(Button::RightTrigger);
+    }
+
+    fn update_trigger_button(&mut self, button: Button) {
+        let (trigger, settings) = match button {
+            Button::LeftTrigger => (self.left_trigger, self.settings.left_trigger),
+            Button::RightTrigger => (self.right_trigger, self.settings.right_trigger),
+            _ => return
+        };
+        if trigger.amount >= settings.threshold && trigger.amount > 0.0 {
+            self.pressed_buttons.entry(button).or_insert_with(Instant::now);
+        } else {
+            self.pressed_buttons.remove(&button);
+        }
+    }
+
+    /// Held buttons, along with any stick or trigger that is away from rest
     pub fn held(&self) -> Vec<event::Controller> {
-        self.pressed_buttons.keys()
+        let mut held: Vec<event::Controller> = self.pressed_buttons.keys()
             .map(|button| event::Controller::Button {
                 state: event::Binary::Hold,
                 button: *button
             })
-            .collect()
+            .collect();
+        if self.left_stick.amount > 0.0 {
+            held.push(event::Controller::LeftStick(self.left_stick));
+        }
+        if self.right_stick.amount > 0.0 {
+            held.push(event::Controller::RightStick(self.right_stick));
+        }
+        if self.left_trigger.amount > 0.0 {
+            held.push(event::Controller::LeftTrigger(self.left_trigger));
+        }
+        if self.right_trigger.amount > 0.0 {
+            held.push(event::Controller::RightTrigger(self.right_trigger));
+        }
+        held
     }
 
-    pub fn press(&mut self, button: sdl2::controller::Button) -> event::Controller {
-        self.pressed_buttons.insert(button.into(), Instant::now());
+    pub fn press(&mut self, button: Button) -> event::Controller {
+        self.pressed_buttons.insert(button, Instant::now());
         event::Controller::Button {
             state: event::Binary::Single,
-            button: button.into()
+            button
         }
     }
-    pub fn release(&mut self, button: sdl2::controller::Button) -> event::Controller {
-        self.pressed_buttons.remove(&button.into());
+    pub fn release(&mut self, button: Button) -> event::Controller {
+        self.pressed_buttons.remove(&button);
         event::Controller::Button {
             state: event::Binary::Release,
-            button: button.into()
+            button
         }
     }
 
-    pub fn axis(&mut self, axis: sdl2::controller::Axis, amount: i16) -> event::Controller {
-        let amount = (amount as f32) / (i16::MAX as f32);
+    /// `amount` is normalised, in [-1, 1] for sticks and [0, 1] for triggers
+    pub fn axis(&mut self, axis: Axis, amount: f32) -> event::Controller {
+        let amount = amount.clamp(-1.0, 1.0);
         match axis {
-            sdl2::controller::Axis::LeftX => {
-                self.left_stick.direction.x = amount;
+            Axis::LeftX => {
+                self.raw_left_stick.x = amount;
+                self.left_stick = self.settings.left_stick.process(self.raw_left_stick);
                 event::Controller::LeftStick(self.left_stick)
             },
-            sdl2::controller::Axis::LeftY => {
-                self.left_stick.direction.y = amount;
+            Axis::LeftY => {
+                self.raw_left_stick.y = amount;
+                self.left_stick = self.settings.left_stick.process(self.raw_left_stick);
                 event::Controller::LeftStick(self.left_stick)
             },
-            sdl2::controller::Axis::RightX => {
-                self.right_stick.direction.x = amount;
+            Axis::RightX => {
+                self.raw_right_stick.x = amount;
+                self.right_stick = self.settings.right_stick.process(self.raw_right_stick);
                 event::Controller::RightStick(self.right_stick)
             },
-            sdl2::controller::Axis::RightY => {
-                self.right_stick.direction.y = amount;
+            Axis::RightY => {
+                self.raw_right_stick.y = amount;
+                self.right_stick = self.settings.right_stick.process(self.raw_right_stick);
                 event::Controller::RightStick(self.right_stick)
             },
-            sdl2::controller::Axis::TriggerLeft => {
-                self.left_trigger.amount = amount;
-                if amount > 0.5 {
-                    self.pressed_buttons.insert(Button::LeftTrigger, Instant::now());
-                } else {
-                    self.pressed_buttons.remove(&Button::LeftTrigger);
-                }
+            Axis::LeftTrigger => {
+                self.raw_left_trigger = amount;
+                self.left_trigger = self.settings.left_trigger.process(amount);
+                self.update_trigger_button(Button::LeftTrigger);
                 event::Controller::LeftTrigger(self.left_trigger)
             },
-            sdl2::controller::Axis::TriggerRight => {
-                self.right_trigger.amount = amount;
-                if amount > 0.5 {
-                    self.pressed_buttons.insert(Button::RightTrigger, Instant::now());
-                } else {
-                    self.pressed_buttons.remove(&Button::RightTrigger);
-                }
+            Axis::RightTrigger => {
+                self.raw_right_trigger = amount;
+                self.right_trigger = self.settings.right_trigger.process(amount);
+                self.update_trigger_button(Button::RightTrigger);
                 event::Controller::RightTrigger(self.right_trigger)
             },
         }
@@ -147,6 +262,36 @@ impl From<sdl2::controller::Button> for Button {
     }
 }
 
+impl From<sdl2::controller::Axis> for Axis {
+    fn from(axis: sdl2::controller::Axis) -> Axis {
+        match axis {
+            sdl2::controller::Axis::LeftX => Axis::LeftX,
+            sdl2::controller::Axis::LeftY => Axis::LeftY,
+            sdl2::controller::Axis::RightX => Axis::RightX,
+            sdl2::controller::Axis::RightY => Axis::RightY,
+            sdl2::controller::Axis::TriggerLeft => Axis::LeftTrigger,
+            sdl2::controller::Axis::TriggerRight => Axis::RightTrigger,
+        }
+    }
+}
+
+impl Family {
+    /// Guesses the family from the name SDL reports for the controller
+    pub fn from_name(name: &str) -> Family {
+        let name = name.to_lowercase();
+        if name.contains("xbox") || name.contains("xinput") {
+            Family::Xbox
+        } else if name.contains("playstation") || name.contains("dualshock")
+            || name.contains("dualsense") || name.contains("ps3") || name.contains("ps4") || name.contains("ps5") {
+            Family::PlayStation
+        } else if name.contains("nintendo") || name.contains("switch") || name.contains("joy-con") {
+            Family::Switch
+        } else {
+            Family::Generic
+        }
+    }
+}
+
 impl Stick {
     pub(super) fn new() -> Stick {
         Stick {
@@ -163,3 +308,166 @@ impl Trigger {
         }
     }
 }
+
+impl Curve {
+    pub fn apply(&self, amount: f32) -> f32 {
+        let amount = amount.clamp(0.0, 1.0);
+        match self {
+            Curve::Linear => amount,
+            Curve::Quadratic => amount * amount,
+            Curve::Custom(curve) => curve(amount).clamp(0.0, 1.0),
+        }
+    }
+}
+
+impl DeadZone {
+    fn axial(&self, amount: f32) -> f32 {
+        if amount.abs() <= self.axial {
+            0.0
+        } else {
+            amount.signum() * (amount.abs() - self.axial) / (1.0 - self.axial)
+        }
+    }
+}
+
+impl Default for DeadZone {
+    fn default() -> DeadZone {
+        DeadZone {
+            radial: 0.15,
+            axial: 0.0,
+            outer: 0.95
+        }
+    }
+}
+
+impl StickSettings {
+    pub fn process(&self, raw: Vec2) -> Stick {
+        let axial = Vec2::new(self.dead_zone.axial(raw.x), self.dead_zone.axial(raw.y));
+        let magnitude = axial.magnitude();
+        if magnitude <= self.dead_zone.radial || magnitude == 0.0 {
+            return Stick::new();
+        }
+
+        let range = (self.dead_zone.outer - self.dead_zone.radial).max(f32::EPSILON);
+        let amount = self.curve.apply((magnitude - self.dead_zone.radial) / range);
+        Stick {
+            direction: axial / magnitude * amount,
+            amount
+        }
+    }
+}
+
+impl Default for StickSettings {
+    fn default() -> StickSettings {
+        StickSettings {
+            dead_zone: DeadZone::default(),
+            curve: Curve::Linear
+        }
+    }
+}
+
+impl TriggerSettings {
+    pub fn process(&self, raw: f32) -> Trigger {
+        if raw <= self.dead_zone {
+            return Trigger::new();
+        }
+
+        let range = (self.outer - self.dead_zone).max(f32::EPSILON);
+        Trigger {
+            amount: self.curve.apply((raw - self.dead_zone) / range)
+        }
+    }
+}
+
+impl Default for TriggerSettings {
+    fn default() -> TriggerSettings {
+        TriggerSettings {
+            dead_zone: 0.05,
+            outer: 1.0,
+            curve: Curve::Linear,
+            threshold: 0.5
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_family_from_name() {
+        assert_eq!(Family::from_name("Xbox Series X Controller"), Family::Xbox);
+        assert_eq!(Family::from_name("PS5 Controller"), Family::PlayStation);
+        assert_eq!(Family::from_name("Nintendo Switch Pro Controller"), Family::Switch);
+        assert_eq!(Family::from_name("8BitDo SN30"), Family::Generic);
+    }
+
+    #[test]
+    fn test_stick_radial_dead_zone() {
+        let settings = StickSettings::default();
+        let stick = settings.process(Vec2::new(0.1, -0.05));
+        assert_eq!(stick.amount, 0.0);
+        assert_eq!(stick.direction, Vec2::zeros());
+    }
+
+    #[test]
+    fn test_stick_outer_saturation() {
+        let settings = StickSettings::default();
+        let stick = settings.process(Vec2::new(0.0, 0.97));
+        assert!((stick.amount - 1.0).abs() < 0.0001);
+        assert!((stick.direction.y - 1.0).abs() < 0.0001);
+    }
+
+    #[test]
+    fn test_stick_rescales_past_dead_zone() {
+        let settings = StickSettings::default();
+        let halfway = (settings.dead_zone.radial + settings.dead_zone.outer) * 0.5;
+        let stick = settings.process(Vec2::new(halfway, 0.0));
+        assert!((stick.amount - 0.5).abs() < 0.0001);
+        assert!((stick.direction.x - 0.5).abs() < 0.0001);
+        assert_eq!(stick.direction.y, 0.0);
+    }
+
+    #[test]
+    fn test_stick_axial_dead_zone() {
+        let settings = StickSettings {
+            dead_zone: DeadZone { radial: 0.0, axial: 0.2, outer: 1.0 },
+            curve: Curve::Linear
+        };
+        let stick = settings.process(Vec2::new(0.15, 1.0));
+        assert_eq!(stick.direction.x, 0.0);
+        assert!((stick.direction.y - 1.0).abs() < 0.0001);
+    }
+
+    #[test]
+    fn test_stick_curves() {
+        let quadratic = StickSettings {
+            dead_zone: DeadZone { radial: 0.0, axial: 0.0, outer: 1.0 },
+            curve: Curve::Quadratic
+        };
+        assert!((quadratic.process(Vec2::new(0.5, 0.0)).amount - 0.25).abs() < 0.0001);
+
+        let custom = StickSettings {
+            curve: Curve::Custom(|amount| amount.sqrt()),
+            ..quadratic
+        };
+        assert!((custom.process(Vec2::new(0.25, 0.0)).amount - 0.5).abs() < 0.0001);
+    }
+
+    #[test]
+    fn test_trigger_threshold() {
+        let mut controller = Controller::with_settings(Settings {
+            left_trigger: TriggerSettings { threshold: 0.9, ..TriggerSettings::default() },
+            ..Settings::default()
+        });
+
+        controller.axis(Axis::LeftTrigger, 0.5);
+        assert!(!controller.pressed_buttons.contains_key(&Button::LeftTrigger));
+
+        controller.axis(Axis::LeftTrigger, 1.0);
+        assert!(controller.pressed_buttons.contains_key(&Button::LeftTrigger));
+
+        controller.axis(Axis::LeftTrigger, 0.0);
+        assert!(!controller.pressed_buttons.contains_key(&Button::LeftTrigger));
+    }
+}
@@ -0,0 +1,130 @@
+use nalgebra_glm::{ IVec2, Vec2 };
+
+use crate::event::{ Channel, Event };
+use crate::input::{ self, controller, keyboard, mouse };
+
+/// Device independent input, produced either from SDL or from a virtual device
+#[derive(Debug, Clone)]
+pub enum Input {
+    KeyPress(keyboard::Scancode),
+    KeyRelease(keyboard::Scancode),
+    MousePress(mouse::Button),
+    MouseRelease(mouse::Button),
+    MouseMotion { position: IVec2, delta: IVec2 },
+    MouseScroll(f32),
+    Text(String),
+    Composition { text: String, start: i32, length: i32 },
+    ControllerConnect(input::Id),
+    ControllerDisconnect(input::Id),
+    ControllerPress(input::Id, controller::Button),
+    ControllerRelease(input::Id, controller::Button),
+    ControllerAxis(input::Id, controller::Axis, f32),
+}
+
+/// Virtual controllers are given ids above anything SDL will hand out
+pub(super) const VIRTUAL_CONTROLLER_BASE: input::Id = 1 << 32;
+
+pub struct VirtualKeyboard {
+    channel: Channel<Input, ()>,
+}
+
+pub struct VirtualMouse {
+    channel: Channel<Input, ()>,
+    position: IVec2,
+}
+
+pub struct VirtualGamepad {
+    channel: Channel<Input, ()>,
+    id: input::Id,
+}
+
+impl VirtualKeyboard {
+    pub(super) fn new(channel: Channel<Input, ()>) -> VirtualKeyboard {
+        VirtualKeyboard { channel }
+    }
+
+    pub fn press(&mut self, key: keyboard::Scancode) {
+        self.channel.push(Event::new(Input::KeyPress(key)));
+    }
+
+    pub fn release(&mut self, key: keyboard::Scancode) {
+        self.channel.push(Event::new(Input::KeyRelease(key)));
+    }
+
+    pub fn type_text(&mut self, text: impl Into<String>) {
+        self.channel.push(Event::new(Input::Text(text.into())));
+    }
+
+    pub fn compose(&mut self, text: impl Into<String>, start: i32, length: i32) {
+        self.channel.push(Event::new(Input::Composition { text: text.into(), start, length }));
+    }
+}
+
+impl VirtualMouse {
+    pub(super) fn new(channel: Channel<Input, ()>) -> VirtualMouse {
+        VirtualMouse {
+            channel,
+            position: IVec2::zeros()
+        }
+    }
+
+    pub fn press(&mut self, button: mouse::Button) {
+        self.channel.push(Event::new(Input::MousePress(button)));
+    }
+
+    pub fn release(&mut self, button: mouse::Button) {
+        self.channel.push(Event::new(Input::MouseRelease(button)));
+    }
+
+    pub fn move_by(&mut self, delta: IVec2) {
+        self.position += delta;
+        self.channel.push(Event::new(Input::MouseMotion { position: self.position, delta }));
+    }
+
+    pub fn move_to(&mut self, position: IVec2) {
+        let delta = position - self.position;
+        self.move_by(delta);
+    }
+
+    pub fn scroll(&mut self, amount: f32) {
+        self.channel.push(Event::new(Input::MouseScroll(amount)));
+    }
+}
+
+impl VirtualGamepad {
+    pub(super) fn new(mut channel: Channel<Input, ()>, id: input::Id) -> VirtualGamepad {
+        channel.push(Event::new(Input::ControllerConnect(id)));
+        VirtualGamepad { channel, id }
+    }
+
+    pub fn id(&self) -> input::Id {
+        self.id
+    }
+
+    pub fn press(&mut self, button: controller::Button) {
+        self.channel.push(Event::new(Input::ControllerPress(self.id, button)));
+    }
+
+    pub fn release(&mut self, button: controller::Button) {
+        self.channel.push(Event::new(Input::ControllerRelease(self.id, button)));
+    }
+
+    /// Axis values are normalised, in [-1, 1] for sticks and [0, 1] for triggers
+    pub fn axis(&mut self, axis: controller::Axis, amount: f32) {
+        self.channel.push(Event::new(Input::ControllerAxis(self.id, axis, amount)));
+    }
+
+    pub fn move_left_stick(&mut self, direction: Vec2) {
+        self.axis(controller::Axis::LeftX, direction.x);
+        self.axis(controller::Axis::LeftY, direction.y);
+    }
+
+    pub fn move_right_stick(&mut self, direction: Vec2) {
+        self.axis(controller::Axis::RightX, direction.x);
+        self.axis(controller::Axis::RightY, direction.y);
+    }
+
+    pub fn disconnect(mut self) {
+        self.channel.push(Event::new(Input::ControllerDisconnect(self.id)));
+    }
+}
@@ -0,0 +1,14 @@
+use thiserror::Error;
+use crate::input;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum Output {
+    #[error("No controller with id `{0}` is connected")]
+    NoController(input::Id),
+    #[error("Controller `{0}` has no output device")]
+    NoDevice(input::Id),
+    #[error("Controller does not support {0}")]
+    Unsupported(&'static str),
+    #[error("Device error: {0}")]
+    Device(String),
+}
@@ -1,4 +1,4 @@
-use nalgebra_glm::IVec2;
+use nalgebra_glm::{ IVec2, Vec2 };
 use crate::input::{ controller, keyboard, mouse };
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
@@ -21,7 +21,8 @@ pub enum Controller {
 #[derive(Debug, Copy, Clone)]
 pub enum Mouse {
     Button { state: Binary, button: mouse::Button },
-    Move { position: IVec2, direction: IVec2 },
+    /// `direction` is the raw motion and `delta` is after mouse settings are applied
+    Move { position: IVec2, direction: IVec2, delta: Vec2 },
     Scroll { scroll: f32 },
 }
 
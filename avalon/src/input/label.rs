@@ -0,0 +1,205 @@
+use crate::input::{ action, controller, keyboard, mouse };
+
+/// A binding as it should be shown to the player. `glyph` names an icon, such as `xbox_a` or `key_e`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Label {
+    pub text: String,
+    pub glyph: String,
+}
+
+impl Label {
+    fn new(text: impl Into<String>, glyph: impl Into<String>) -> Label {
+        Label {
+            text: text.into(),
+            glyph: glyph.into(),
+        }
+    }
+}
+
+fn glyph_name(text: &str) -> String {
+    text.to_lowercase().split_whitespace().collect::<Vec<_>>().join("_")
+}
+
+/// Scancodes are named by the key at that position on the active layout, keycodes by the key itself
+pub fn key(key: action::Key) -> Label {
+    let text = match key {
+        action::Key::Scancode(scancode) => keyboard::Keycode::from_scancode(scancode)
+            .map(|keycode| keycode.name())
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| scancode.name().to_string()),
+        action::Key::Keycode(keycode) => keycode.name(),
+    };
+    let glyph = format!("key_{}", glyph_name(&text));
+    Label::new(text, glyph)
+}
+
+pub fn mouse_button(button: mouse::Button) -> Label {
+    let (text, glyph) = match button {
+        mouse::Button::Unknown => ("Mouse", "mouse"),
+        mouse::Button::Left => ("Left Click", "mouse_left"),
+        mouse::Button::Right => ("Right Click", "mouse_right"),
+        mouse::Button::Middle => ("Middle Click", "mouse_middle"),
+        mouse::Button::X1 => ("Mouse 4", "mouse_x1"),
+        mouse::Button::X2 => ("Mouse 5", "mouse_x2"),
+    };
+    Label::new(text, glyph)
+}
+
+fn family_prefix(family: controller::Family) -> &'static str {
+    match family {
+        controller::Family::Generic => "gamepad",
+        controller::Family::Xbox => "xbox",
+        controller::Family::PlayStation => "playstation",
+        controller::Family::Switch => "switch",
+    }
+}
+
+/// SDL maps Nintendo controllers by their printed labels unless told otherwise
+const BUTTON_LABELS_HINT: &str = "SDL_GAMECONTROLLER_USE_BUTTON_LABELS";
+
+/// Has SDL map every controller by position, which `button` relies on. Must be set before controllers are opened
+pub(crate) fn use_positional_buttons() {
+    sdl2::hint::set(BUTTON_LABELS_HINT, "0");
+}
+
+/// Buttons are named by position, so `Button::A`, the bottom face button, is B on a Switch controller.
+/// `input::Engine::new` sets SDL up to report them that way
+pub fn button(button: controller::Button, family: controller::Family) -> Label {
+    use controller::Button;
+    use controller::Family;
+
+    let text = match (family, button) {
+        (_, Button::Unknown) => "?",
+        (Family::PlayStation, Button::A) => "Cross",
+        (Family::PlayStation, Button::B) => "Circle",
+        (Family::PlayStation, Button::X) => "Square",
+        (Family::PlayStation, Button::Y) => "Triangle",
+        (Family::PlayStation, Button::Back) => "Share",
+        (Family::PlayStation, Button::Guide) => "PS",
+        (Family::PlayStation, Button::Start) => "Options",
+        (Family::PlayStation, Button::LeftStick) => "L3",
+        (Family::PlayStation, Button::RightStick) => "R3",
+        (Family::PlayStation, Button::LeftShoulder) => "L1",
+        (Family::PlayStation, Button::RightShoulder) => "R1",
+        (Family::PlayStation, Button::LeftTrigger) => "L2",
+        (Family::PlayStation, Button::RightTrigger) => "R2",
+        (Family::Switch, Button::A) => "B",
+        (Family::Switch, Button::B) => "A",
+        (Family::Switch, Button::X) => "Y",
+        (Family::Switch, Button::Y) => "X",
+        (Family::Switch, Button::Back) => "Minus",
+        (Family::Switch, Button::Guide) => "Home",
+        (Family::Switch, Button::Start) => "Plus",
+        (Family::Switch, Button::LeftShoulder) => "L",
+        (Family::Switch, Button::RightShoulder) => "R",
+        (Family::Switch, Button::LeftTrigger) => "ZL",
+        (Family::Switch, Button::RightTrigger) => "ZR",
+        (Family::Xbox, Button::Back) => "View",
+        (Family::Xbox, Button::Guide) => "Xbox",
+        (Family::Xbox, Button::Start) => "Menu",
+        (_, Button::A) => "A",
+        (_, Button::B) => "B",
+        (_, Button::X) => "X",
+        (_, Button::Y) => "Y",
+        (_, Button::Back) => "Back",
+        (_, Button::Guide) => "Guide",
+        (_, Button::Start) => "Start",
+        (_, Button::LeftStick) => "LS",
+        (_, Button::RightStick) => "RS",
+        (_, Button::LeftShoulder) => "LB",
+        (_, Button::RightShoulder) => "RB",
+        (_, Button::LeftTrigger) => "LT",
+        (_, Button::RightTrigger) => "RT",
+        (_, Button::DPadUp) => "D-Pad Up",
+        (_, Button::DPadDown) => "D-Pad Down",
+        (_, Button::DPadLeft) => "D-Pad Left",
+        (_, Button::DPadRight) => "D-Pad Right",
+        (_, Button::Touchpad) => "Touchpad",
+    };
+    let glyph = format!("{}_{}", family_prefix(family), glyph_name(text));
+    Label::new(text, glyph)
+}
+
+fn analog(text: &str, family: controller::Family) -> Label {
+    Label::new(text, format!("{}_{}", family_prefix(family), glyph_name(text)))
+}
+
+fn source(source: action::Source, family: controller::Family) -> Label {
+    match source {
+        action::Source::Key(key) => self::key(key),
+        action::Source::Mouse(mouse) => mouse_button(mouse),
+        action::Source::Controller(controller) => button(controller, family),
+    }
+}
+
+/// Labels for one binding. Composites made of buttons give one label per button
+pub fn binding(binding: &action::Binding, family: controller::Family) -> Vec<Label> {
+    match *binding {
+        action::Binding::Key(keyboard) => match keyboard {
+            action::Keyboard::Press(key) | action::Keyboard::Release(key) | action::Keyboard::Hold(key) => {
+                vec![self::key(key)]
+            },
+        },
+        action::Binding::Mouse(mouse) => match mouse {
+            action::Mouse::Press(button) | action::Mouse::Release(button) | action::Mouse::Hold(button) => {
+                vec![mouse_button(button)]
+            },
+            action::Mouse::Scroll => vec![Label::new("Scroll", "mouse_scroll")],
+            action::Mouse::Move => vec![Label::new("Mouse", "mouse_move")],
+        },
+        action::Binding::Controller(bound) => match bound {
+            action::Controller::Press(pressed) | action::Controller::Release(pressed) | action::Controller::Hold(pressed) => {
+                vec![button(pressed, family)]
+            },
+            action::Controller::LeftStick => vec![analog("Left Stick", family)],
+            action::Controller::RightStick => vec![analog("Right Stick", family)],
+            action::Controller::LeftTrigger => vec![button(controller::Button::LeftTrigger, family)],
+            action::Controller::RightTrigger => vec![button(controller::Button::RightTrigger, family)],
+        },
+        action::Binding::Axis1d(axis) => match axis {
+            action::Axis1d::Buttons { negative, positive } => vec![source(negative, family), source(positive, family)],
+            action::Axis1d::LeftStickX | action::Axis1d::LeftStickY => vec![analog("Left Stick", family)],
+            action::Axis1d::RightStickX | action::Axis1d::RightStickY => vec![analog("Right Stick", family)],
+            action::Axis1d::LeftTrigger => vec![button(controller::Button::LeftTrigger, family)],
+            action::Axis1d::RightTrigger => vec![button(controller::Button::RightTrigger, family)],
+        },
+        action::Binding::Axis2d(axis) => match axis {
+            action::Axis2d::Buttons { up, down, left, right } => vec![
+                source(up, family),
+                source(left, family),
+                source(down, family),
+                source(right, family),
+            ],
+            action::Axis2d::LeftStick => vec![analog("Left Stick", family)],
+            action::Axis2d::RightStick => vec![analog("Right Stick", family)],
+        },
+    }
+}
+
+/// Labels for every input in the mapping, in the order they were bound
+pub fn mapping(mapping: &action::Mapping, family: controller::Family) -> Vec<Label> {
+    mapping.bindings().iter()
+        .flat_map(|bound| binding(bound, family))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_switch_buttons_by_position() {
+        use controller::{ Button, Family };
+
+        use_positional_buttons();
+        assert_eq!(sdl2::hint::get(BUTTON_LABELS_HINT).as_deref(), Some("0"));
+
+        let text = |button| self::button(button, Family::Switch).text;
+        assert_eq!(text(Button::A), "B");
+        assert_eq!(text(Button::B), "A");
+        assert_eq!(text(Button::X), "Y");
+        assert_eq!(text(Button::Y), "X");
+        assert_eq!(self::button(Button::A, Family::Xbox).text, "A");
+        assert_eq!(self::button(Button::A, Family::PlayStation).glyph, "playstation_cross");
+    }
+}
@@ -1,11 +1,20 @@
 use crate::input::{ self, action, context };
 use crate::event::Channel;
-use itertools::Itertools;
 use std::collections::HashSet;
 
 pub struct Layer {
     pub(super) name: String,
-    pub(super) context_stack: Vec<context::Context>
+    pub(super) context_stack: Vec<context::Context>,
+    pub(super) action_map: Option<action::Map>,
+}
+
+/// Everything a layer hands out in one dispatch
+pub(super) struct Frame<'e> {
+    pub(super) actions: Vec<(input::action::Action, Vec<&'e input::event::Event>)>,
+    /// The same actions with keyboard events removed, for contexts not taking text
+    pub(super) without_keyboard: Vec<(input::action::Action, Vec<&'e input::event::Event>)>,
+    pub(super) text: Vec<input::action::Action>,
+    pub(super) text_mode: bool,
 }
 
 pub struct ContextBuilder<'layer> {
@@ -13,7 +22,9 @@ pub struct ContextBuilder<'layer> {
     allowed_actions: HashSet<action::ActionId>,
     name: Option<String>,
     priority: context::Priority,
-    blocking: context::Block
+    blocking: context::Block,
+    text_input: bool,
+    enabled: bool
 }
 
 impl<'layer> ContextBuilder<'layer> {
@@ -32,51 +43,204 @@ impl<'layer> ContextBuilder<'layer> {
         self
     }
 
+    /// Receive text and composition actions, and stop keyboard bindings reaching contexts without text input
+    pub fn text_input(mut self) -> ContextBuilder<'layer> {
+        self.text_input = true;
+        self
+    }
+
+    /// Start the context disabled, to be enabled by name later
+    pub fn disabled(mut self) -> ContextBuilder<'layer> {
+        self.enabled = false;
+        self
+    }
+
     pub fn action(mut self, action: impl Into<action::ActionId>) -> ContextBuilder<'layer> {
         self.allowed_actions.insert(action.into());
         self
     }
 
-    pub fn build(self) -> Channel<action::Action, &'static str> {
+    /// Builds the context without an event channel, for systems that only poll its state
+    pub fn build_polled(mut self) -> context::State {
+        let context = self.create();
+        let state = context.state();
+        self.layer.context_stack.push(context);
+        state
+    }
+
+    fn create(&mut self) -> context::Context {
         let mut context = context::Context::new(
             self.priority,
             self.blocking,
-            self.allowed_actions
+            std::mem::take(&mut self.allowed_actions)
         );
-        context.name = self.name;
+        context.name = self.name.take();
+        context.text_input = self.text_input;
+        context.enabled = self.enabled;
+        context
+    }
+
+    pub fn build(mut self) -> Channel<action::Action, &'static str> {
+        let context = self.create();
         self.layer.context_stack.push(context);
         self.layer.context_stack.last_mut().unwrap().context_handler()
     }
 }
 
 impl Layer {
-    pub(super) fn process_actions(&mut self, actions: Vec<(input::action::Action, Vec<&input::event::Event>)>) {
+    pub(super) fn new(name: String, action_map: Option<action::Map>) -> Layer {
+        Layer {
+            name,
+            context_stack: Vec::new(),
+            action_map
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Enabled contexts in the order they see input: highest priority first, newest first within a priority
+    fn order(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.context_stack.len())
+            .filter(|idx| self.context_stack[*idx].enabled)
+            .collect();
+        order.sort_by(|lhs, rhs| {
+            let lhs_priority = self.context_stack[*lhs].priority;
+            let rhs_priority = self.context_stack[*rhs].priority;
+            rhs_priority.cmp(&lhs_priority).then(rhs.cmp(lhs))
+        });
+        order
+    }
+
+    /// Contexts that will be given input, after blocking is applied
+    fn reachable(&self) -> Vec<usize> {
+        let mut reachable = Vec::new();
         let mut only_critical = false;
-        for context in self.context_stack.iter_mut()
-            .rev()
-            .take_while_inclusive(|context| !matches!(context.block, context::Block::All)) {
-            if only_critical {
-                if let context::Priority::Low = context.priority {
-                    continue;
-                }
+        for idx in self.order() {
+            let context = &self.context_stack[idx];
+            if only_critical && !context.priority.is_critical() {
+                continue;
             }
 
-            // send slice of actions to context
-            context.process(&actions);
+            reachable.push(idx);
+            match context.block {
+                context::Block::All => break,
+                context::Block::NonCritical => only_critical = true,
+                context::Block::None => {},
+            }
+        }
+        reachable
+    }
 
-            if let context::Block::NonCritical = context.block {
-                only_critical = true;
+    pub(super) fn process_actions(&mut self, frame: Frame) {
+        let reachable = self.reachable();
+        for (idx, context) in self.context_stack.iter_mut().enumerate() {
+            if !reachable.contains(&idx) {
+                context.idle();
+            }
+        }
+
+        for idx in reachable {
+            let context = &mut self.context_stack[idx];
+            // send slice of actions to context
+            if context.text_input || !frame.text_mode {
+                context.process(&frame.actions, &frame.text);
+            } else {
+                context.process(&frame.without_keyboard, &frame.text);
             }
         }
     }
 
+    pub(super) fn idle(&mut self) {
+        for context in self.context_stack.iter_mut() {
+            context.idle();
+        }
+    }
+
+    fn named_mut<'a>(&'a mut self, name: &'a str) -> impl Iterator<Item = &'a mut context::Context> + 'a {
+        self.context_stack.iter_mut()
+            .filter(move |context| context.name.as_deref() == Some(name))
+    }
+
+    pub fn state(&self, name: &str) -> Option<context::State> {
+        self.context_stack.iter()
+            .find(|context| context.name.as_deref() == Some(name))
+            .map(|context| context.state())
+    }
+
     pub fn context_handler<'layer>(&'layer mut self) -> ContextBuilder<'layer> {
         ContextBuilder {
             layer: self,
             allowed_actions: HashSet::new(),
             name: None,
-            priority: context::Priority::Low,
-            blocking: context::Block::None
+            priority: context::Priority::LOW,
+            blocking: context::Block::None,
+            text_input: false,
+            enabled: true
+        }
+    }
+
+    /// Whether any context that would receive input is taking text
+    pub fn wants_text(&self) -> bool {
+        self.reachable().into_iter().any(|idx| self.context_stack[idx].text_input)
+    }
+
+    pub fn set_text_input(&mut self, name: &str, enabled: bool) -> bool {
+        let mut found = false;
+        for context in self.named_mut(name) {
+            context.text_input = enabled;
+            found = true;
+        }
+        found
+    }
+
+    pub fn enable(&mut self, name: &str) -> bool {
+        let mut found = false;
+        for context in self.named_mut(name) {
+            context.enabled = true;
+            found = true;
         }
+        found
+    }
+
+    pub fn disable(&mut self, name: &str) -> bool {
+        let mut found = false;
+        for context in self.named_mut(name) {
+            context.enabled = false;
+            found = true;
+        }
+        found
+    }
+
+    pub fn is_enabled(&self, name: &str) -> Option<bool> {
+        self.context_stack.iter()
+            .find(|context| context.name.as_deref() == Some(name))
+            .map(|context| context.enabled)
+    }
+
+    pub fn set_priority(&mut self, name: &str, priority: context::Priority) -> bool {
+        let mut found = false;
+        for context in self.named_mut(name) {
+            context.priority = priority;
+            found = true;
+        }
+        found
+    }
+
+    /// Removes every context with the name. Their channels stop receiving actions
+    pub fn remove(&mut self, name: &str) -> bool {
+        let count = self.context_stack.len();
+        self.context_stack.retain(|context| context.name.as_deref() != Some(name));
+        count != self.context_stack.len()
+    }
+
+    pub fn action_map(&self) -> Option<&action::Map> {
+        self.action_map.as_ref()
+    }
+
+    /// Replaces the bindings this layer uses. `None` falls back to the engine's action map
+    pub fn set_action_map(&mut self, action_map: Option<action::Map>) {
+        self.action_map = action_map;
     }
 }
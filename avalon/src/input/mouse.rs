@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 use std::time::Instant;
-use nalgebra_glm::{ IVec2, vec2 };
+use nalgebra_glm::{ IVec2, Vec2, vec2 };
 
 use crate::input::event;
 
@@ -14,12 +14,26 @@ pub enum Button {
     X2
 }
 
+#[derive(Debug, Copy, Clone)]
+pub struct Settings {
+    /// Scale applied to raw motion, per axis
+    pub sensitivity: Vec2,
+    /// Extra scale per pixel of motion in a poll. Zero disables acceleration
+    pub acceleration: f32,
+    pub invert_x: bool,
+    pub invert_y: bool,
+}
+
 #[derive(Debug, Clone)]
 pub struct Mouse {
     pub pressed_buttons: HashMap<Button, Instant>,
     pub position: IVec2,
+    /// Raw motion accumulated since the last poll
     pub direction: IVec2,
-    pub scroll: f32
+    /// `direction` after sensitivity, acceleration and inversion
+    pub delta: Vec2,
+    pub scroll: f32,
+    pub settings: Settings,
 }
 
 impl Mouse {
@@ -28,10 +42,18 @@ impl Mouse {
             pressed_buttons: HashMap::new(),
             position: IVec2::zeros(),
             direction: IVec2::zeros(),
-            scroll: 0.0
+            delta: Vec2::zeros(),
+            scroll: 0.0,
+            settings: Settings::default(),
         }
     }
 
+    pub(super) fn reset_motion(&mut self) {
+        self.direction = IVec2::zeros();
+        self.delta = Vec2::zeros();
+        self.scroll = 0.0;
+    }
+
     pub fn held(&self) -> Vec<event::Mouse> {
         self.pressed_buttons.keys()
             .map(|button| event::Mouse::Button {
@@ -41,50 +63,83 @@ impl Mouse {
             .collect()
     }
 
-    pub fn press(&mut self, button: sdl2::mouse::MouseButton) -> event::Mouse {
+    pub fn press(&mut self, button: Button) -> event::Mouse {
         let _t1 = Instant::now();
-        let t0 = self.pressed_buttons.insert(button.into(), Instant::now());
+        let t0 = self.pressed_buttons.insert(button, Instant::now());
         if let Some(_t0) = t0 {
             event::Mouse::Button {
                 state: event::Binary::Single,
-                button: button.into()
+                button
             }
         } else {
             event::Mouse::Button {
                 state: event::Binary::Single,
-                button: button.into()
+                button
             }
         }
     }
-    pub fn release(&mut self, button: sdl2::mouse::MouseButton) -> event::Mouse {
-        self.pressed_buttons.remove(&button.into());
+    pub fn release(&mut self, button: Button) -> event::Mouse {
+        self.pressed_buttons.remove(&button);
         event::Mouse::Button {
             state: event::Binary::Release,
-            button: button.into()
+            button
         }
     }
 
+    /// Motion accumulates until the next poll, so every event carries the total so far
     pub fn motion(&mut self, position_x: i32, position_y: i32, direction_x: i32, direction_y: i32) -> event::Mouse {
         self.position = vec2(position_x, position_y);
-        self.direction = vec2(direction_x, direction_y);
+        self.direction += vec2(direction_x, direction_y);
+        self.delta = self.settings.process(self.direction);
         event::Mouse::Move {
             position: self.position,
-            direction: self.direction
+            direction: self.direction,
+            delta: self.delta
         }
     }
 
-    pub fn scroll(&mut self, scroll: f32, direction: sdl2::mouse::MouseWheelDirection) -> event::Mouse {
-        self.scroll += scroll * match direction {
-            sdl2::mouse::MouseWheelDirection::Normal => 1.0,
-            sdl2::mouse::MouseWheelDirection::Flipped => -1.0,
-            sdl2::mouse::MouseWheelDirection::Unknown(_) => 1.0,
-        };
+    pub fn scroll(&mut self, scroll: f32) -> event::Mouse {
+        self.scroll += scroll;
         event::Mouse::Scroll {
             scroll: self.scroll
         }
     }
 }
 
+impl Settings {
+    pub fn process(&self, direction: IVec2) -> Vec2 {
+        let raw: Vec2 = direction.cast();
+        let acceleration = 1.0 + self.acceleration.max(0.0) * raw.magnitude();
+        let mut delta = raw.component_mul(&self.sensitivity) * acceleration;
+        if self.invert_x {
+            delta.x = -delta.x;
+        }
+        if self.invert_y {
+            delta.y = -delta.y;
+        }
+        delta
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Settings {
+        Settings {
+            sensitivity: vec2(1.0, 1.0),
+            acceleration: 0.0,
+            invert_x: false,
+            invert_y: false,
+        }
+    }
+}
+
+pub(super) fn wheel_scale(direction: sdl2::mouse::MouseWheelDirection) -> f32 {
+    match direction {
+        sdl2::mouse::MouseWheelDirection::Normal => 1.0,
+        sdl2::mouse::MouseWheelDirection::Flipped => -1.0,
+        sdl2::mouse::MouseWheelDirection::Unknown(_) => 1.0,
+    }
+}
+
 impl From<sdl2::mouse::MouseButton> for Button {
     fn from(button: sdl2::mouse::MouseButton) -> Button {
         match button {
@@ -0,0 +1,121 @@
+use std::sync::{ Arc, Mutex };
+use std::time::Duration;
+
+use crate::input::error;
+
+/// Motor intensities are in [0, 1]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Rumble {
+    pub low_frequency: f32,
+    pub high_frequency: f32,
+    pub duration: Duration,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TriggerRumble {
+    pub left: f32,
+    pub right: f32,
+    pub duration: Duration,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Colour {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+}
+
+/// Feedback sent back to a controller
+pub trait Backend {
+    fn rumble(&mut self, rumble: Rumble) -> Result<(), error::Output>;
+    fn rumble_triggers(&mut self, rumble: TriggerRumble) -> Result<(), error::Output>;
+    fn set_led(&mut self, colour: Colour) -> Result<(), error::Output>;
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Command {
+    Rumble(Rumble),
+    TriggerRumble(TriggerRumble),
+    Led(Colour),
+}
+
+/// Records every command sent to it. Clones share the same log
+#[derive(Debug, Clone, Default)]
+pub struct Recorder {
+    commands: Arc<Mutex<Vec<Command>>>,
+}
+
+fn intensity(amount: f32) -> u16 {
+    (amount.clamp(0.0, 1.0) * u16::MAX as f32).round() as u16
+}
+
+fn duration_ms(duration: Duration) -> u32 {
+    duration.as_millis().min(u32::MAX as u128) as u32
+}
+
+impl Backend for sdl2::controller::GameController {
+    fn rumble(&mut self, rumble: Rumble) -> Result<(), error::Output> {
+        if !self.has_rumble() {
+            return Err(error::Output::Unsupported("rumble"));
+        }
+        self.set_rumble(
+            intensity(rumble.low_frequency),
+            intensity(rumble.high_frequency),
+            duration_ms(rumble.duration)
+        ).map_err(|e| error::Output::Device(e.to_string()))
+    }
+
+    fn rumble_triggers(&mut self, rumble: TriggerRumble) -> Result<(), error::Output> {
+        if !self.has_rumble_triggers() {
+            return Err(error::Output::Unsupported("trigger rumble"));
+        }
+        self.set_rumble_triggers(
+            intensity(rumble.left),
+            intensity(rumble.right),
+            duration_ms(rumble.duration)
+        ).map_err(|e| error::Output::Device(e.to_string()))
+    }
+
+    fn set_led(&mut self, colour: Colour) -> Result<(), error::Output> {
+        if !self.has_led() {
+            return Err(error::Output::Unsupported("LED"));
+        }
+        sdl2::controller::GameController::set_led(self, colour.red, colour.green, colour.blue)
+            .map_err(|e| error::Output::Device(e.to_string()))
+    }
+}
+
+impl Recorder {
+    pub fn new() -> Recorder {
+        Recorder::default()
+    }
+
+    pub fn commands(&self) -> Vec<Command> {
+        self.commands.lock().unwrap().clone()
+    }
+
+    pub fn clear(&self) {
+        self.commands.lock().unwrap().clear();
+    }
+
+    fn record(&self, command: Command) {
+        self.commands.lock().unwrap().push(command);
+    }
+}
+
+impl Backend for Recorder {
+    fn rumble(&mut self, rumble: Rumble) -> Result<(), error::Output> {
+        self.record(Command::Rumble(rumble));
+        Ok(())
+    }
+
+    fn rumble_triggers(&mut self, rumble: TriggerRumble) -> Result<(), error::Output> {
+        self.record(Command::TriggerRumble(rumble));
+        Ok(())
+    }
+
+    fn set_led(&mut self, colour: Colour) -> Result<(), error::Output> {
+        self.record(Command::Led(colour));
+        Ok(())
+    }
+}
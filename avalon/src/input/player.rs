@@ -0,0 +1,91 @@
+use std::collections::{ HashSet, HashMap };
+
+use crate::input::{ self, action, layer };
+
+pub type Id = u32;
+
+/// The player that owns the keyboard, mouse and every controller connected at startup
+pub const PRIMARY: Id = 0;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Device {
+    KeyboardMouse,
+    Controller(input::Id),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Hotplug {
+    /// Newly connected controllers are left unassigned
+    Manual,
+    /// Newly connected controllers go to a player who lost one, otherwise to the first player without one
+    Reassign,
+}
+
+pub struct Player {
+    pub(super) id: Id,
+    pub(super) devices: HashSet<Device>,
+    pub(super) layers: Vec<layer::Layer>,
+    pub(super) pop_layer: bool,
+}
+
+impl Player {
+    pub(super) fn new(id: Id) -> Player {
+        Player {
+            id,
+            devices: HashSet::new(),
+            layers: Vec::new(),
+            pop_layer: false,
+        }
+    }
+
+    pub fn id(&self) -> Id {
+        self.id
+    }
+
+    pub fn devices(&self) -> impl Iterator<Item = &Device> {
+        self.devices.iter()
+    }
+
+    pub fn owns(&self, device: Device) -> bool {
+        self.devices.contains(&device)
+    }
+
+    pub fn has_controller(&self) -> bool {
+        self.devices.iter().any(|device| matches!(device, Device::Controller(_)))
+    }
+
+    /// Whether the controller was used more recently than the keyboard and mouse
+    pub(super) fn prefers_controller(&self, last_used: &HashMap<Device, input::Timestamp>) -> bool {
+        let mut last_controller = 0;
+        let mut last_kbm = 0;
+        for device in self.devices.iter() {
+            let timestamp = last_used.get(device).copied().unwrap_or(0);
+            match device {
+                Device::KeyboardMouse => last_kbm = last_kbm.max(timestamp),
+                Device::Controller(_) => last_controller = last_controller.max(timestamp),
+            }
+        }
+        last_controller > last_kbm
+    }
+
+    pub fn active_layer(&self) -> Option<&layer::Layer> {
+        self.layers.last()
+    }
+
+    pub fn active_layer_mut(&mut self) -> Option<&mut layer::Layer> {
+        self.layers.last_mut()
+    }
+
+    pub fn push_layer(&mut self, name: impl Into<String>) {
+        self.layers.push(layer::Layer::new(name.into(), None));
+    }
+
+    /// Pushes a layer with its own bindings instead of the engine's action map
+    pub fn push_layer_with_map(&mut self, name: impl Into<String>, action_map: action::Map) {
+        self.layers.push(layer::Layer::new(name.into(), Some(action_map)));
+    }
+
+    pub fn pop_layer(&mut self) {
+        self.pop_layer = true;
+    }
+}
@@ -5,10 +5,13 @@ use nalgebra_glm::Vec3;
 
 #[derive(Debug)]
 struct Gpu {
-    vertex_array_object: State,
+    /// One per level of detail, all drawing from the same buffers
+    vertex_array_objects: Vec<State>,
     position_vbo: gl::types::GLuint,
     extra_vbo: gl::types::GLuint,
     index_buffer: gl::types::GLuint,
+    /// Across all three buffers
+    bytes: usize,
 }
 
 #[derive(Debug)]
@@ -26,107 +29,142 @@ impl Model {
     pub fn extents(&self) -> Vec3 {
         self.max_bounds - self.min_bounds
     }
+
+    /// Levels of detail, counting the full model
+    pub fn lod_count(&self) -> usize {
+        self.gpu.vertex_array_objects.len()
+    }
+
+    /// The model at a level of detail, 0 being full detail. Levels past the coarsest give the coarsest
+    pub fn lod(&self, level: usize) -> &State {
+        &self.gpu.vertex_array_objects[level.min(self.lod_count() - 1)]
+    }
 }
 
-impl asset_library::Asset for Model {}
+impl asset_library::Asset for Model {
+    fn resident_size(&self) -> asset_library::Size {
+        asset_library::Size { cpu: 0, gpu: self.gpu.bytes }
+    }
+}
 impl std::ops::Deref for Model {
     type Target = State;
     fn deref(&self) -> &State {
-        &self.gpu.vertex_array_object
+        &self.gpu.vertex_array_objects[0]
     }
 }
 
 impl From<&PackedModel> for Model {
     fn from(model: &PackedModel) -> Model {
-        let mut gpu = Gpu::new();
+        let mut gpu = Gpu::new(1 + model.lods.len());
 
+        // corners with the same index share attributes, so they are stored once per index
+        let stride = 8 * std::mem::size_of::<f32>();
+        let mut packed_additional = vec![0; stride * model.positions.len()];
         let mut indices = Vec::new();
-        let mut packed_additional = Vec::new();
         for triangle in model.triangles.iter() {
             for vertex in triangle.vertices {
-                packed_additional.extend(vertex.reduced_pack());
+                let offset = stride * vertex.index as usize;
+                packed_additional[offset..offset + stride].copy_from_slice(&vertex.reduced_pack());
                 indices.push(vertex.index);
             }
         }
-        #[allow(clippy::erasing_op)]
-        unsafe {
-            let vao_bind = gpu.vertex_array_object.bind();
-            gl::BindBuffer(gl::ARRAY_BUFFER, gpu.position_vbo);
-            gl::BufferData(
-                gl::ARRAY_BUFFER,
-                (3 * std::mem::size_of::<f32>() * model.positions.len()) as isize,
-                model.positions.iter()
-                    .flat_map(|p| *p)
-                    .collect::<Vec<f32>>()
-                    .as_slice()
-                    .as_ptr() as *const std::ffi::c_void,
-                gl::STATIC_DRAW
-            );
-            gl::VertexAttribPointer(
-                0,
-                3,
-                gl::FLOAT,
-                gl::FALSE,
-                3 * std::mem::size_of::<f32>() as i32,
-                std::ptr::null()
-            );
-            gl::BindBuffer(gl::ARRAY_BUFFER, gpu.extra_vbo);
-            gl::BufferData(
-                gl::ARRAY_BUFFER,
-                packed_additional.len() as isize,
-                packed_additional.as_slice().as_ptr() as *const std::ffi::c_void,
-                gl::STATIC_DRAW
-            );
-            // vertex normal
-            gl::VertexAttribPointer(
-                1,
-                3,
-                gl::FLOAT,
-                gl::FALSE,
-                8 * std::mem::size_of::<f32>() as i32,
-                (0 * std::mem::size_of::<f32>()) as *const std::ffi::c_void
-            );
-            // vertex tangent
-            gl::VertexAttribPointer(
-                2,
-                3,
-                gl::FLOAT,
-                gl::FALSE,
-                8 * std::mem::size_of::<f32>() as i32,
-                (3 * std::mem::size_of::<f32>()) as *const std::ffi::c_void
-            );
-            // vertex uv
-            gl::VertexAttribPointer(
-                3,
-                2,
-                gl::FLOAT,
-                gl::FALSE,
-                8 * std::mem::size_of::<f32>() as i32,
-                (6 * std::mem::size_of::<f32>()) as *const std::ffi::c_void
-            );
-
-            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, gpu.index_buffer);
-            gl::BufferData(
-                gl::ELEMENT_ARRAY_BUFFER,
-                (std::mem::size_of::<u32>() * indices.len()) as isize,
-                indices.as_slice().as_ptr() as *const std::ffi::c_void,
-                gl::STATIC_DRAW
-            );
-
-            gl::EnableVertexAttribArray(0);
-            gl::EnableVertexAttribArray(1);
-            gl::EnableVertexAttribArray(2);
-            gl::EnableVertexAttribArray(3);
-
-            drop(vao_bind);
-
-            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, 0);
-            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+        let mut ranges = vec![(0, indices.len())];
+        for level in model.lods.iter() {
+            ranges.push((indices.len(), level.len()));
+            indices.extend_from_slice(level);
         }
 
-        gpu.vertex_array_object.count = crate::gpu_buffer::Kind::Indexed {
-            index_count: model.index_count
-        };
+        for (level, (vertex_array_object, (first_index, index_count))) in gpu.vertex_array_objects.iter_mut().zip(ranges).enumerate() {
+            #[allow(clippy::erasing_op)]
+            unsafe {
+                let vao_bind = vertex_array_object.bind();
+                gl::BindBuffer(gl::ARRAY_BUFFER, gpu.position_vbo);
+                if level == 0 {
+                    gl::BufferData(
+                        gl::ARRAY_BUFFER,
+                        (3 * std::mem::size_of::<f32>() * model.positions.len()) as isize,
+                        model.positions.iter()
+                            .flat_map(|p| *p)
+                            .collect::<Vec<f32>>()
+                            .as_slice()
+                            .as_ptr() as *const std::ffi::c_void,
+                        gl::STATIC_DRAW
+                    );
+                }
+                gl::VertexAttribPointer(
+                    0,
+                    3,
+                    gl::FLOAT,
+                    gl::FALSE,
+                    3 * std::mem::size_of::<f32>() as i32,
+                    std::ptr::null()
+                );
+                gl::BindBuffer(gl::ARRAY_BUFFER, gpu.extra_vbo);
+                if level == 0 {
+                    gl::BufferData(
+                        gl::ARRAY_BUFFER,
+                        packed_additional.len() as isize,
+                        packed_additional.as_slice().as_ptr() as *const std::ffi::c_void,
+                        gl::STATIC_DRAW
+                    );
+                }
+                // vertex normal
+                gl::VertexAttribPointer(
+                    1,
+                    3,
+                    gl::FLOAT,
+                    gl::FALSE,
+                    8 * std::mem::size_of::<f32>() as i32,
+                    (0 * std::mem::size_of::<f32>()) as *const std::ffi::c_void
+                );
+                // vertex tangent
+                gl::VertexAttribPointer(
+                    2,
+                    3,
+                    gl::FLOAT,
+                    gl::FALSE,
+                    8 * std::mem::size_of::<f32>() as i32,
+                    (3 * std::mem::size_of::<f32>()) as *const std::ffi::c_void
+                );
+                // vertex uv
+                gl::VertexAttribPointer(
+                    3,
+                    2,
+                    gl::FLOAT,
+                    gl::FALSE,
+                    8 * std::mem::size_of::<f32>() as i32,
+                    (6 * std::mem::size_of::<f32>()) as *const std::ffi::c_void
+                );
+
+                gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, gpu.index_buffer);
+                if level == 0 {
+                    gl::BufferData(
+                        gl::ELEMENT_ARRAY_BUFFER,
+                        (std::mem::size_of::<u32>() * indices.len()) as isize,
+                        indices.as_slice().as_ptr() as *const std::ffi::c_void,
+                        gl::STATIC_DRAW
+                    );
+                }
+
+                gl::EnableVertexAttribArray(0);
+                gl::EnableVertexAttribArray(1);
+                gl::EnableVertexAttribArray(2);
+                gl::EnableVertexAttribArray(3);
+
+                drop(vao_bind);
+
+                gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, 0);
+                gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+            }
+            vertex_array_object.count = crate::gpu_buffer::Kind::Indexed {
+                index_count,
+                first_index
+            };
+        }
+
+        gpu.bytes = 3 * std::mem::size_of::<f32>() * model.positions.len()
+            + packed_additional.len()
+            + std::mem::size_of::<u32>() * indices.len();
         Model {
             gpu,
             min_bounds: model.min_bounds.into(),
@@ -148,7 +186,7 @@ impl From<PackedModel> for Model {
 }
 
 impl Gpu {
-    fn new() -> Gpu {
+    fn new(levels: usize) -> Gpu {
         let (position_vbo, extra_vbo, index_buffer) = unsafe {
             let mut buffers = [0; 3];
             gl::GenBuffers(3, buffers.as_mut_ptr());
@@ -156,10 +194,11 @@ impl Gpu {
         };
 
         Gpu {
-            vertex_array_object: State::new(),
+            vertex_array_objects: (0..levels).map(|_| State::new()).collect(),
             position_vbo,
             extra_vbo,
-            index_buffer
+            index_buffer,
+            bytes: 0
         }
     }
 }
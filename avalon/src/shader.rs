@@ -63,7 +63,7 @@ pub trait Source: MetaShader {
         let successful_compile = unsafe {
             let mut successful = 0;
             gl::GetShaderiv(shader.id(), gl::COMPILE_STATUS, &mut successful);
-            successful == gl::TRUE.into()
+            successful == gl::types::GLint::from(gl::TRUE)
         };
 
         if !successful_compile {
@@ -11,6 +11,8 @@ pub enum Creation {
     FileReadError(#[from] std::io::Error),
     #[error("Failed to parse shader file")]
     FileParseError(#[from] std::string::FromUtf8Error),
+    #[error("Cannot build program `{id}` from its stages: {reason}")]
+    InvalidStages { id: String, reason: &'static str },
 }
 
 #[derive(Error, Debug)]
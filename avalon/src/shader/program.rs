@@ -11,6 +11,7 @@ use crate::shader::uniform::Uniform;
 use crate::shader::error;
 use crate::texture::gpu::{ Access, Sampler, Image, TextureAttachment, ImageAttachment };
 use crate::gpu_buffer::storage;
+use crate::asset_library;
 
 #[derive(Debug, Clone)]
 pub struct Program {
@@ -82,7 +83,7 @@ impl Program {
         unsafe {
             let mut successful = 0;
             gl::GetProgramiv(self.program, gl::LINK_STATUS, &mut successful);
-            if successful == gl::FALSE.into() {
+            if successful == gl::types::GLint::from(gl::FALSE) {
                 return Err(error::Creation::FailedToLink {
                     reason: self.info_log()
                 });
@@ -128,6 +129,8 @@ impl Drop for Program {
     }
 }
 
+impl asset_library::Asset for Program {}
+
 impl AttachedProgram<'_> {
     fn location_from_uniform(&self, uniform: &str) -> gl::types::GLint {
         unsafe {
@@ -128,6 +128,13 @@ impl From<gpu::SizedComponent> for Component {
             gpu::SizedComponent::Depth16 => Component::Depth,
             gpu::SizedComponent::Depth24 => Component::Depth,
             gpu::SizedComponent::FloatDepth32 => Component::Depth,
+            gpu::SizedComponent::Bc1RGBA => Component::RGBA,
+            gpu::SizedComponent::Bc1SRGBA => Component::RGBA,
+            gpu::SizedComponent::Bc3RGBA => Component::RGBA,
+            gpu::SizedComponent::Bc3SRGBA => Component::RGBA,
+            gpu::SizedComponent::Bc5RG => Component::RG,
+            gpu::SizedComponent::Bc7RGBA => Component::RGBA,
+            gpu::SizedComponent::Bc7SRGBA => Component::RGBA,
         }
     }
 }
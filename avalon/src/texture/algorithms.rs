@@ -1,25 +1,21 @@
 use nalgebra_glm::IVec2;
 use crate::debug::GpuAnnotation;
-use crate::shader::{ self, Source, Program };
+use crate::asset_library::{ AssetView, BundleView };
+use crate::shader::Program;
 use crate::texture::GpuTexture2d;
 use crate::texture::gpu::{ self, ManagedTexture, UniqueTexture, Arguments2d, Mipmap };
 
 pub struct Rescaler {
-    downsample: Program,
-    upscale: Program
+    downsample: AssetView<Program>,
+    upscale: AssetView<Program>
 }
 
 impl Rescaler {
-    pub fn new() -> Rescaler {
+    /// Uses the `downsample` and `upscale` compute programs in `shaders`
+    pub fn new(shaders: &BundleView) -> Rescaler {
         Rescaler {
-            downsample: Program::new()
-                .compute(shader::Compute::load_from_path("assets/shaders/downsample.comp").unwrap())
-                .build()
-                .unwrap(),
-            upscale: Program::new()
-                .compute(shader::Compute::load_from_path("assets/shaders/upscale.comp").unwrap())
-                .build()
-                .unwrap(),
+            downsample: shaders.tag::<Program>("downsample").unwrap(),
+            upscale: shaders.tag::<Program>("upscale").unwrap(),
         }
     }
 
@@ -35,7 +31,8 @@ impl Rescaler {
         }).map(|t| Some(t.as_managed()));
 
         {
-            let mut bind = self.downsample.activate();
+            let program = self.downsample.get().unwrap();
+            let mut bind = program.activate();
             if halve_count == 0 {
                 let downsampled = temp_downsampled[1].as_ref().unwrap();
                 bind.uniform("originalSize").unwrap().set_ivec2(size);
@@ -43,7 +40,7 @@ impl Rescaler {
                 bind.sampler("original", original).unwrap();
                 bind.image("downsampled", downsampled, gpu::Access::Write(0)).unwrap();
 
-                let (dispatch_x, dispatch_y, dispatch_z) = self.downsample.dispatch_counts(
+                let (dispatch_x, dispatch_y, dispatch_z) = program.dispatch_counts(
                     size.x as usize,
                     size.y as usize,
                     1
@@ -64,7 +61,7 @@ impl Rescaler {
                     bind.sampler("original", original).unwrap();
                     bind.image("downsampled", downsampled.unwrap(), gpu::Access::Write(0)).unwrap();
 
-                    let (dispatch_x, dispatch_y, dispatch_z) = self.downsample.dispatch_counts(
+                    let (dispatch_x, dispatch_y, dispatch_z) = program.dispatch_counts(
                         size.x as usize,
                         size.y as usize,
                         1
@@ -91,7 +88,8 @@ impl Rescaler {
         }).map(|t| Some(t.as_managed()));
 
         {
-            let mut bind = self.upscale.activate();
+            let program = self.upscale.get().unwrap();
+            let mut bind = program.activate();
             if double_count == 0 {
                 let upscaled = temp_upscaled[1].as_ref().unwrap();
                 bind.uniform("originalSize").unwrap().set_ivec2(size);
@@ -99,7 +97,7 @@ impl Rescaler {
                 bind.sampler("original", original).unwrap();
                 bind.image("upscaled", upscaled, gpu::Access::Write(0)).unwrap();
 
-                let (dispatch_x, dispatch_y, dispatch_z) = self.upscale.dispatch_counts(
+                let (dispatch_x, dispatch_y, dispatch_z) = program.dispatch_counts(
                     size.x as usize,
                     size.y as usize,
                     1
@@ -120,7 +118,7 @@ impl Rescaler {
                     bind.sampler("original", original).unwrap();
                     bind.image("upscaled", upscaled, gpu::Access::Write(0)).unwrap();
 
-                    let (dispatch_x, dispatch_y, dispatch_z) = self.upscale.dispatch_counts(
+                    let (dispatch_x, dispatch_y, dispatch_z) = program.dispatch_counts(
                         size.x as usize,
                         size.y as usize,
                         1
@@ -153,13 +151,14 @@ impl Rescaler {
 
         let near_texture = self.downsample_halving(original, halves_to_near).as_managed();
 
-        let mut bind = self.downsample.activate();
+        let program = self.downsample.get().unwrap();
+        let mut bind = program.activate();
         bind.uniform("originalSize").unwrap().set_ivec2(original_size / 2_i32.pow(halves_to_near));
         bind.uniform("newSize").unwrap().set_ivec2(desired_size);
         bind.sampler("original", &near_texture).unwrap();
         bind.image("downsampled", &downsampled, gpu::Access::Write(0)).unwrap();
 
-        let (dispatch_x, dispatch_y, dispatch_z) = self.downsample.dispatch_counts(
+        let (dispatch_x, dispatch_y, dispatch_z) = program.dispatch_counts(
             desired_size.x as usize,
             desired_size.y as usize,
             1
@@ -187,13 +186,14 @@ impl Rescaler {
 
         let near_texture = self.upscale_doubling(original, doubles_to_near).as_managed();
 
-        let mut bind = self.upscale.activate();
+        let program = self.upscale.get().unwrap();
+        let mut bind = program.activate();
         bind.uniform("originalSize").unwrap().set_ivec2(near_texture.dimensions());
         bind.uniform("newSize").unwrap().set_ivec2(desired_size);
         bind.sampler("original", &near_texture).unwrap();
         bind.image("upscaled", &upscaled, gpu::Access::Write(0)).unwrap();
 
-        let (dispatch_x, dispatch_y, dispatch_z) = self.upscale.dispatch_counts(
+        let (dispatch_x, dispatch_y, dispatch_z) = program.dispatch_counts(
             desired_size.x as usize,
             desired_size.y as usize,
             1
@@ -263,7 +263,7 @@ impl Data {
         let dimension = image.dimensions();
         (Data::from_image(image), IVec2::new(dimension.0 as i32, dimension.1 as i32))
     }
-    pub fn from_buffer(buffer: Vec<u8>) -> (Data, IVec2) {
+    pub fn from_buffer(buffer: &[u8]) -> (Data, IVec2) {
         let cursor = std::io::Cursor::new(buffer);
         let image = image::ImageReader::new(cursor).with_guessed_format().unwrap().decode().unwrap();
         let dimension = image.dimensions();
@@ -5,6 +5,12 @@ use crate::texture::Component;
 use crate::asset_library::Asset;
 use std::marker::PhantomData;
 
+// S3TC never became core, so the `gl` bindings leave these out
+const COMPRESSED_RGBA_S3TC_DXT1: gl::types::GLenum = 0x83F1;
+const COMPRESSED_RGBA_S3TC_DXT5: gl::types::GLenum = 0x83F3;
+const COMPRESSED_SRGB_ALPHA_S3TC_DXT1: gl::types::GLenum = 0x8C4D;
+const COMPRESSED_SRGB_ALPHA_S3TC_DXT5: gl::types::GLenum = 0x8C4F;
+
 pub trait UniqueTexture where Self: Sized {
     fn handle(&self) -> u32;
     fn levels(&self) -> u32;
@@ -30,7 +36,11 @@ impl<T: Copy + UniqueTexture> ManagedTexture<T> {
     }
 }
 
-impl<T: UniqueTexture + Asset> Asset for ManagedTexture<T> {}
+impl<T: UniqueTexture + Asset> Asset for ManagedTexture<T> {
+    fn resident_size(&self) -> crate::asset_library::Size {
+        self.texture.resident_size()
+    }
+}
 
 impl<T: UniqueTexture> Drop for ManagedTexture<T> {
     fn drop(&mut self) {
@@ -252,6 +262,14 @@ pub enum SizedComponent {
     Depth16,
     Depth24,
     FloatDepth32,
+    /// Block compressed, uploaded from data already in blocks
+    Bc1RGBA,
+    Bc1SRGBA,
+    Bc3RGBA,
+    Bc3SRGBA,
+    Bc5RG,
+    Bc7RGBA,
+    Bc7SRGBA,
 }
 
 impl SizedComponent {
@@ -288,6 +306,7 @@ impl SizedComponent {
                     SizedComponent::UnsignedIntRG16 => (),
                     SizedComponent::IntRG32 => (),
                     SizedComponent::UnsignedIntRG32 => (),
+                    SizedComponent::Bc5RG => (),
                     _ => panic!("Mismatched components and desired size: components[{components:?}] vs size[{self:?}]"),
                 }
             },
@@ -332,6 +351,12 @@ impl SizedComponent {
                     SizedComponent::UnsignedIntRGBA16 => (),
                     SizedComponent::IntRGBA32 => (),
                     SizedComponent::UnsignedIntRGBA32 => (),
+                    SizedComponent::Bc1RGBA => (),
+                    SizedComponent::Bc1SRGBA => (),
+                    SizedComponent::Bc3RGBA => (),
+                    SizedComponent::Bc3SRGBA => (),
+                    SizedComponent::Bc7RGBA => (),
+                    SizedComponent::Bc7SRGBA => (),
                     _ => panic!("Mismatched components and desired size: components[{components:?}] vs size[{self:?}]"),
                 }
             },
@@ -420,6 +445,13 @@ impl SizedComponent {
             SizedComponent::Depth16 => gl::DEPTH_COMPONENT16,
             SizedComponent::Depth24 => gl::DEPTH_COMPONENT24,
             SizedComponent::FloatDepth32 => gl::DEPTH_COMPONENT32,
+            SizedComponent::Bc1RGBA => COMPRESSED_RGBA_S3TC_DXT1,
+            SizedComponent::Bc1SRGBA => COMPRESSED_SRGB_ALPHA_S3TC_DXT1,
+            SizedComponent::Bc3RGBA => COMPRESSED_RGBA_S3TC_DXT5,
+            SizedComponent::Bc3SRGBA => COMPRESSED_SRGB_ALPHA_S3TC_DXT5,
+            SizedComponent::Bc5RG => gl::COMPRESSED_RG_RGTC2,
+            SizedComponent::Bc7RGBA => gl::COMPRESSED_RGBA_BPTC_UNORM,
+            SizedComponent::Bc7SRGBA => gl::COMPRESSED_SRGB_ALPHA_BPTC_UNORM,
         }) as gl::types::GLint
     }
 
@@ -491,6 +523,43 @@ impl SizedComponent {
             SizedComponent::FloatRGB32 => gl::FLOAT,
             SizedComponent::FloatRGBA32 => gl::FLOAT,
             SizedComponent::FloatDepth32 => gl::FLOAT,
+            SizedComponent::Bc1RGBA => gl::UNSIGNED_BYTE,
+            SizedComponent::Bc1SRGBA => gl::UNSIGNED_BYTE,
+            SizedComponent::Bc3RGBA => gl::UNSIGNED_BYTE,
+            SizedComponent::Bc3SRGBA => gl::UNSIGNED_BYTE,
+            SizedComponent::Bc5RG => gl::UNSIGNED_BYTE,
+            SizedComponent::Bc7RGBA => gl::UNSIGNED_BYTE,
+            SizedComponent::Bc7SRGBA => gl::UNSIGNED_BYTE,
+        }
+    }
+
+    /// Bytes per 4x4 block for compressed formats
+    pub(crate) fn block_bytes(self) -> Option<usize> {
+        match self {
+            SizedComponent::Bc1RGBA | SizedComponent::Bc1SRGBA => Some(8),
+            SizedComponent::Bc3RGBA | SizedComponent::Bc3SRGBA => Some(16),
+            SizedComponent::Bc5RG => Some(16),
+            SizedComponent::Bc7RGBA | SizedComponent::Bc7SRGBA => Some(16),
+            _ => None,
+        }
+    }
+
+    /// Bytes for a level of this size, ignoring any padding the driver adds
+    pub(crate) fn level_bytes(self, width: i32, height: i32) -> usize {
+        let (width, height) = (width.max(1) as usize, height.max(1) as usize);
+        match self.block_bytes() {
+            Some(block_bytes) => width.div_ceil(4) * height.div_ceil(4) * block_bytes,
+            None => width * height * self.texel_bytes(),
+        }
+    }
+
+    /// Bytes per texel, ignoring any padding the driver adds
+    pub(crate) fn texel_bytes(self) -> usize {
+        match self.map_to_cpu_types() {
+            gl::UNSIGNED_INT_24_8 | gl::UNSIGNED_INT_10_10_10_2 => 4,
+            gl::BYTE | gl::UNSIGNED_BYTE => self.component_count(),
+            gl::SHORT | gl::UNSIGNED_SHORT | gl::HALF_FLOAT => 2 * self.component_count(),
+            _ => 4 * self.component_count(),
         }
     }
 
@@ -562,6 +631,13 @@ impl SizedComponent {
             SizedComponent::Depth16 => 1,
             SizedComponent::Depth24 => 1,
             SizedComponent::FloatDepth32 => 1,
+            SizedComponent::Bc1RGBA => 4,
+            SizedComponent::Bc1SRGBA => 4,
+            SizedComponent::Bc3RGBA => 4,
+            SizedComponent::Bc3SRGBA => 4,
+            SizedComponent::Bc5RG => 2,
+            SizedComponent::Bc7RGBA => 4,
+            SizedComponent::Bc7SRGBA => 4,
         }
     }
 }
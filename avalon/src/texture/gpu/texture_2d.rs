@@ -164,6 +164,79 @@ impl Texture2d {
         })
     }
 
+    /// Uploads every mip level at once, largest first. Levels are blocks when `internal_size` is compressed,
+    /// otherwise tightly packed RGBA8
+    pub fn generate_with_levels(
+        dimensions: IVec2,
+        internal_components: Component,
+        internal_size: SizedComponent,
+        levels: &[&[u8]]
+    ) -> Texture2d {
+        internal_size.verify(internal_components);
+        assert!(
+            internal_size.block_bytes().is_some() || internal_size.texel_bytes() == 4,
+            "uncompressed levels are uploaded as RGBA8, {internal_size:?} cannot hold them"
+        );
+        let handle = unsafe {
+            let mut texture = 0;
+            gl::GenTextures(1, &mut texture);
+            texture
+        };
+
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, handle);
+            gl::TexStorage2D(
+                gl::TEXTURE_2D,
+                levels.len().max(1) as i32,
+                internal_size.as_api() as u32,
+                dimensions.x,
+                dimensions.y
+            );
+            for (level, data) in levels.iter().enumerate() {
+                let width = (dimensions.x >> level).max(1);
+                let height = (dimensions.y >> level).max(1);
+                assert_eq!(data.len(), internal_size.level_bytes(width, height), "mip level {level} is the wrong size");
+                if internal_size.block_bytes().is_some() {
+                    gl::CompressedTexSubImage2D(
+                        gl::TEXTURE_2D,
+                        level as i32,
+                        0,
+                        0,
+                        width,
+                        height,
+                        internal_size.as_api() as u32,
+                        data.len() as i32,
+                        data.as_ptr() as *const std::ffi::c_void
+                    );
+                } else {
+                    gl::TexSubImage2D(
+                        gl::TEXTURE_2D,
+                        level as i32,
+                        0,
+                        0,
+                        width,
+                        height,
+                        gl::RGBA,
+                        gl::UNSIGNED_BYTE,
+                        data.as_ptr() as *const std::ffi::c_void
+                    );
+                }
+            }
+            let min_filter = if levels.len() > 1 { gl::LINEAR_MIPMAP_LINEAR } else { gl::NEAREST };
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, min_filter as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as i32);
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+        }
+
+        Texture2d {
+            handle,
+            internal_components,
+            internal_size,
+            dimensions,
+            mip_levels: levels.len() as u32
+        }
+    }
+
     pub fn bind(&mut self) -> TextureBind2d {
         unsafe {
             gl::BindTexture(gl::TEXTURE_2D, self.handle);
@@ -178,7 +251,14 @@ impl Texture2d {
     }
 }
 
-impl asset_library::Asset for Texture2d {}
+impl asset_library::Asset for Texture2d {
+    fn resident_size(&self) -> asset_library::Size {
+        let gpu = (0..self.mip_levels.max(1))
+            .map(|level| self.internal_size.level_bytes(self.dimensions.x >> level, self.dimensions.y >> level))
+            .sum();
+        asset_library::Size { cpu: 0, gpu }
+    }
+}
 
 impl UniqueTexture for Texture2d {
     fn levels(&self) -> u32 {
@@ -189,7 +189,14 @@ impl Texture3d {
     }
 }
 
-impl asset_library::Asset for Texture3d {}
+impl asset_library::Asset for Texture3d {
+    fn resident_size(&self) -> asset_library::Size {
+        let texels = self.dimensions.iter().map(|side| (*side).max(0) as usize).product::<usize>();
+        let base = texels * self.internal_size.texel_bytes();
+        let gpu = if self.mip_levels > 0 { base * 8 / 7 } else { base };
+        asset_library::Size { cpu: 0, gpu }
+    }
+}
 
 impl UniqueTexture for Texture3d {
     fn levels(&self) -> u32 {
@@ -4,12 +4,10 @@ pub use serialization::write;
 #[cfg(feature = "read")]
 pub use serialization::read;
 
-use crate::{ error, shader, model, texture, text };
+use crate::{ error, shader, model, texture, text, config, dependency };
 
 use uuid;
 use std::path::PathBuf;
-use std::sync::atomic;
-use std::ops::Deref;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum Type {
@@ -17,19 +15,24 @@ pub enum Type {
     Texture,
     Model,
     Text,
+    Config,
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub enum Unit {
     Shader(shader::Shader),
     Texture(texture::Texture),
     Model(model::Model),
     Text(text::Text),
+    Config(config::Config),
 }
 
-#[derive(Debug, Hash)]
-pub struct AssetReference<'asset> {
-    asset: &'asset Asset
+/// One piece of a source file that holds several assets, such as a glTF scene. Written as
+/// `mesh/<mesh>/primitive/<primitive>` or `image/<image>`
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Part {
+    Primitive { mesh: usize, primitive: usize },
+    Image(usize),
 }
 
 #[derive(Debug, Clone)]
@@ -38,27 +41,17 @@ pub struct Metadata {
     pub tag: String,
     pub filepath: Option<PathBuf>,
     pub unit: Unit,
+    pub dependencies: Vec<dependency::Dependency>,
+    /// Which part of `filepath` is packed, when it holds more than this asset
+    pub part: Option<Part>,
 }
 
 #[derive(Debug)]
 pub struct Asset {
     pub(crate) metadata: Metadata,
-    references: atomic::AtomicUsize
 }
 
 impl Asset {
-    fn increment_reference(&self) {
-        self.references.fetch_add(1, atomic::Ordering::Acquire);
-    }
-
-    fn decrement_reference(&self) {
-        self.references.fetch_sub(1, atomic::Ordering::Acquire);
-    }
-
-    pub fn refer(&self) -> AssetReference {
-        self.into()
-    }
-
     pub fn metadata(&self) -> Metadata {
         self.metadata.clone()
     }
@@ -70,7 +63,9 @@ impl Metadata {
             uuid: uuid::Uuid::new_v4(),
             tag: tag.into(),
             filepath: Some(filepath.into()),
-            unit: unit.into()
+            unit: unit.into(),
+            dependencies: Vec::new(),
+            part: None
         }
     }
 }
@@ -79,7 +74,6 @@ impl From<Metadata> for Asset {
     fn from(metadata: Metadata) -> Asset {
         Asset {
             metadata,
-            references: atomic::AtomicUsize::new(0)
         }
     }
 }
@@ -110,43 +104,6 @@ impl PartialEq for Asset {
 }
 impl Eq for Asset {}
 
-impl<'old> Clone for AssetReference<'old> {
-    fn clone(&self) -> AssetReference<'old> {
-        self.asset.increment_reference();
-        AssetReference {
-            asset: self.asset
-        }
-    }
-}
-
-impl Drop for AssetReference<'_> {
-    fn drop(&mut self) {
-        self.asset.decrement_reference();
-    }
-}
-
-impl AsRef<Asset> for AssetReference<'_> {
-    fn as_ref(&self) -> &Asset {
-        self.asset
-    }
-}
-
-impl<'reference, 'asset: 'reference> From<&'asset Asset> for AssetReference<'reference> {
-    fn from(asset: &'asset Asset) -> AssetReference<'reference> {
-        asset.increment_reference();
-        AssetReference {
-            asset
-        }
-    }
-}
-
-impl Deref for AssetReference<'_> {
-    type Target = Asset;
-    fn deref(&self) -> &Asset {
-        self.as_ref()
-    }
-}
-
 impl TryFrom<Unit> for shader::Shader {
     type Error = error::UnitConversionError;
     fn try_from(unit: Unit) -> Result<Self, Self::Error> {
@@ -177,6 +134,41 @@ impl TryFrom<Unit> for text::Text {
     }
 }
 
+impl TryFrom<Unit> for config::Config {
+    type Error = error::UnitConversionError;
+    fn try_from(unit: Unit) -> Result<Self, Self::Error> {
+        if let Unit::Config(config) = unit {
+            return Ok(config);
+        }
+        Err(error::UnitConversionError::UnitIsNotConfig)
+    }
+}
+
+impl std::str::FromStr for Part {
+    type Err = error::PartError;
+    fn from_str(source: &str) -> Result<Part, Self::Err> {
+        let invalid = || error::PartError::Invalid(source.to_string());
+        let fields: Vec<_> = source.split('/').collect();
+        match fields.as_slice() {
+            ["mesh", mesh, "primitive", primitive] => Ok(Part::Primitive {
+                mesh: mesh.parse().map_err(|_| invalid())?,
+                primitive: primitive.parse().map_err(|_| invalid())?,
+            }),
+            ["image", image] => Ok(Part::Image(image.parse().map_err(|_| invalid())?)),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl std::fmt::Display for Part {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Part::Primitive { mesh, primitive } => write!(formatter, "mesh/{mesh}/primitive/{primitive}"),
+            Part::Image(image) => write!(formatter, "image/{image}"),
+        }
+    }
+}
+
 impl Type {
     pub fn from_path(path: impl AsRef<std::path::Path>) -> Option<Type> {
         let path = path.as_ref();
@@ -187,7 +179,8 @@ impl Type {
         match extension.to_ascii_lowercase().to_str()? {
             "png" | "jpg" | "jpeg" => Some(Type::Texture),
             "comp" | "vert" | "frag" => Some(Type::Shader),
-            "obj" => Some(Type::Model),
+            "obj" | "gltf" | "glb" => Some(Type::Model),
+            "cfg" | "config" => Some(Type::Config),
             _ => None,
         }
     }
@@ -200,6 +193,7 @@ impl std::fmt::Display for Type {
             Type::Texture => "Texture".fmt(formatter),
             Type::Model => "Model".fmt(formatter),
             Type::Text => "Text".fmt(formatter),
+            Type::Config => "Config".fmt(formatter),
         }
     }
 }
@@ -211,6 +205,7 @@ impl From<Unit> for Type {
             Unit::Shader(_) => Type::Shader,
             Unit::Texture(_) => Type::Texture,
             Unit::Text(_) => Type::Text,
+            Unit::Config(_) => Type::Config,
         }
     }
 }
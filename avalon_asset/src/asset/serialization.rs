@@ -7,6 +7,8 @@ pub mod write {
     struct MetadataStream<'a> {
         metadata: &'a Metadata,
         uuid_string: Option<String>,
+        dependency_strings: Vec<String>,
+        part_string: Option<String>,
         state: usize
     }
 
@@ -29,8 +31,20 @@ pub mod write {
                         Unit::Texture(texture) => (Cow::Borrowed("texture"), texture),
                         Unit::Model(model) => (Cow::Borrowed("model"), model),
                         Unit::Text(text) => (Cow::Borrowed("text"), text),
+                        Unit::Config(config) => (Cow::Borrowed("config"), config),
                     })
                 },
+                3 => {
+                    self.dependency_strings = self.metadata.dependencies.iter()
+                        .map(|dependency| dependency.to_string())
+                        .collect();
+                    Some((Cow::Borrowed("dependencies"), &self.dependency_strings))
+                },
+                4 => {
+                    self.part_string = Some(self.metadata.part?.to_string());
+                    let str_ref = self.part_string.as_ref().unwrap();
+                    Some((Cow::Borrowed("part"), str_ref))
+                },
                 _ => None,
             }
         }
@@ -41,6 +55,8 @@ pub mod write {
             ser::Fragment::Map(Box::new(MetadataStream {
                 metadata: self,
                 uuid_string: None,
+                dependency_strings: Vec::new(),
+                part_string: None,
                 state: 0
             }))
         }
@@ -49,8 +65,8 @@ pub mod write {
 
 #[cfg(feature = "read")]
 pub mod read {
-    use crate::asset::{ Metadata, Unit };
-    use crate::{ shader, texture, text, model };
+    use crate::asset::{ Metadata, Part, Unit };
+    use crate::{ shader, texture, text, model, config, dependency };
     use miniserde::{ make_place, de, Deserialize, Result };
     use std::str::FromStr;
 
@@ -61,6 +77,7 @@ pub mod read {
         texture: Option<texture::Texture>,
         model: Option<model::Model>,
         text: Option<text::Text>,
+        config: Option<config::Config>,
     }
 
     impl MetadataUnit {
@@ -77,6 +94,9 @@ pub mod read {
             if let Some(text) = self.text {
                 return Some(Unit::Text(text));
             }
+            if let Some(config) = self.config {
+                return Some(Unit::Config(config));
+            }
             None
         }
     }
@@ -84,6 +104,8 @@ pub mod read {
     struct MetadataBuilder<'a> {
         tag: Option<String>,
         uuid_string: Option<String>,
+        dependency_strings: Option<Vec<String>>,
+        part_string: Option<String>,
         unit: MetadataUnit,
         out: &'a mut Option<Metadata>
     }
@@ -93,11 +115,14 @@ pub mod read {
             Ok(Box::new(MetadataBuilder {
                 tag: None,
                 uuid_string: None,
+                dependency_strings: None,
+                part_string: None,
                 unit: MetadataUnit {
                     shader: None,
                     texture: None,
                     model: None,
-                    text: None
+                    text: None,
+                    config: None
                 },
                 out: &mut self.out
             }))
@@ -109,10 +134,13 @@ pub mod read {
             match k {
                 "tag" => Ok(Deserialize::begin(&mut self.tag)),
                 "uuid" => Ok(Deserialize::begin(&mut self.uuid_string)),
+                "dependencies" => Ok(Deserialize::begin(&mut self.dependency_strings)),
+                "part" => Ok(Deserialize::begin(&mut self.part_string)),
                 "shader" => Ok(Deserialize::begin(&mut self.unit.shader)),
                 "texture" => Ok(Deserialize::begin(&mut self.unit.texture)),
                 "model" => Ok(Deserialize::begin(&mut self.unit.model)),
                 "text" => Ok(Deserialize::begin(&mut self.unit.text)),
+                "config" => Ok(Deserialize::begin(&mut self.unit.config)),
                 _ => Ok(<dyn de::Visitor>::ignore())
             }
         }
@@ -121,11 +149,22 @@ pub mod read {
             let tag = self.tag.take().ok_or(miniserde::Error)?;
             let uuid = self.uuid_string.take().ok_or(miniserde::Error)?;
             let unit = self.unit.as_unit().ok_or(miniserde::Error)?;
+            // bundles packed before dependencies existed have none
+            let dependencies = self.dependency_strings.take()
+                .unwrap_or_default()
+                .iter()
+                .map(|dependency| dependency::Dependency::from_str(dependency).map_err(|_| miniserde::Error))
+                .collect::<Result<Vec<_>>>()?;
+            let part = self.part_string.take()
+                .map(|part| Part::from_str(&part).map_err(|_| miniserde::Error))
+                .transpose()?;
 
             *self.out = Some(Metadata {
                 tag,
                 unit,
                 filepath: None,
+                dependencies,
+                part,
                 uuid: uuid::Uuid::from_str(&uuid).map_err(|_| miniserde::Error)?
             });
             Ok(())
@@ -1,9 +1,11 @@
-use crate::asset;
+use crate::{ asset, dependency, error };
 
 #[derive(Debug, Eq)]
 pub struct Bundle {
     pub group: Vec<asset::Metadata>,
     pub name: String,
+    /// Names of other bundles that must be loaded first
+    pub dependencies: Vec<String>,
 }
 
 impl PartialEq for Bundle {
@@ -19,6 +21,14 @@ impl std::hash::Hash for Bundle {
 }
 
 impl Bundle {
+    pub fn new(name: impl Into<String>) -> Bundle {
+        Bundle {
+            group: Vec::new(),
+            name: name.into(),
+            dependencies: Vec::new(),
+        }
+    }
+
     pub fn asset(&self, tag: impl Into<String>) -> Option<asset::Metadata> {
         let tag = tag.into();
         for asset in self.group.iter() {
@@ -28,5 +38,16 @@ impl Bundle {
         }
         None
     }
-}
 
+    /// Checks references within the bundle. Other bundles can't be checked until load, so those only
+    /// need to be listed in `dependencies`
+    pub fn validate(&self) -> Vec<error::DependencyError> {
+        dependency::Graph::new([self]).errors().iter()
+            .filter(|e| match e {
+                error::DependencyError::MissingBundle { bundle, .. } => !self.dependencies.contains(bundle),
+                _ => true,
+            })
+            .cloned()
+            .collect()
+    }
+}
@@ -0,0 +1,332 @@
+use crate::error;
+use miniserde::{ Deserialize, Serialize };
+use std::collections::BTreeMap;
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Config {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Number(f64),
+    Bool(bool),
+    String(String),
+    Vector(Vec<f64>),
+    Table(Table),
+}
+
+/// Key/value data read from a config file
+///
+/// ```text
+/// # comments run to the end of the line
+/// name = "player"
+/// offset = [0.0, 1.6, 0.0]
+///
+/// [movement]
+/// max_speed = 10.0
+/// crouch.height = 0.9
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Table {
+    entries: BTreeMap<String, Value>,
+}
+
+impl Table {
+    pub fn new() -> Table {
+        Table::default()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Table, error::ConfigError> {
+        let source = std::str::from_utf8(bytes).map_err(|_| error::ConfigError::InvalidUtf8)?;
+        source.parse()
+    }
+
+    /// Looks up a value by a dotted path, such as `movement.max_speed`
+    pub fn get(&self, path: &str) -> Option<&Value> {
+        let mut table = self;
+        let mut keys = path.split('.').peekable();
+        while let Some(key) = keys.next() {
+            let value = table.entries.get(key)?;
+            if keys.peek().is_none() {
+                return Some(value);
+            }
+            let Value::Table(inner) = value else {
+                return None;
+            };
+            table = inner;
+        }
+        None
+    }
+
+    pub fn number(&self, path: &str) -> Option<f64> {
+        match self.get(path)? {
+            Value::Number(number) => Some(*number),
+            _ => None,
+        }
+    }
+
+    pub fn bool(&self, path: &str) -> Option<bool> {
+        match self.get(path)? {
+            Value::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn string(&self, path: &str) -> Option<&str> {
+        match self.get(path)? {
+            Value::String(string) => Some(string),
+            _ => None,
+        }
+    }
+
+    pub fn vector(&self, path: &str) -> Option<&[f64]> {
+        match self.get(path)? {
+            Value::Vector(vector) => Some(vector),
+            _ => None,
+        }
+    }
+
+    pub fn table(&self, path: &str) -> Option<&Table> {
+        match self.get(path)? {
+            Value::Table(table) => Some(table),
+            _ => None,
+        }
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.entries.keys().map(|key| key.as_str())
+    }
+
+    /// Sets a value by a dotted path, creating tables along the way. Fails if the path runs through a non-table
+    pub fn insert(&mut self, path: &str, value: Value) -> Result<Option<Value>, error::ConfigError> {
+        let keys: Vec<&str> = path.split('.').collect();
+        let (last, parents) = keys.split_last().unwrap();
+        let table = self.table_mut(parents)?;
+        Ok(table.entries.insert(last.to_string(), value))
+    }
+
+    fn table_mut(&mut self, keys: &[&str]) -> Result<&mut Table, error::ConfigError> {
+        let mut table = self;
+        for key in keys {
+            let value = table.entries.entry(key.to_string())
+                .or_insert_with(|| Value::Table(Table::new()));
+            let Value::Table(inner) = value else {
+                return Err(error::ConfigError::NotATable(key.to_string()));
+            };
+            table = inner;
+        }
+        Ok(table)
+    }
+
+    fn write(&self, prefix: &str, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for (key, value) in self.entries.iter() {
+            match value {
+                Value::Table(_) => {},
+                Value::Number(number) => writeln!(formatter, "{key} = {number:?}")?,
+                Value::Bool(value) => writeln!(formatter, "{key} = {value}")?,
+                Value::String(string) => writeln!(formatter, "{key} = \"{}\"", escape(string))?,
+                Value::Vector(vector) => {
+                    let items: Vec<String> = vector.iter().map(|number| format!("{number:?}")).collect();
+                    writeln!(formatter, "{key} = [{}]", items.join(", "))?
+                },
+            }
+        }
+        for (key, value) in self.entries.iter() {
+            if let Value::Table(table) = value {
+                let path = if prefix.is_empty() { key.clone() } else { format!("{prefix}.{key}") };
+                writeln!(formatter, "\n[{path}]")?;
+                table.write(&path, formatter)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn escape(string: &str) -> String {
+    string.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\t', "\\t")
+}
+
+fn syntax(line: usize, message: impl Into<String>) -> error::ConfigError {
+    error::ConfigError::Syntax(line, message.into())
+}
+
+fn is_key(key: &str) -> bool {
+    !key.is_empty() && key.split('.').all(|part| {
+        !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    })
+}
+
+/// Drops a trailing comment, ignoring `#` inside strings
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    let mut escaped = false;
+    for (idx, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '#' if !in_string => return &line[..idx],
+            _ => {},
+        }
+    }
+    line
+}
+
+fn parse_string(line: usize, source: &str) -> Result<String, error::ConfigError> {
+    let Some(inner) = source.strip_prefix('"').and_then(|source| source.strip_suffix('"')) else {
+        return Err(syntax(line, "unterminated string"));
+    };
+    let mut string = String::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c == '"' {
+            return Err(syntax(line, "unexpected quote in string"));
+        }
+        if c != '\\' {
+            string.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => string.push('\n'),
+            Some('t') => string.push('\t'),
+            Some('"') => string.push('"'),
+            Some('\\') => string.push('\\'),
+            _ => return Err(syntax(line, "unknown escape in string")),
+        }
+    }
+    Ok(string)
+}
+
+fn parse_number(line: usize, source: &str) -> Result<f64, error::ConfigError> {
+    source.parse().map_err(|_| syntax(line, format!("`{source}` is not a number")))
+}
+
+fn parse_value(line: usize, source: &str) -> Result<Value, error::ConfigError> {
+    match source {
+        "" => Err(syntax(line, "missing value")),
+        "true" => Ok(Value::Bool(true)),
+        "false" => Ok(Value::Bool(false)),
+        _ if source.starts_with('"') => parse_string(line, source).map(Value::String),
+        _ if source.starts_with('[') => {
+            let Some(inner) = source.strip_prefix('[').and_then(|source| source.strip_suffix(']')) else {
+                return Err(syntax(line, "unterminated vector"));
+            };
+            if inner.trim().is_empty() {
+                return Ok(Value::Vector(Vec::new()));
+            }
+            inner.split(',')
+                .map(|item| parse_number(line, item.trim()))
+                .collect::<Result<Vec<_>, _>>()
+                .map(Value::Vector)
+        },
+        _ => parse_number(line, source).map(Value::Number),
+    }
+}
+
+impl std::str::FromStr for Table {
+    type Err = error::ConfigError;
+    fn from_str(source: &str) -> Result<Table, error::ConfigError> {
+        let mut root = Table::new();
+        let mut section: Vec<String> = Vec::new();
+        for (idx, line) in source.lines().enumerate() {
+            let line_number = idx + 1;
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(header) = line.strip_prefix('[') {
+                let Some(header) = header.strip_suffix(']').map(str::trim) else {
+                    return Err(syntax(line_number, "unterminated table header"));
+                };
+                if !is_key(header) {
+                    return Err(syntax(line_number, format!("`{header}` is not a valid table name")));
+                }
+                section = header.split('.').map(String::from).collect();
+                let keys: Vec<&str> = section.iter().map(String::as_str).collect();
+                root.table_mut(&keys)?;
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(syntax(line_number, "expected `key = value`"));
+            };
+            let key = key.trim();
+            if !is_key(key) {
+                return Err(syntax(line_number, format!("`{key}` is not a valid key")));
+            }
+            let value = parse_value(line_number, value.trim())?;
+
+            let path = section.iter().map(String::as_str)
+                .chain(key.split('.'))
+                .collect::<Vec<_>>()
+                .join(".");
+            if root.get(&path).is_some() {
+                return Err(error::ConfigError::DuplicateKey(line_number, path));
+            }
+            root.insert(&path, value)?;
+        }
+        Ok(root)
+    }
+}
+
+impl std::fmt::Display for Table {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        self.write("", formatter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ Table, Value };
+
+    const SOURCE: &str = r#"
+# player tuning
+name = "player # one"
+offset = [0.0, 1.6, 0.0]
+
+[movement]
+max_speed = 10.0
+grounded = true
+crouch.height = 0.9
+"#;
+
+    #[test]
+    fn test_query_by_path() {
+        let table: Table = SOURCE.parse().unwrap();
+        assert_eq!(table.string("name"), Some("player # one"));
+        assert_eq!(table.vector("offset"), Some([0.0, 1.6, 0.0].as_slice()));
+        assert_eq!(table.number("movement.max_speed"), Some(10.0));
+        assert_eq!(table.bool("movement.grounded"), Some(true));
+        assert_eq!(table.number("movement.crouch.height"), Some(0.9));
+        assert!(table.table("movement.crouch").is_some());
+        assert_eq!(table.number("movement.missing"), None);
+        assert_eq!(table.number("name.inner"), None);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let table: Table = SOURCE.parse().unwrap();
+        let written = table.to_string();
+        let reread: Table = written.parse().unwrap();
+        assert_eq!(table, reread);
+    }
+
+    #[test]
+    fn test_errors_report_line() {
+        assert!(matches!("a = 1\na = 2".parse::<Table>(), Err(crate::error::ConfigError::DuplicateKey(2, _))));
+        assert!(matches!("a = \"open".parse::<Table>(), Err(crate::error::ConfigError::Syntax(1, _))));
+        assert!(matches!("\n\nvalue".parse::<Table>(), Err(crate::error::ConfigError::Syntax(3, _))));
+        assert!(matches!("a = 1\na.b = 2".parse::<Table>(), Err(crate::error::ConfigError::NotATable(_))));
+    }
+
+    #[test]
+    fn test_insert_creates_tables() {
+        let mut table = Table::new();
+        table.insert("camera.fov", Value::Number(70.0)).unwrap();
+        assert_eq!(table.number("camera.fov"), Some(70.0));
+        assert_eq!(table.keys().collect::<Vec<_>>(), vec!["camera"]);
+    }
+}
@@ -0,0 +1,320 @@
+use crate::{ asset, bundle, error };
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// A reference from one asset to another. Written as a uuid, a tag in the same bundle, or `bundle/tag`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Dependency {
+    Uuid(uuid::Uuid),
+    Tag { bundle: Option<String>, tag: String },
+}
+
+#[derive(Debug)]
+struct Node {
+    bundle: String,
+    tag: String,
+    dependencies: Vec<uuid::Uuid>,
+}
+
+/// Resolved references between every asset and bundle it was built from
+#[derive(Debug, Default)]
+pub struct Graph {
+    assets: HashMap<uuid::Uuid, Node>,
+    /// In the order they were added, which breaks ties in the load order
+    asset_order: Vec<uuid::Uuid>,
+    bundles: Vec<String>,
+    bundle_dependencies: HashMap<String, Vec<String>>,
+    errors: Vec<error::DependencyError>,
+}
+
+#[derive(Copy, Clone, PartialEq)]
+enum Visit {
+    InProgress,
+    Done,
+}
+
+impl Dependency {
+    pub fn tag(tag: impl Into<String>) -> Dependency {
+        Dependency::Tag { bundle: None, tag: tag.into() }
+    }
+}
+
+impl FromStr for Dependency {
+    type Err = std::convert::Infallible;
+    fn from_str(source: &str) -> Result<Dependency, Self::Err> {
+        if let Ok(uuid) = uuid::Uuid::from_str(source) {
+            return Ok(Dependency::Uuid(uuid));
+        }
+        Ok(match source.split_once('/') {
+            Some((bundle, tag)) => Dependency::Tag { bundle: Some(bundle.to_string()), tag: tag.to_string() },
+            None => Dependency::tag(source),
+        })
+    }
+}
+
+impl std::fmt::Display for Dependency {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Dependency::Uuid(uuid) => uuid.fmt(formatter),
+            Dependency::Tag { bundle: Some(bundle), tag } => write!(formatter, "{bundle}/{tag}"),
+            Dependency::Tag { bundle: None, tag } => tag.fmt(formatter),
+        }
+    }
+}
+
+/// Depth first, so dependencies come before whatever needs them. Back edges are cycles, reported and skipped
+fn sort<K: Clone + Eq + std::hash::Hash>(
+    keys: &[K],
+    edges: impl Fn(&K) -> Vec<K>,
+    name: impl Fn(&K) -> String,
+    errors: &mut Vec<error::DependencyError>
+) -> Vec<K> {
+    fn visit<K: Clone + Eq + std::hash::Hash>(
+        key: &K,
+        edges: &dyn Fn(&K) -> Vec<K>,
+        name: &dyn Fn(&K) -> String,
+        state: &mut HashMap<K, Visit>,
+        path: &mut Vec<K>,
+        order: &mut Vec<K>,
+        errors: &mut Vec<error::DependencyError>
+    ) {
+        match state.get(key) {
+            Some(Visit::Done) => return,
+            Some(Visit::InProgress) => {
+                let start = path.iter().position(|other| other == key).unwrap_or(0);
+                let mut cycle: Vec<String> = path[start..].iter().map(name).collect();
+                cycle.push(name(key));
+                errors.push(error::DependencyError::Cycle(cycle));
+                return;
+            },
+            None => {},
+        }
+
+        state.insert(key.clone(), Visit::InProgress);
+        path.push(key.clone());
+        for next in edges(key) {
+            visit(&next, edges, name, state, path, order, errors);
+        }
+        path.pop();
+        state.insert(key.clone(), Visit::Done);
+        order.push(key.clone());
+    }
+
+    let mut state = HashMap::new();
+    let mut order = Vec::new();
+    for key in keys {
+        visit(key, &edges, &name, &mut state, &mut Vec::new(), &mut order, errors);
+    }
+    order
+}
+
+impl Graph {
+    /// Resolves every reference in the bundles, collecting anything missing or cyclic into `errors`
+    pub fn new<'b>(bundles: impl IntoIterator<Item = &'b bundle::Bundle>) -> Graph {
+        let bundles: Vec<&bundle::Bundle> = bundles.into_iter().collect();
+        let mut graph = Graph::default();
+        for bundle in bundles.iter() {
+            graph.bundles.push(bundle.name.clone());
+            graph.bundle_dependencies.insert(bundle.name.clone(), Vec::new());
+        }
+
+        for bundle in bundles.iter() {
+            for required in bundle.dependencies.iter() {
+                if !graph.bundles.contains(required) {
+                    graph.errors.push(error::DependencyError::MissingBundle {
+                        from: bundle.name.clone(),
+                        bundle: required.clone()
+                    });
+                } else {
+                    graph.add_bundle_edge(&bundle.name, required);
+                }
+            }
+
+            for asset in bundle.group.iter() {
+                let mut dependencies = Vec::new();
+                for dependency in asset.dependencies.iter() {
+                    match graph.resolve(&bundles, bundle, dependency) {
+                        Ok((owner, uuid)) => {
+                            if owner != bundle.name {
+                                graph.add_bundle_edge(&bundle.name, &owner);
+                            }
+                            dependencies.push(uuid);
+                        },
+                        Err(e) => graph.errors.push(e),
+                    }
+                }
+                graph.asset_order.push(asset.uuid);
+                graph.assets.insert(asset.uuid, Node {
+                    bundle: bundle.name.clone(),
+                    tag: asset.tag.clone(),
+                    dependencies,
+                });
+            }
+        }
+
+        // sorting once up front reports every cycle
+        let mut errors = Vec::new();
+        graph.sorted_bundles(&mut errors);
+        graph.sorted_assets(&mut errors);
+        graph.errors.extend(errors);
+        graph
+    }
+
+    fn add_bundle_edge(&mut self, from: &str, to: &str) {
+        let edges = self.bundle_dependencies.entry(from.to_string()).or_default();
+        if !edges.iter().any(|edge| edge == to) {
+            edges.push(to.to_string());
+        }
+    }
+
+    fn resolve(
+        &self,
+        bundles: &[&bundle::Bundle],
+        from: &bundle::Bundle,
+        dependency: &Dependency
+    ) -> Result<(String, uuid::Uuid), error::DependencyError> {
+        let missing = || error::DependencyError::Missing {
+            asset: from.name.clone(),
+            dependency: dependency.to_string()
+        };
+        match dependency {
+            Dependency::Uuid(uuid) => bundles.iter()
+                .find(|bundle| bundle.group.iter().any(|asset| asset.uuid == *uuid))
+                .map(|bundle| (bundle.name.clone(), *uuid))
+                .ok_or_else(missing),
+            Dependency::Tag { bundle, tag } => {
+                let owner = match bundle {
+                    None => from,
+                    Some(name) => *bundles.iter()
+                        .find(|bundle| bundle.name == *name)
+                        .ok_or_else(|| error::DependencyError::MissingBundle {
+                            from: from.name.clone(),
+                            bundle: name.clone()
+                        })?,
+                };
+                owner.asset(tag.clone())
+                    .map(|asset| (owner.name.clone(), asset.uuid))
+                    .ok_or_else(missing)
+            },
+        }
+    }
+
+    fn sorted_assets(&self, errors: &mut Vec<error::DependencyError>) -> Vec<uuid::Uuid> {
+        sort(
+            &self.asset_order,
+            |uuid| self.assets.get(uuid).map(|node| node.dependencies.clone()).unwrap_or_default(),
+            |uuid| self.assets.get(uuid)
+                .map(|node| format!("{}/{}", node.bundle, node.tag))
+                .unwrap_or_else(|| uuid.to_string()),
+            errors
+        )
+    }
+
+    fn sorted_bundles(&self, errors: &mut Vec<error::DependencyError>) -> Vec<String> {
+        sort(
+            &self.bundles,
+            |bundle| self.bundle_dependencies.get(bundle).cloned().unwrap_or_default(),
+            |bundle| bundle.clone(),
+            errors
+        )
+    }
+
+    /// Missing references and cycles found while building the graph
+    pub fn errors(&self) -> &[error::DependencyError] {
+        &self.errors
+    }
+
+    /// Every asset, each after the assets it depends on. Cycles are broken where they were found
+    pub fn load_order(&self) -> Vec<uuid::Uuid> {
+        self.sorted_assets(&mut Vec::new())
+    }
+
+    /// Every bundle, each after the bundles it depends on
+    pub fn bundle_order(&self) -> Vec<String> {
+        self.sorted_bundles(&mut Vec::new())
+    }
+
+    /// The assets this asset references directly
+    pub fn dependencies(&self, asset: &asset::Metadata) -> &[uuid::Uuid] {
+        self.assets.get(&asset.uuid)
+            .map(|node| node.dependencies.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// The assets that reference this asset directly
+    pub fn dependents(&self, asset: &asset::Metadata) -> Vec<uuid::Uuid> {
+        self.asset_order.iter()
+            .filter(|uuid| self.assets[uuid].dependencies.contains(&asset.uuid))
+            .copied()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ Dependency, Graph };
+    use crate::{ asset, bundle, error, model, texture };
+
+    fn texture(tag: &str) -> asset::Metadata {
+        asset::Metadata::new(tag, "", asset::Unit::Texture(texture::Texture { colour_space: texture::ColourSpace::SRGBA, compression: None }))
+    }
+
+    fn model(tag: &str, dependencies: &[&str]) -> asset::Metadata {
+        let mut metadata = asset::Metadata::new(tag, "", asset::Unit::Model(model::Model { lods: None }));
+        metadata.dependencies = dependencies.iter().map(|dependency| dependency.parse().unwrap()).collect();
+        metadata
+    }
+
+    #[test]
+    fn test_parse_dependency() {
+        let uuid = uuid::Uuid::new_v4();
+        assert_eq!(uuid.to_string().parse::<Dependency>().unwrap(), Dependency::Uuid(uuid));
+        assert_eq!("wall".parse::<Dependency>().unwrap(), Dependency::tag("wall"));
+        assert_eq!(
+            "core/wall".parse::<Dependency>().unwrap(),
+            Dependency::Tag { bundle: Some("core".to_string()), tag: "wall".to_string() }
+        );
+    }
+
+    #[test]
+    fn test_load_order() {
+        let core = bundle::Bundle {
+            name: "core".to_string(),
+            group: vec![texture("wall")],
+            dependencies: Vec::new(),
+        };
+        let crate_model = model("crate", &["core/wall", "lid"]);
+        let lid = model("lid", &[]);
+        let props = bundle::Bundle {
+            name: "props".to_string(),
+            group: vec![crate_model.clone(), lid.clone()],
+            dependencies: Vec::new(),
+        };
+
+        let graph = Graph::new([&props, &core]);
+        assert!(graph.errors().is_empty());
+        assert_eq!(graph.bundle_order(), vec!["core".to_string(), "props".to_string()]);
+
+        let order = graph.load_order();
+        let position = |uuid| order.iter().position(|other| *other == uuid).unwrap();
+        assert!(position(core.group[0].uuid) < position(crate_model.uuid));
+        assert!(position(lid.uuid) < position(crate_model.uuid));
+        assert_eq!(graph.dependents(&lid), vec![crate_model.uuid]);
+    }
+
+    #[test]
+    fn test_missing_and_cycles() {
+        let props = bundle::Bundle {
+            name: "props".to_string(),
+            group: vec![model("a", &["b"]), model("b", &["a"]), model("c", &["missing", "other/thing"])],
+            dependencies: Vec::new(),
+        };
+
+        let graph = Graph::new([&props]);
+        let errors = graph.errors();
+        assert!(errors.iter().any(|e| matches!(e, error::DependencyError::Missing { dependency, .. } if dependency == "missing")));
+        assert!(errors.iter().any(|e| matches!(e, error::DependencyError::MissingBundle { bundle, .. } if bundle == "other")));
+        assert!(errors.iter().any(|e| matches!(e, error::DependencyError::Cycle(cycle) if cycle.len() == 3)));
+        assert_eq!(graph.load_order().len(), 3);
+    }
+}
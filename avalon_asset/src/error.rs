@@ -1,3 +1,4 @@
+use crate::asset;
 use thiserror::Error;
 use miniserde;
 
@@ -15,6 +16,12 @@ pub enum UnitConversionError {
     UnitIsNotText,
 }
 
+#[derive(Debug, Clone, Error)]
+pub enum PartError {
+    #[error("`{0}` is not `mesh/<mesh>/primitive/<primitive>` or `image/<image>`")]
+    Invalid(String),
+}
+
 #[derive(Debug, Error)]
 pub enum PackError {
     #[error("Invalid pack directory: {0}")]
@@ -22,7 +29,65 @@ pub enum PackError {
     #[error("Error while modifying archive: {0}")]
     ZipError(#[from] zip::result::ZipError),
     #[error("Error with model: {0}")]
-    ModelError(#[from] ModelUnpackError)
+    ModelError(#[from] ModelUnpackError),
+    #[error("Error with config: {0}")]
+    ConfigError(#[from] ConfigError),
+    #[error("Error with text: {0}")]
+    TextError(#[from] TextError),
+    #[error("Error with dependencies: {0}")]
+    DependencyError(#[from] DependencyError),
+    #[error("Error with texture: {0}")]
+    TextureError(#[from] TextureError),
+    #[error("No stored data for asset \"{0}\"")]
+    MissingData(String)
+}
+
+#[derive(Debug, Error)]
+pub enum MigrationError {
+    #[error("Cannot read bundle: {0}")]
+    Unpack(#[from] UnpackError),
+    #[error("Cannot write bundle: {0}")]
+    Pack(#[from] PackError),
+    #[error("Cannot replace bundle: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum DependencyError {
+    #[error("`{asset}` depends on `{dependency}`, which does not exist")]
+    Missing { asset: String, dependency: String },
+    #[error("`{from}` depends on bundle `{bundle}`, which is not loaded")]
+    MissingBundle { from: String, bundle: String },
+    #[error("Dependency cycle: {}", .0.join(" -> "))]
+    Cycle(Vec<String>),
+}
+
+#[derive(Debug, Error)]
+pub enum TextureError {
+    #[error("Cannot decode image: {0}")]
+    Image(#[from] image::ImageError),
+}
+
+#[derive(Debug, Error)]
+pub enum TextError {
+    #[error("Text is not valid UTF-8")]
+    InvalidUtf8,
+    #[error("Line {0}: {1}")]
+    Syntax(usize, String),
+    #[error("Line {0}: key `{1}` is already set")]
+    DuplicateKey(usize, String),
+}
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("Config is not valid UTF-8")]
+    InvalidUtf8,
+    #[error("Line {0}: {1}")]
+    Syntax(usize, String),
+    #[error("Line {0}: key `{1}` is already set")]
+    DuplicateKey(usize, String),
+    #[error("`{0}` is a value, not a table")]
+    NotATable(String),
 }
 
 #[derive(Debug, Error)]
@@ -31,10 +96,24 @@ pub enum ModelUnpackError {
     ObjError(#[from] obj::ObjError),
     #[error("Obj file needs to have Position/Texture/Normal data defined")]
     InvalidFormat,
-    #[error("Exported model has a face with > 4 vertices")]
-    TooManyVertices,
     #[error("Exported model could not be triangulated: {0}")]
-    TriangulationError(#[from] NgonError)
+    TriangulationError(#[from] NgonError),
+    #[error("Cannot read model from glTF: {0}")]
+    GltfError(#[from] gltf::Error),
+    #[error("glTF models need to be split into their parts before packing")]
+    Unexpanded,
+    #[error("Model has no {0}")]
+    MissingPart(asset::Part),
+    #[error("Part {0} does not hold this kind of asset")]
+    PartMismatch(asset::Part),
+    #[error("glTF primitive has no positions")]
+    MissingPositions,
+    #[error("glTF primitive refers to vertex {0}, which does not exist")]
+    IndexOutOfBounds(u32),
+    #[error("glTF primitive is drawn as {0}, only triangles are supported")]
+    UnsupportedMode(String),
+    #[error("glTF image {0} does not match its size and format")]
+    InvalidImage(usize),
 }
 
 #[derive(Debug, Error)]
@@ -49,12 +128,18 @@ pub enum UnpackError {
     UnexpectedFileStructure,
     #[error("Stored data not correct size. Read: {0} | Expected: {1}")]
     SizeMismatch(usize, usize),
+    #[error("Bundle format version {found} is newer than the supported version {supported}")]
+    UnsupportedVersion { found: u32, supported: u32 },
+    #[error("Asset {0} is listed but its metadata or data is missing")]
+    MissingAsset(String),
+    #[error("Asset \"{asset}\" is corrupt. Expected hash {expected}, found {found}")]
+    HashMismatch { asset: String, expected: String, found: String },
 }
 
 #[derive(Debug, Copy, Clone, Error)]
 pub enum NgonError {
-    #[error("Cannot triangulate given quad")]
-    CannotTriangulateQuad,
-    #[error("Cannot triangulate models with faces that have more than 4 vertices")]
-    NgonIsNotQuadOrTriangle
+    #[error("Face has {0} vertices, at least 3 are needed")]
+    TooFewVertices(usize),
+    #[error("Face has no area to triangulate")]
+    Degenerate
 }
@@ -3,6 +3,7 @@
 pub mod asset;
 pub mod bundle;
 pub mod config;
+pub mod dependency;
 pub mod error;
 pub mod model;
 pub mod packed;
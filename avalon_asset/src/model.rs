@@ -1,7 +1,13 @@
 pub mod packed;
 pub mod ngon;
+pub mod optimise;
+#[cfg(feature = "write")]
+pub mod gltf;
 
 use miniserde::{ Deserialize, Serialize };
 
-#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
-pub struct Model {}
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Model {
+    /// Simplified levels of detail generated when packing, none when left out
+    pub lods: Option<u32>,
+}
@@ -0,0 +1,391 @@
+use crate::asset::{ Metadata, Part, Unit };
+use crate::model::ngon::{ Triangle, Vertex };
+use crate::model::packed::PackedModel;
+use crate::texture::{ ColourSpace, Compression, Texture };
+use crate::{ dependency, error };
+use std::path::Path;
+
+/// A glTF 2.0 file with its buffers and images loaded, for packing the parts it was split into
+pub struct Scene {
+    document: ::gltf::Document,
+    buffers: Vec<::gltf::buffer::Data>,
+    images: Vec<::gltf::image::Data>,
+}
+
+/// The textures a material can reference, with the colour space each is sampled in
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Role {
+    BaseColour,
+    MetallicRoughness,
+    Normal,
+    Occlusion,
+    Emissive,
+}
+
+impl Role {
+    const ALL: [Role; 5] = [Role::BaseColour, Role::MetallicRoughness, Role::Normal, Role::Occlusion, Role::Emissive];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Role::BaseColour => "base_colour",
+            Role::MetallicRoughness => "metallic_roughness",
+            Role::Normal => "normal",
+            Role::Occlusion => "occlusion",
+            Role::Emissive => "emissive",
+        }
+    }
+
+    fn image(&self, material: &::gltf::Material) -> Option<usize> {
+        let texture = match self {
+            Role::BaseColour => material.pbr_metallic_roughness().base_color_texture()?.texture(),
+            Role::MetallicRoughness => material.pbr_metallic_roughness().metallic_roughness_texture()?.texture(),
+            Role::Normal => material.normal_texture()?.texture(),
+            Role::Occlusion => material.occlusion_texture()?.texture(),
+            Role::Emissive => material.emissive_texture()?.texture(),
+        };
+        Some(texture.source().index())
+    }
+
+    /// Normal maps only need two channels, so they get BC5 whenever anything is compressed
+    fn texture(&self, compression: Option<Compression>) -> Texture {
+        match self {
+            Role::BaseColour | Role::Emissive => Texture { colour_space: ColourSpace::SRGBA, compression },
+            Role::Normal => Texture { colour_space: ColourSpace::RGBA, compression: compression.map(|_| Compression::BC5) },
+            Role::MetallicRoughness | Role::Occlusion => Texture { colour_space: ColourSpace::RGBA, compression },
+        }
+    }
+}
+
+pub fn is_gltf(path: impl AsRef<Path>) -> bool {
+    path.as_ref().extension()
+        .and_then(|extension| extension.to_ascii_lowercase().to_str().map(|extension| extension == "gltf" || extension == "glb"))
+        .unwrap_or(false)
+}
+
+/// Splits a glTF model into a model per mesh primitive and a texture per texture its materials reference.
+/// Primitives depend on their material's textures, tagged `<tag>.<material>.<role>` such as
+/// `crate.wood.base_colour`. Anything other than an unsplit glTF model is returned as is
+pub fn expand(metadata: Metadata, compression: Option<Compression>) -> Result<Vec<Metadata>, error::ModelUnpackError> {
+    let Some(path) = metadata.filepath.as_ref() else {
+        return Ok(vec![metadata]);
+    };
+    if !matches!(metadata.unit, Unit::Model(_)) || metadata.part.is_some() || !is_gltf(path) {
+        return Ok(vec![metadata]);
+    }
+    let gltf = ::gltf::Gltf::open(path)?;
+
+    let material_names = unique_names(gltf.materials().map(|material| material.name()), "material");
+    let mut textures = Vec::new();
+    let mut material_textures = vec![Vec::new(); material_names.len()];
+    for (material, name) in gltf.materials().zip(material_names.iter()) {
+        for role in Role::ALL {
+            let Some(image) = role.image(&material) else {
+                continue;
+            };
+            let tag = format!("{}.{name}.{}", metadata.tag, role.name());
+            material_textures[material.index().unwrap()].push(dependency::Dependency::tag(&tag));
+            textures.push(part(&metadata, tag, Unit::Texture(role.texture(compression)), Part::Image(image), Vec::new()));
+        }
+    }
+
+    let primitive_count: usize = gltf.meshes().map(|mesh| mesh.primitives().len()).sum();
+    let mesh_names = unique_names(gltf.meshes().map(|mesh| mesh.name()), "mesh");
+    let mut parts = Vec::new();
+    for (mesh, mesh_name) in gltf.meshes().zip(mesh_names.iter()) {
+        for primitive in mesh.primitives() {
+            let tag = match (primitive_count, mesh.primitives().len()) {
+                (1, _) => metadata.tag.clone(),
+                (_, 1) => format!("{}.{mesh_name}", metadata.tag),
+                _ => format!("{}.{mesh_name}.{}", metadata.tag, primitive.index()),
+            };
+            let mut dependencies = metadata.dependencies.clone();
+            if let Some(material) = primitive.material().index() {
+                dependencies.extend(material_textures[material].iter().cloned());
+            }
+            let part_of = Part::Primitive { mesh: mesh.index(), primitive: primitive.index() };
+            parts.push(part(&metadata, tag, metadata.unit, part_of, dependencies));
+        }
+    }
+    parts.extend(textures);
+    Ok(parts)
+}
+
+/// Names as written in the file, falling back to `<fallback><index>` when missing or shared. `/` would read
+/// as a bundle in a dependency, so it is replaced
+fn unique_names<'a>(names: impl Iterator<Item = Option<&'a str>>, fallback: &str) -> Vec<String> {
+    let names: Vec<_> = names.collect();
+    names.iter()
+        .enumerate()
+        .map(|(idx, name)| match name {
+            Some(name) if !name.is_empty() && names.iter().filter(|other| **other == Some(*name)).count() == 1 => {
+                name.replace('/', "_")
+            },
+            _ => format!("{fallback}{idx}"),
+        })
+        .collect()
+}
+
+/// Parts keep the same uuid as long as the file they come from does and their tag is unchanged
+fn part(metadata: &Metadata, tag: String, unit: Unit, part: Part, dependencies: Vec<dependency::Dependency>) -> Metadata {
+    let seed = [metadata.uuid.as_bytes().as_slice(), tag.as_bytes()].concat();
+    let high = crate::packed::content_hash(&seed);
+    let low = crate::packed::content_hash(&[seed.as_slice(), b"\0"].concat());
+    Metadata {
+        uuid: uuid::Builder::from_random_bytes(((high as u128) << 64 | low as u128).to_be_bytes()).into_uuid(),
+        tag,
+        filepath: metadata.filepath.clone(),
+        unit,
+        dependencies,
+        part: Some(part),
+    }
+}
+
+impl Scene {
+    pub fn open(path: impl AsRef<Path>) -> Result<Scene, error::ModelUnpackError> {
+        let (document, buffers, images) = ::gltf::import(path)?;
+        Ok(Scene {
+            document,
+            buffers,
+            images,
+        })
+    }
+
+    /// Positions, normals, uvs and tangents of one primitive, in the space of its mesh. Missing normals are
+    /// flat and missing tangents are worked out from the uvs
+    pub fn pack_primitive(&self, mesh: usize, primitive: usize) -> Result<PackedModel, error::ModelUnpackError> {
+        let missing = error::ModelUnpackError::MissingPart(Part::Primitive { mesh, primitive });
+        let primitive = self.document.meshes().nth(mesh)
+            .and_then(|mesh| mesh.primitives().nth(primitive))
+            .ok_or(missing)?;
+        let reader = primitive.reader(|buffer| Some(&self.buffers[buffer.index()][..]));
+
+        let positions: Vec<[f32; 3]> = reader.read_positions()
+            .ok_or(error::ModelUnpackError::MissingPositions)?
+            .collect();
+        let normals: Option<Vec<[f32; 3]>> = reader.read_normals().map(Iterator::collect);
+        let tangents: Option<Vec<[f32; 4]>> = reader.read_tangents().map(Iterator::collect);
+        // glTF puts the uv origin at the top left, obj at the bottom left
+        let uvs: Option<Vec<[f32; 2]>> = reader.read_tex_coords(0)
+            .map(|uvs| uvs.into_f32().map(|[u, v]| [u, 1.0 - v]).collect());
+        let indices: Vec<u32> = match reader.read_indices() {
+            Some(indices) => indices.into_u32().collect(),
+            None => (0..positions.len() as u32).collect(),
+        };
+        if let Some(&index) = indices.iter().find(|index| **index as usize >= positions.len()) {
+            return Err(error::ModelUnpackError::IndexOutOfBounds(index));
+        }
+
+        let corners: Vec<[u32; 3]> = match primitive.mode() {
+            ::gltf::mesh::Mode::Triangles => indices.chunks_exact(3)
+                .map(|corners| [corners[0], corners[1], corners[2]])
+                .collect(),
+            // every other strip triangle is wound the other way
+            ::gltf::mesh::Mode::TriangleStrip => indices.windows(3)
+                .enumerate()
+                .map(|(idx, corners)| if idx % 2 == 0 {
+                    [corners[0], corners[1], corners[2]]
+                } else {
+                    [corners[1], corners[0], corners[2]]
+                })
+                .collect(),
+            ::gltf::mesh::Mode::TriangleFan => indices.windows(2)
+                .skip(1)
+                .map(|corners| [indices[0], corners[0], corners[1]])
+                .collect(),
+            mode => return Err(error::ModelUnpackError::UnsupportedMode(format!("{mode:?}"))),
+        };
+
+        let triangles: Vec<Triangle> = corners.iter()
+            .map(|corners| {
+                let face_normal = face_normal(corners.map(|corner| positions[corner as usize]));
+                let vertices = corners.map(|corner| {
+                    let normal = normals.as_ref().map_or(face_normal, |normals| normals[corner as usize]);
+                    let uv = uvs.as_ref().map_or([0.0; 2], |uvs| uvs[corner as usize]);
+                    (corner, normal, uv)
+                });
+                match (tangents.as_ref(), uvs.is_some()) {
+                    (Some(tangents), _) => with_tangents(vertices, |corner, _| {
+                        let [x, y, z, _] = tangents[corner as usize];
+                        [x, y, z]
+                    }),
+                    (None, true) => Triangle::new(&positions, vertices),
+                    // without uvs there is no tangent to speak of, so any direction along the surface does
+                    (None, false) => with_tangents(vertices, |_, normal| perpendicular(normal)),
+                }
+            })
+            .collect();
+
+        let (min_bounds, max_bounds) = positions.iter().fold(
+            ([f32::INFINITY; 3], [f32::NEG_INFINITY; 3]),
+            |(min_bounds, max_bounds), position| (
+                [0, 1, 2].map(|axis| min_bounds[axis].min(position[axis])),
+                [0, 1, 2].map(|axis| max_bounds[axis].max(position[axis])),
+            )
+        );
+
+        Ok(PackedModel {
+            index_count: 3 * triangles.len(),
+            min_bounds,
+            max_bounds,
+            positions,
+            triangles,
+            lods: Vec::new(),
+        })
+    }
+
+    /// An image the file embeds or references, as tightly packed RGBA8 texels
+    pub fn image(&self, image: usize) -> Result<image::RgbaImage, error::ModelUnpackError> {
+        use ::gltf::image::Format;
+        use image::{ DynamicImage, ImageBuffer };
+
+        let data = self.images.get(image).ok_or(error::ModelUnpackError::MissingPart(Part::Image(image)))?;
+        let (width, height) = (data.width, data.height);
+        let words = || data.pixels.chunks_exact(2).map(|word| u16::from_ne_bytes([word[0], word[1]])).collect::<Vec<_>>();
+        let floats = || data.pixels.chunks_exact(4).map(|word| f32::from_ne_bytes([word[0], word[1], word[2], word[3]])).collect::<Vec<_>>();
+        let decoded = match data.format {
+            Format::R8 => ImageBuffer::from_raw(width, height, data.pixels.clone()).map(DynamicImage::ImageLuma8),
+            Format::R8G8 => ImageBuffer::from_raw(width, height, data.pixels.clone()).map(DynamicImage::ImageLumaA8),
+            Format::R8G8B8 => ImageBuffer::from_raw(width, height, data.pixels.clone()).map(DynamicImage::ImageRgb8),
+            Format::R8G8B8A8 => ImageBuffer::from_raw(width, height, data.pixels.clone()).map(DynamicImage::ImageRgba8),
+            Format::R16 => ImageBuffer::from_raw(width, height, words()).map(DynamicImage::ImageLuma16),
+            Format::R16G16 => ImageBuffer::from_raw(width, height, words()).map(DynamicImage::ImageLumaA16),
+            Format::R16G16B16 => ImageBuffer::from_raw(width, height, words()).map(DynamicImage::ImageRgb16),
+            Format::R16G16B16A16 => ImageBuffer::from_raw(width, height, words()).map(DynamicImage::ImageRgba16),
+            Format::R32G32B32FLOAT => ImageBuffer::from_raw(width, height, floats()).map(DynamicImage::ImageRgb32F),
+            Format::R32G32B32A32FLOAT => ImageBuffer::from_raw(width, height, floats()).map(DynamicImage::ImageRgba32F),
+        };
+        decoded
+            .map(|decoded| decoded.to_rgba8())
+            .ok_or(error::ModelUnpackError::InvalidImage(image))
+    }
+}
+
+fn with_tangents(vertices: [(u32, [f32; 3], [f32; 2]); 3], tangent: impl Fn(u32, [f32; 3]) -> [f32; 3]) -> Triangle {
+    Triangle {
+        vertices: vertices.map(|(index, normal, uv)| Vertex {
+            index,
+            normal,
+            tangent: tangent(index, normal),
+            uv,
+        }),
+    }
+}
+
+fn face_normal([p0, p1, p2]: [[f32; 3]; 3]) -> [f32; 3] {
+    let edge1 = [p1[0] - p0[0], p1[1] - p0[1], p1[2] - p0[2]];
+    let edge2 = [p2[0] - p0[0], p2[1] - p0[1], p2[2] - p0[2]];
+    normalize([
+        edge1[1] * edge2[2] - edge1[2] * edge2[1],
+        edge1[2] * edge2[0] - edge1[0] * edge2[2],
+        edge1[0] * edge2[1] - edge1[1] * edge2[0],
+    ])
+}
+
+fn perpendicular(normal: [f32; 3]) -> [f32; 3] {
+    // crossed with whichever axis is furthest from parallel
+    let [x, y, z] = normal;
+    if x.abs() < 0.9 {
+        normalize([0.0, z, -y])
+    } else {
+        normalize([-z, 0.0, x])
+    }
+}
+
+fn normalize(v: [f32; 3]) -> [f32; 3] {
+    let length = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+    if length == 0.0 {
+        return v;
+    }
+    v.map(|component| component / length)
+}
+
+#[cfg(test)]
+mod gltf_tests {
+    use super::{ expand, Scene };
+    use crate::asset::{ Metadata, Part, Unit };
+    use crate::dependency::Dependency;
+    use crate::model::Model;
+    use crate::texture::{ ColourSpace, Compression, Texture };
+
+    /// A unit quad facing +z with a textured material, as an exporter would write it
+    fn write_quad(directory: &std::path::Path) -> std::path::PathBuf {
+        let mut buffer = Vec::new();
+        for position in [[0.0f32, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0], [0.0, 1.0, 0.0]] {
+            buffer.extend(position.iter().flat_map(|component| component.to_le_bytes()));
+        }
+        for _ in 0..4 {
+            buffer.extend([0.0f32, 0.0, 1.0].iter().flat_map(|component| component.to_le_bytes()));
+        }
+        for uv in [[0.0f32, 1.0], [1.0, 1.0], [1.0, 0.0], [0.0, 0.0]] {
+            buffer.extend(uv.iter().flat_map(|component| component.to_le_bytes()));
+        }
+        for index in [0u16, 1, 2, 0, 2, 3] {
+            buffer.extend(index.to_le_bytes());
+        }
+        std::fs::write(directory.join("quad.bin"), &buffer).unwrap();
+        image::RgbaImage::from_pixel(4, 2, image::Rgba([200, 100, 50, 255])).save(directory.join("wood.png")).unwrap();
+
+        let path = directory.join("quad.gltf");
+        std::fs::write(&path, r#"{
+            "asset": { "version": "2.0" },
+            "buffers": [{ "uri": "quad.bin", "byteLength": 140 }],
+            "bufferViews": [
+                { "buffer": 0, "byteOffset": 0, "byteLength": 128 },
+                { "buffer": 0, "byteOffset": 128, "byteLength": 12 }
+            ],
+            "accessors": [
+                { "bufferView": 0, "byteOffset": 0, "componentType": 5126, "count": 4, "type": "VEC3", "min": [0, 0, 0], "max": [1, 1, 0] },
+                { "bufferView": 0, "byteOffset": 48, "componentType": 5126, "count": 4, "type": "VEC3" },
+                { "bufferView": 0, "byteOffset": 96, "componentType": 5126, "count": 4, "type": "VEC2" },
+                { "bufferView": 1, "componentType": 5123, "count": 6, "type": "SCALAR" }
+            ],
+            "images": [{ "uri": "wood.png" }],
+            "textures": [{ "source": 0 }],
+            "materials": [{ "name": "wood", "pbrMetallicRoughness": { "baseColorTexture": { "index": 0 } } }],
+            "meshes": [{ "name": "quad", "primitives": [{
+                "attributes": { "POSITION": 0, "NORMAL": 1, "TEXCOORD_0": 2 },
+                "indices": 3,
+                "material": 0
+            }] }]
+        }"#).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_expand_and_pack() {
+        let directory = std::env::temp_dir().join(format!("avalon_gltf_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = write_quad(&directory);
+
+        let metadata = Metadata::new("prop", &path, Unit::Model(Model { lods: None }));
+        let parts = expand(metadata.clone(), Some(Compression::BC7)).unwrap();
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].tag, "prop");
+        assert_eq!(parts[0].part, Some(Part::Primitive { mesh: 0, primitive: 0 }));
+        assert_eq!(parts[0].dependencies, vec![Dependency::tag("prop.wood.base_colour")]);
+        assert_eq!(parts[1].tag, "prop.wood.base_colour");
+        assert_eq!(parts[1].part, Some(Part::Image(0)));
+        assert_eq!(parts[1].unit, Unit::Texture(Texture { colour_space: ColourSpace::SRGBA, compression: Some(Compression::BC7) }));
+        // the same file expands to the same ids
+        let again = expand(metadata, None).unwrap();
+        assert_eq!(parts.iter().map(|part| part.uuid).collect::<Vec<_>>(), again.iter().map(|part| part.uuid).collect::<Vec<_>>());
+
+        let scene = Scene::open(&path).unwrap();
+        let model = scene.pack_primitive(0, 0).unwrap();
+        assert_eq!(model.positions.len(), 4);
+        assert_eq!(model.index_count, 6);
+        assert_eq!((model.min_bounds, model.max_bounds), ([0.0; 3], [1.0, 1.0, 0.0]));
+        let first = model.triangles[0].vertices[0];
+        assert_eq!((first.index, first.normal, first.uv), (0, [0.0, 0.0, 1.0], [0.0, 0.0]));
+        for vertex in model.triangles.iter().flat_map(|triangle| triangle.vertices) {
+            assert!((vertex.tangent[0] - 1.0).abs() < 1e-5, "{:?}", vertex.tangent);
+        }
+        assert!(scene.pack_primitive(1, 0).is_err());
+
+        let image = scene.image(0).unwrap();
+        assert_eq!((image.width(), image.height()), (4, 2));
+        assert_eq!(image.get_pixel(0, 0).0, [200, 100, 50, 255]);
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
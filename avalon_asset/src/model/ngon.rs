@@ -69,138 +69,121 @@ impl Vertex {
     }
 }
 
-#[derive(Debug, Copy, Clone)]
-pub struct Quad {
-    pub vertices: [Vertex; 4],
-    pub edges: [(usize, usize); 4]
+/// A face with any number of vertices, wound counter-clockwise
+#[derive(Debug, Clone)]
+pub struct Polygon {
+    pub vertices: Vec<Vertex>
 }
 
-impl Quad {
-    pub fn new(vertices: [(u32, [f32; 3], [f32; 2]); 4]) -> Quad {
-        Quad {
-            vertices: [
-                Vertex {
-                    index: vertices[0].0,
-                    normal: vertices[0].1,
-                    uv: vertices[0].2,
-                    tangent: [0.0; 3]
-                },
-                Vertex {
-                    index: vertices[1].0,
-                    normal: vertices[1].1,
-                    uv: vertices[1].2,
-                    tangent: [0.0; 3]
-                },
-                Vertex {
-                    index: vertices[2].0,
-                    normal: vertices[2].1,
-                    uv: vertices[2].2,
-                    tangent: [0.0; 3]
-                },
-                Vertex {
-                    index: vertices[3].0,
-                    normal: vertices[3].1,
-                    uv: vertices[3].2,
-                    tangent: [0.0; 3]
-                },
-            ],
-            edges: [
-                (0, 1),
-                (1, 2),
-                (2, 3),
-                (3, 0)
-            ]
+impl Polygon {
+    pub fn new(vertices: impl IntoIterator<Item = (u32, [f32; 3], [f32; 2])>) -> Polygon {
+        Polygon {
+            vertices: vertices.into_iter()
+                .map(|(index, normal, uv)| Vertex { index, normal, uv, tangent: [0.0; 3] })
+                .collect()
         }
     }
-    pub fn to_triangles(self, positions: &[[f32; 3]]) -> Result<(Triangle, Triangle), error::NgonError> {
-        // Triangulate quad by splitting on shortest possible non-existing edge
-        let edges = self.edges.map(|(v0, v1)| (v0.min(v1), v1.max(v0)));
-        let mut non_connected_edges = vec![
-            (0, 1),
-            (0, 2),
-            (0, 3),
-            (1, 2),
-            (1, 3),
-            (2, 3),
-        ];
 
-        // Remove edges that exist in the polygon
-        for edge in edges {
-            let mut remove_idx: Option<usize> = None;
-            for (idx, test_edge) in non_connected_edges.iter().enumerate() {
-                if *test_edge == edge {
-                    remove_idx = Some(idx);
-                    break;
-                }
-            }
-
-            if let Some(idx) = remove_idx {
-                non_connected_edges.remove(idx);
-            }
+    /// Ear clipping on the polygon projected onto its best-fit plane. Of the ears available the one
+    /// with the shortest new edge is clipped first, which keeps slivers out of convex faces
+    pub fn to_triangles(&self, positions: &[[f32; 3]]) -> Result<Vec<Triangle>, error::NgonError> {
+        let count = self.vertices.len();
+        if count < 3 {
+            return Err(error::NgonError::TooFewVertices(count));
         }
-
-        if non_connected_edges.is_empty() {
-            return Err(error::NgonError::CannotTriangulateQuad);
+        let points: Vec<[f64; 3]> = self.vertices.iter()
+            .map(|vertex| positions[vertex.index as usize].map(|p| p as f64))
+            .collect();
+
+        // Newell's method, the normal of the plane that fits the polygon best, twice its area long
+        let mut normal = [0.0f64; 3];
+        for (idx, current) in points.iter().enumerate() {
+            let next = points[(idx + 1) % count];
+            normal[0] += (current[1] - next[1]) * (current[2] + next[2]);
+            normal[1] += (current[2] - next[2]) * (current[0] + next[0]);
+            normal[2] += (current[0] - next[0]) * (current[1] + next[1]);
         }
-
-        // Find smallest edge
-        let mut smallest_distance = f32::INFINITY;
-        let mut smallest_edge = None;
-        for edge in non_connected_edges {
-            let v0 = self.vertices[edge.0];
-            let v1 = self.vertices[edge.1];
-
-            let p0 = positions[v0.index as usize];
-            let p1 = positions[v1.index as usize];
-
-            let dx = p0[0] - p1[0];
-            let dy = p0[1] - p1[1];
-            let dz = p0[2] - p1[2];
-
-            let distance = dx * dx + dy * dy + dz * dz;
-            if distance < smallest_distance {
-                smallest_distance = distance;
-                smallest_edge = Some(edge);
-            }
+        let length = dot(normal, normal).sqrt();
+        let extent = points.iter()
+            .flat_map(|point| points.iter().map(move |other| distance_squared(*point, *other)))
+            .fold(0.0, f64::max);
+        if length <= extent * 1e-10 {
+            return Err(error::NgonError::Degenerate);
         }
-
-        let Some(smallest_edge) = smallest_edge else {
-            return Err(error::NgonError::CannotTriangulateQuad);
+        let normal = normal.map(|n| n / length);
+
+        // Project onto axes where counter-clockwise around the normal stays counter-clockwise
+        let helper = if normal[0].abs() < 0.9 { [1.0, 0.0, 0.0] } else { [0.0, 1.0, 0.0] };
+        let u = normalise(cross(helper, normal));
+        let v = cross(normal, u);
+        let flat: Vec<[f64; 2]> = points.iter().map(|p| [dot(*p, u), dot(*p, v)]).collect();
+        let epsilon = length * 1e-12;
+
+        let area = |a: usize, b: usize, c: usize| {
+            let (a, b, c) = (flat[a], flat[b], flat[c]);
+            (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0])
+        };
+        let is_ear = |remaining: &[usize], at: usize| {
+            let len = remaining.len();
+            let (a, b, c) = (remaining[(at + len - 1) % len], remaining[at], remaining[(at + 1) % len]);
+            remaining.iter()
+                .filter(|&&other| [a, b, c].iter().all(|&corner| points[corner] != points[other]))
+                .all(|&other| area(a, b, other) < -epsilon || area(b, c, other) < -epsilon || area(c, a, other) < -epsilon)
         };
 
-        // Because the vertices will not be neighbours, each vertex will have the same
-        // 2 neighbours. We construct two triangles with vertices (v0, v1, n0) and
-        // (v0, v1, n1).
-        let split_v0 = self.vertices[smallest_edge.0];
-        let split_v1 = self.vertices[smallest_edge.1];
-
-        let mut neighbours = [split_v0, split_v1];
-        let mut idx = 0;
-        for (iv0, iv1) in edges {
-            let v0 = self.vertices[iv0];
-            let v1 = self.vertices[iv1];
-            if v0.index == split_v0.index {
-                neighbours[idx] = v1;
-                idx += 1;
-            } else if v1.index == split_v0.index {
-                neighbours[idx] = v0;
-                idx += 1;
-            }
+        let mut remaining: Vec<usize> = (0..count).collect();
+        let mut triangles = Vec::with_capacity(count - 2);
+        while remaining.len() > 3 {
+            let len = remaining.len();
+            let diagonal = |at: usize| distance_squared(points[remaining[(at + len - 1) % len]], points[remaining[(at + 1) % len]]);
+            let convex: Vec<usize> = (0..len)
+                .filter(|&at| area(remaining[(at + len - 1) % len], remaining[at], remaining[(at + 1) % len]) > epsilon)
+                .collect();
+            let ears: Vec<usize> = convex.iter().copied().filter(|&at| is_ear(&remaining, at)).collect();
+
+            // A self-intersecting polygon can run out of ears, any convex corner still makes progress
+            let candidates = if ears.is_empty() { &convex } else { &ears };
+            let Some(&at) = candidates.iter().min_by(|&&a, &&b| diagonal(a).total_cmp(&diagonal(b))) else {
+                // Everything left is a line with no area to cover
+                break;
+            };
+            triangles.push(self.triangle(positions, [remaining[(at + len - 1) % len], remaining[at], remaining[(at + 1) % len]]));
+            remaining.remove(at);
+        }
+        if remaining.len() == 3 && area(remaining[0], remaining[1], remaining[2]) > epsilon {
+            triangles.push(self.triangle(positions, [remaining[0], remaining[1], remaining[2]]));
         }
 
-        Ok((
-            Triangle::new(positions, [
-                (split_v0.index, split_v0.normal, split_v0.uv),
-                (split_v1.index, split_v1.normal, split_v1.uv),
-                (neighbours[0].index, neighbours[0].normal, neighbours[0].uv),
-            ]),
-            Triangle::new(positions, [
-                (split_v0.index, split_v0.normal, split_v0.uv),
-                (split_v1.index, split_v1.normal, split_v1.uv),
-                (neighbours[1].index, neighbours[1].normal, neighbours[1].uv),
-            ]),
-        ))
+        if triangles.is_empty() {
+            return Err(error::NgonError::Degenerate);
+        }
+        Ok(triangles)
     }
+
+    fn triangle(&self, positions: &[[f32; 3]], corners: [usize; 3]) -> Triangle {
+        Triangle::new(positions, corners.map(|corner| {
+            let vertex = self.vertices[corner];
+            (vertex.index, vertex.normal, vertex.uv)
+        }))
+    }
+}
+
+fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[1] * b[2] - a[2] * b[1], a[2] * b[0] - a[0] * b[2], a[0] * b[1] - a[1] * b[0]]
+}
+
+fn normalise(a: [f64; 3]) -> [f64; 3] {
+    let length = dot(a, a).sqrt();
+    a.map(|value| value / length)
+}
+
+fn distance_squared(a: [f64; 3], b: [f64; 3]) -> f64 {
+    let d = [a[0] - b[0], a[1] - b[1], a[2] - b[2]];
+    dot(d, d)
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -485,3 +468,60 @@ mod triangle_tests {
         assert_eq!(triangle.vertices[2].uv, unpacked.vertices[2].uv);
     }
 }
+
+#[cfg(test)]
+mod polygon_tests {
+    use super::{ Polygon, Triangle };
+    use crate::error::NgonError;
+
+    fn face(count: usize) -> Polygon {
+        Polygon::new((0..count as u32).map(|index| (index, [0.0, 0.0, 1.0], [0.0, 0.0])))
+    }
+
+    fn area(positions: &[[f32; 3]], triangles: &[Triangle]) -> [f32; 3] {
+        let mut total = [0.0; 3];
+        for triangle in triangles {
+            let [a, b, c] = triangle.vertices.map(|vertex| positions[vertex.index as usize]);
+            let (e0, e1) = ([b[0] - a[0], b[1] - a[1], b[2] - a[2]], [c[0] - a[0], c[1] - a[1], c[2] - a[2]]);
+            total[0] += (e0[1] * e1[2] - e0[2] * e1[1]) / 2.0;
+            total[1] += (e0[2] * e1[0] - e0[0] * e1[2]) / 2.0;
+            total[2] += (e0[0] * e1[1] - e0[1] * e1[0]) / 2.0;
+        }
+        total
+    }
+
+    #[test]
+    fn test_concave() {
+        // A square with a notch cut into the top, tilted out of the xy plane. (2, 1) is reflex
+        let positions: Vec<[f32; 3]> = [[0.0, 0.0], [4.0, 0.0], [4.0, 4.0], [2.0, 1.0], [0.0, 4.0]].iter()
+            .map(|&[x, y]| [x, y, x * 0.5])
+            .collect();
+        let triangles = face(5).to_triangles(&positions).unwrap();
+
+        assert_eq!(triangles.len(), 3);
+        let total = area(&positions, &triangles);
+        assert!((total[2] - 10.0).abs() < 1e-4, "{total:?}");
+        assert!((total[0] + 5.0).abs() < 1e-4, "{total:?}");
+        for triangle in &triangles {
+            let single = area(&positions, std::slice::from_ref(triangle));
+            assert!(single[2] > 0.0, "triangle is flipped or empty: {single:?}");
+        }
+    }
+
+    #[test]
+    fn test_collinear_vertices() {
+        let positions = [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [2.0, 0.0, 0.0], [2.0, 1.0, 0.0], [0.0, 1.0, 0.0]];
+        let triangles = face(5).to_triangles(&positions).unwrap();
+
+        assert_eq!(triangles.len(), 3);
+        assert!((area(&positions, &triangles)[2] - 2.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_degenerate() {
+        let line = [[0.0, 0.0, 0.0], [1.0, 1.0, 1.0], [2.0, 2.0, 2.0], [3.0, 3.0, 3.0]];
+        assert!(matches!(face(4).to_triangles(&line), Err(NgonError::Degenerate)));
+        assert!(matches!(face(3).to_triangles(&line), Err(NgonError::Degenerate)));
+        assert!(matches!(face(2).to_triangles(&line), Err(NgonError::TooFewVertices(2))));
+    }
+}
@@ -0,0 +1,475 @@
+use crate::model::ngon::{ Triangle, Vertex };
+use crate::model::packed::PackedModel;
+use std::collections::{ BinaryHeap, HashMap, VecDeque };
+use std::cmp::Reverse;
+
+/// Size of the post-transform cache triangles are ordered for
+const CACHE_SIZE: usize = 32;
+/// Size of the FIFO cache `Stats` measure with, as found on older GPUs
+const MEASURED_CACHE_SIZE: usize = 16;
+
+/// What optimising a model changed, as before and after pairs
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stats {
+    /// Before welding every triangle corner is its own vertex
+    pub vertices: (usize, usize),
+    pub triangles: (usize, usize),
+    /// Average cache miss ratio, the vertices transformed per triangle drawn. 0.5 is as good as it gets
+    pub acmr: (f32, f32),
+    /// Triangles in each level of detail past the first
+    pub lod_triangles: Vec<usize>,
+}
+
+impl std::fmt::Display for Stats {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            formatter,
+            "{} -> {} vertices, {} -> {} triangles, ACMR {:.2} -> {:.2}",
+            self.vertices.0, self.vertices.1, self.triangles.0, self.triangles.1, self.acmr.0, self.acmr.1
+        )?;
+        if !self.lod_triangles.is_empty() {
+            let levels: Vec<_> = self.lod_triangles.iter().map(usize::to_string).collect();
+            write!(formatter, ", LODs of {} triangles", levels.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Welds identical vertices, orders triangles for the vertex cache and vertices for fetching, then adds up to
+/// `lods` levels of detail, each with about half the triangles of the last
+pub fn optimise(model: &mut PackedModel, lods: usize) -> Stats {
+    let vertices_before = 3 * model.triangles.len();
+    let triangles_before = model.triangles.len();
+    weld(model);
+
+    let (attributes, indices) = flatten(model);
+    let acmr_before = acmr(&indices);
+    let mut indices = optimise_cache(&indices, attributes.len());
+    let mut lods: Vec<Vec<u32>> = simplify_levels(&model.positions, &indices, lods)
+        .iter()
+        .map(|level| optimise_cache(level, attributes.len()))
+        .collect();
+    let order = optimise_fetch(&mut indices, &mut lods, attributes.len());
+
+    model.positions = order.iter().map(|vertex| model.positions[*vertex as usize]).collect();
+    let attributes: Vec<Vertex> = order.iter().map(|vertex| attributes[*vertex as usize]).collect();
+    model.triangles = indices.chunks_exact(3)
+        .map(|corners| Triangle {
+            vertices: [0, 1, 2].map(|corner| Vertex { index: corners[corner], ..attributes[corners[corner] as usize] }),
+        })
+        .collect();
+    model.index_count = indices.len();
+    model.lods = lods;
+
+    Stats {
+        vertices: (vertices_before, model.positions.len()),
+        triangles: (triangles_before, model.triangles.len()),
+        acmr: (acmr_before, acmr(&indices)),
+        lod_triangles: model.lods.iter().map(|level| level.len() / 3).collect(),
+    }
+}
+
+/// Merges vertices with the same position, normal, tangent and uv, so each index has one set of attributes.
+/// Triangles left with two corners on the same vertex are dropped
+pub fn weld(model: &mut PackedModel) {
+    // adding zero turns -0.0 into 0.0, so both weld together
+    let bits = |values: &[f32]| values.iter().map(|value| (value + 0.0).to_bits()).collect::<Vec<_>>();
+    let mut welded: HashMap<Vec<u32>, u32> = HashMap::new();
+    let mut positions = Vec::new();
+    let mut triangles = Vec::with_capacity(model.triangles.len());
+    for triangle in model.triangles.iter() {
+        let vertices = triangle.vertices.map(|vertex| {
+            let position = model.positions[vertex.index as usize];
+            let key = [bits(&position), bits(&vertex.normal), bits(&vertex.tangent), bits(&vertex.uv)].concat();
+            let index = *welded.entry(key).or_insert_with(|| {
+                positions.push(position);
+                (positions.len() - 1) as u32
+            });
+            Vertex { index, ..vertex }
+        });
+        let [v0, v1, v2] = vertices.map(|vertex| vertex.index);
+        if v0 != v1 && v1 != v2 && v2 != v0 {
+            triangles.push(Triangle { vertices });
+        }
+    }
+    model.positions = positions;
+    model.index_count = 3 * triangles.len();
+    model.triangles = triangles;
+}
+
+/// The attributes of each welded vertex, and the corners of every triangle as indices
+fn flatten(model: &PackedModel) -> (Vec<Vertex>, Vec<u32>) {
+    let empty = Vertex { index: 0, normal: [0.0; 3], tangent: [0.0; 3], uv: [0.0; 2] };
+    let mut attributes = vec![empty; model.positions.len()];
+    let mut indices = Vec::with_capacity(3 * model.triangles.len());
+    for vertex in model.triangles.iter().flat_map(|triangle| triangle.vertices) {
+        attributes[vertex.index as usize] = vertex;
+        indices.push(vertex.index);
+    }
+    (attributes, indices)
+}
+
+fn acmr(indices: &[u32]) -> f32 {
+    if indices.is_empty() {
+        return 0.0;
+    }
+    let mut cache = VecDeque::with_capacity(MEASURED_CACHE_SIZE);
+    let mut misses = 0;
+    for index in indices {
+        if !cache.contains(index) {
+            misses += 1;
+            if cache.len() == MEASURED_CACHE_SIZE {
+                cache.pop_front();
+            }
+            cache.push_back(*index);
+        }
+    }
+    misses as f32 / (indices.len() / 3) as f32
+}
+
+/// Forsyth's scoring: vertices recently used, and vertices with few triangles left to draw, are worth more
+fn vertex_score(cache_position: Option<usize>, remaining: usize) -> f32 {
+    if remaining == 0 {
+        return -1.0;
+    }
+    let cache_score = match cache_position {
+        None => 0.0,
+        // the triangle just drawn scores the same whichever of its corners went in last
+        Some(position) if position < 3 => 0.75,
+        Some(position) => (1.0 - (position - 3) as f32 / (CACHE_SIZE - 3) as f32).powf(1.5),
+    };
+    cache_score + 2.0 / (remaining as f32).sqrt()
+}
+
+/// Greedily draws the triangle whose corners score highest against a simulated LRU cache
+fn optimise_cache(indices: &[u32], vertex_count: usize) -> Vec<u32> {
+    let triangle_count = indices.len() / 3;
+    let mut vertex_triangles = vec![Vec::new(); vertex_count];
+    for (triangle, corners) in indices.chunks_exact(3).enumerate() {
+        for vertex in corners {
+            vertex_triangles[*vertex as usize].push(triangle);
+        }
+    }
+    let mut scores: Vec<f32> = vertex_triangles.iter().map(|triangles| vertex_score(None, triangles.len())).collect();
+    let triangle_score = |scores: &[f32], triangle: usize| -> f32 {
+        indices[3 * triangle..3 * triangle + 3].iter().map(|vertex| scores[*vertex as usize]).sum()
+    };
+
+    let mut drawn = vec![false; triangle_count];
+    let mut cache: Vec<u32> = Vec::with_capacity(CACHE_SIZE + 3);
+    let mut order = Vec::with_capacity(indices.len());
+    let mut next_undrawn = 0;
+    let mut best = (0..triangle_count).max_by(|a, b| triangle_score(&scores, *a).total_cmp(&triangle_score(&scores, *b)));
+    for _ in 0..triangle_count {
+        // nothing in the cache has triangles left, so start again from the first one not yet drawn
+        let triangle = best.unwrap_or_else(|| {
+            while drawn[next_undrawn] {
+                next_undrawn += 1;
+            }
+            next_undrawn
+        });
+        drawn[triangle] = true;
+        let corners = &indices[3 * triangle..3 * triangle + 3];
+        order.extend_from_slice(corners);
+        for vertex in corners.iter().rev() {
+            vertex_triangles[*vertex as usize].retain(|other| *other != triangle);
+            cache.retain(|cached| cached != vertex);
+            cache.insert(0, *vertex);
+        }
+
+        let evicted = cache.split_off(cache.len().min(CACHE_SIZE));
+        for vertex in evicted.iter() {
+            scores[*vertex as usize] = vertex_score(None, vertex_triangles[*vertex as usize].len());
+        }
+        for (position, vertex) in cache.iter().enumerate() {
+            scores[*vertex as usize] = vertex_score(Some(position), vertex_triangles[*vertex as usize].len());
+        }
+
+        best = None;
+        let mut best_score = f32::NEG_INFINITY;
+        for vertex in cache.iter() {
+            for triangle in vertex_triangles[*vertex as usize].iter() {
+                let score = triangle_score(&scores, *triangle);
+                if score > best_score {
+                    best = Some(*triangle);
+                    best_score = score;
+                }
+            }
+        }
+    }
+    order
+}
+
+/// Renumbers vertices in the order they are first drawn, so fetches walk forwards through memory. Vertices
+/// nothing draws are left out. Returns the old index of each new vertex
+fn optimise_fetch(indices: &mut [u32], lods: &mut [Vec<u32>], vertex_count: usize) -> Vec<u32> {
+    let mut remap: Vec<Option<u32>> = vec![None; vertex_count];
+    let mut order = Vec::new();
+    for index in indices.iter_mut().chain(lods.iter_mut().flatten()) {
+        *index = *remap[*index as usize].get_or_insert_with(|| {
+            order.push(*index);
+            (order.len() - 1) as u32
+        });
+    }
+    order
+}
+
+/// Halves the triangle count for each level, stopping early once a level barely shrinks
+fn simplify_levels(positions: &[[f32; 3]], indices: &[u32], lods: usize) -> Vec<Vec<u32>> {
+    let mut levels: Vec<Vec<u32>> = Vec::new();
+    let mut target = indices.len() / 3;
+    for _ in 0..lods {
+        target /= 2;
+        if target == 0 {
+            break;
+        }
+        let previous = levels.last().map_or(indices.len(), Vec::len);
+        let level = simplify(positions, indices, target);
+        if level.len() * 10 > previous * 9 {
+            break;
+        }
+        levels.push(level);
+    }
+    levels
+}
+
+/// Symmetric 4x4 matrix of summed squared distances to planes, as the upper triangle
+#[derive(Debug, Copy, Clone, Default)]
+struct Quadric([f64; 10]);
+
+impl Quadric {
+    fn plane(normal: [f64; 3], distance: f64, weight: f64) -> Quadric {
+        let [a, b, c] = normal;
+        let d = distance;
+        Quadric([a * a, a * b, a * c, a * d, b * b, b * c, b * d, c * c, c * d, d * d].map(|value| value * weight))
+    }
+
+    fn add(&mut self, other: &Quadric) {
+        for (value, other) in self.0.iter_mut().zip(other.0.iter()) {
+            *value += other;
+        }
+    }
+
+    fn error(&self, position: [f32; 3]) -> f64 {
+        let [x, y, z] = position.map(|component| component as f64);
+        let [aa, ab, ac, ad, bb, bc, bd, cc, cd, dd] = self.0;
+        aa * x * x + 2.0 * ab * x * y + 2.0 * ac * x * z + 2.0 * ad * x
+            + bb * y * y + 2.0 * bc * y * z + 2.0 * bd * y
+            + cc * z * z + 2.0 * cd * z
+            + dd
+    }
+}
+
+fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[1] * b[2] - a[2] * b[1], a[2] * b[0] - a[0] * b[2], a[0] * b[1] - a[1] * b[0]]
+}
+
+fn face_normal(positions: &[[f32; 3]], corners: [u32; 3]) -> [f64; 3] {
+    let [p0, p1, p2] = corners.map(|corner| positions[corner as usize].map(|component| component as f64));
+    cross([p1[0] - p0[0], p1[1] - p0[1], p1[2] - p0[2]], [p2[0] - p0[0], p2[1] - p0[1], p2[2] - p0[2]])
+}
+
+/// Collapses edges by quadric error until at most `target` triangles are left. Vertices move onto a
+/// neighbour rather than somewhere new, so every level shares the same vertices. Vertices on borders and
+/// attribute seams have an edge with one triangle, and stay where they are
+fn simplify(positions: &[[f32; 3]], indices: &[u32], target: usize) -> Vec<u32> {
+    let mut triangles: Vec<[u32; 3]> = indices.chunks_exact(3).map(|corners| [corners[0], corners[1], corners[2]]).collect();
+    let mut alive = vec![true; triangles.len()];
+    let mut remaining = triangles.len();
+
+    let mut vertex_triangles = vec![Vec::new(); positions.len()];
+    let mut quadrics = vec![Quadric::default(); positions.len()];
+    let mut edges: HashMap<(u32, u32), usize> = HashMap::new();
+    for (triangle, corners) in triangles.iter().enumerate() {
+        let normal = face_normal(positions, *corners);
+        let length = (normal[0] * normal[0] + normal[1] * normal[1] + normal[2] * normal[2]).sqrt();
+        let p0 = positions[corners[0] as usize].map(|component| component as f64);
+        for (idx, vertex) in corners.iter().enumerate() {
+            vertex_triangles[*vertex as usize].push(triangle);
+            if length > 0.0 {
+                let unit = normal.map(|component| component / length);
+                let distance = -(unit[0] * p0[0] + unit[1] * p0[1] + unit[2] * p0[2]);
+                // weighted by area, so slivers count for little
+                quadrics[*vertex as usize].add(&Quadric::plane(unit, distance, length / 2.0));
+            }
+            let next = corners[(idx + 1) % 3];
+            *edges.entry((*vertex.min(&next), *vertex.max(&next))).or_default() += 1;
+        }
+    }
+    let mut locked = vec![false; positions.len()];
+    for ((v0, v1), count) in edges.iter() {
+        if *count != 2 {
+            locked[*v0 as usize] = true;
+            locked[*v1 as usize] = true;
+        }
+    }
+
+    let neighbours = |vertex_triangles: &[Vec<usize>], triangles: &[[u32; 3]], alive: &[bool], vertex: u32| -> Vec<u32> {
+        let mut neighbours: Vec<u32> = vertex_triangles[vertex as usize].iter()
+            .filter(|triangle| alive[**triangle])
+            .flat_map(|triangle| triangles[*triangle])
+            .filter(|other| *other != vertex)
+            .collect();
+        neighbours.sort_unstable();
+        neighbours.dedup();
+        neighbours
+    };
+    let cost = |quadrics: &[Quadric], from: u32, to: u32| -> f64 {
+        let mut quadric = quadrics[from as usize];
+        quadric.add(&quadrics[to as usize]);
+        quadric.error(positions[to as usize]).max(0.0)
+    };
+
+    // costs are never negative, so their bits sort the same as they do
+    let mut candidates = BinaryHeap::new();
+    for (v0, v1) in edges.keys() {
+        for (from, to) in [(*v0, *v1), (*v1, *v0)] {
+            if !locked[from as usize] {
+                candidates.push(Reverse((cost(&quadrics, from, to).to_bits(), from, to)));
+            }
+        }
+    }
+
+    let mut removed = vec![false; positions.len()];
+    while remaining > target {
+        let Some(Reverse((bits, from, to))) = candidates.pop() else {
+            break;
+        };
+        if removed[from as usize] || removed[to as usize] {
+            continue;
+        }
+        // collapses since this was queued make it costlier, so it goes back in line
+        let current = cost(&quadrics, from, to);
+        if current > f64::from_bits(bits) {
+            candidates.push(Reverse((current.to_bits(), from, to)));
+            continue;
+        }
+
+        let from_neighbours = neighbours(&vertex_triangles, &triangles, &alive, from);
+        if from_neighbours.binary_search(&to).is_err() {
+            continue;
+        }
+        // more than two shared neighbours would pinch the surface into something non-manifold
+        let to_neighbours = neighbours(&vertex_triangles, &triangles, &alive, to);
+        if from_neighbours.iter().filter(|vertex| to_neighbours.binary_search(vertex).is_ok()).count() > 2 {
+            continue;
+        }
+        let flips = vertex_triangles[from as usize].iter()
+            .filter(|triangle| alive[**triangle] && !triangles[**triangle].contains(&to))
+            .any(|triangle| {
+                let before = face_normal(positions, triangles[*triangle]);
+                let after = face_normal(positions, triangles[*triangle].map(|vertex| if vertex == from { to } else { vertex }));
+                before[0] * after[0] + before[1] * after[1] + before[2] * after[2] <= 0.0
+            });
+        if flips {
+            continue;
+        }
+
+        for triangle in std::mem::take(&mut vertex_triangles[from as usize]) {
+            if !alive[triangle] {
+                continue;
+            }
+            if triangles[triangle].contains(&to) {
+                alive[triangle] = false;
+                remaining -= 1;
+            } else {
+                for vertex in triangles[triangle].iter_mut().filter(|vertex| **vertex == from) {
+                    *vertex = to;
+                }
+                vertex_triangles[to as usize].push(triangle);
+            }
+        }
+        let from_quadric = quadrics[from as usize];
+        quadrics[to as usize].add(&from_quadric);
+        removed[from as usize] = true;
+
+        for neighbour in neighbours(&vertex_triangles, &triangles, &alive, to) {
+            if !locked[neighbour as usize] {
+                candidates.push(Reverse((cost(&quadrics, neighbour, to).to_bits(), neighbour, to)));
+            }
+            if !locked[to as usize] {
+                candidates.push(Reverse((cost(&quadrics, to, neighbour).to_bits(), to, neighbour)));
+            }
+        }
+    }
+
+    triangles.iter()
+        .zip(alive.iter())
+        .filter(|(_, alive)| **alive)
+        .flat_map(|(corners, _)| *corners)
+        .collect()
+}
+
+#[cfg(test)]
+mod optimise_tests {
+    use super::{ optimise, weld, acmr };
+    use crate::model::ngon::Triangle;
+    use crate::model::packed::PackedModel;
+
+    /// A flat `size` x `size` grid of quads facing +z, with every corner its own position like an obj export
+    fn grid(size: u32) -> PackedModel {
+        let mut positions = Vec::new();
+        let mut triangles = Vec::new();
+        let mut corner = |x: u32, y: u32| {
+            positions.push([x as f32, y as f32, 0.0]);
+            ((positions.len() - 1) as u32, [0.0, 0.0, 1.0], [x as f32 / size as f32, y as f32 / size as f32])
+        };
+        let mut corners = Vec::new();
+        for y in 0..size {
+            for x in 0..size {
+                corners.push([corner(x, y), corner(x + 1, y), corner(x + 1, y + 1)]);
+                corners.push([corner(x, y), corner(x + 1, y + 1), corner(x, y + 1)]);
+            }
+        }
+        for triangle in corners {
+            triangles.push(Triangle::new(&positions, triangle));
+        }
+        PackedModel {
+            index_count: 3 * triangles.len(),
+            min_bounds: [0.0; 3],
+            max_bounds: [size as f32, size as f32, 0.0],
+            positions,
+            triangles,
+            lods: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_weld() {
+        let mut model = grid(4);
+        weld(&mut model);
+        assert_eq!(model.positions.len(), 25);
+        assert_eq!(model.triangles.len(), 32);
+        for vertex in model.triangles.iter().flat_map(|triangle| triangle.vertices) {
+            let position = model.positions[vertex.index as usize];
+            assert_eq!(vertex.uv, [position[0] / 4.0, position[1] / 4.0]);
+        }
+    }
+
+    #[test]
+    fn test_optimise() {
+        let mut model = grid(16);
+        let stats = optimise(&mut model, 3);
+        assert_eq!(stats.vertices, (3 * 512, 289));
+        assert_eq!(stats.triangles, (512, 512));
+        assert!(stats.acmr.1 <= stats.acmr.0, "{stats:?}");
+        assert_eq!(acmr(&model.triangles.iter().flat_map(|triangle| triangle.vertices.map(|vertex| vertex.index)).collect::<Vec<_>>()), stats.acmr.1);
+
+        // vertices are numbered in the order they are first drawn
+        let mut next = 0;
+        for vertex in model.triangles.iter().flat_map(|triangle| triangle.vertices) {
+            assert!(vertex.index <= next);
+            next = next.max(vertex.index + 1);
+        }
+
+        // the grid is flat, so only the border holds the simplified levels back
+        assert!(!model.lods.is_empty());
+        let mut previous = model.triangles.len();
+        for (level, triangles) in model.lods.iter().zip(stats.lod_triangles.iter()) {
+            assert_eq!(level.len(), 3 * triangles);
+            assert!(*triangles < previous);
+            assert!(level.iter().all(|index| (*index as usize) < model.positions.len()));
+            previous = *triangles;
+        }
+    }
+}
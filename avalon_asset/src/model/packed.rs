@@ -1,17 +1,25 @@
 use crate::model::ngon::{ Vertex, Triangle };
+use crate::model::optimise;
 
+/// Triangles with their corners' attributes. Corners with the same index share the same attributes
 #[derive(Debug, Clone)]
 pub struct PackedModel {
     pub index_count: usize,
     pub min_bounds: [f32; 3],
     pub max_bounds: [f32; 3],
     pub positions: Vec<[f32; 3]>,
-    pub triangles: Vec<Triangle>
+    pub triangles: Vec<Triangle>,
+    /// Simplified levels of detail as indices into the same vertices, each coarser than the last
+    pub lods: Vec<Vec<u32>>,
 }
 
 impl PackedModel {
+    /// Tells a model from those packed before vertices were welded, which start with their vertex count
+    const MAGIC: &[u8; 4] = b"AVMD";
+
     pub fn to_buffer(&self) -> Vec<u8> {
         let mut buffer = Vec::new();
+        buffer.extend_from_slice(Self::MAGIC);
         buffer.extend_from_slice(&(self.positions.len() as u32).to_be_bytes());
         buffer.extend_from_slice(&(self.index_count as u32).to_be_bytes());
         buffer.extend_from_slice(&(self.triangles.len() as u32).to_be_bytes());
@@ -28,12 +36,21 @@ impl PackedModel {
         for t in self.triangles.iter() {
             buffer.extend(t.to_buffer());
         }
+        buffer.extend_from_slice(&(self.lods.len() as u32).to_be_bytes());
+        for level in self.lods.iter() {
+            buffer.extend_from_slice(&(level.len() as u32).to_be_bytes());
+            buffer.extend(level.iter().flat_map(|index| index.to_be_bytes()));
+        }
         buffer
     }
 
     #[allow(clippy::erasing_op)]
     #[allow(clippy::identity_op)]
     pub fn from_buffer(buffer: &[u8]) -> PackedModel {
+        let (buffer, welded) = match buffer.strip_prefix(Self::MAGIC) {
+            Some(buffer) => (buffer, true),
+            None => (buffer, false),
+        };
         let mut offset = 0;
         let vertex_count = u32::from_be_bytes([
             buffer[offset + 0],
@@ -136,13 +153,38 @@ impl PackedModel {
             offset += 3 * Vertex::BYTE_N;
         }
 
-        PackedModel {
+        let mut lods = Vec::new();
+        if welded {
+            let mut next_word = || {
+                let word = u32::from_be_bytes([
+                    buffer[offset + 0],
+                    buffer[offset + 1],
+                    buffer[offset + 2],
+                    buffer[offset + 3],
+                ]);
+                offset += 4;
+                word
+            };
+            let lod_count = next_word();
+            for _ in 0..lod_count {
+                let length = next_word();
+                lods.push((0..length).map(|_| next_word()).collect());
+            }
+        }
+
+        let mut model = PackedModel {
             index_count: index_count as usize,
             min_bounds,
             max_bounds,
             positions,
-            triangles
+            triangles,
+            lods
+        };
+        // older models can have one position under corners with different attributes
+        if !welded {
+            optimise::weld(&mut model);
         }
+        model
     }
 }
 
@@ -209,6 +251,7 @@ mod packed_model_tests {
                     ]
                 },
             ],
+            lods: vec![vec![10, 13, 15], vec![]],
         };
 
         let buffer = packed_model.to_buffer();
@@ -218,6 +261,7 @@ mod packed_model_tests {
         assert_eq!(packed_model.min_bounds, unpacked.min_bounds);
         assert_eq!(packed_model.max_bounds, unpacked.max_bounds);
         assert_eq!(packed_model.positions, unpacked.positions);
+        assert_eq!(packed_model.lods, unpacked.lods);
 
         assert_eq!(unpacked.triangles.len(), 2);
         assert_eq!(packed_model.triangles[0].vertices[0].index, unpacked.triangles[0].vertices[0].index);
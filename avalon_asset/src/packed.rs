@@ -1,27 +1,77 @@
 use std::collections::HashMap;
 use crate::{ bundle, asset };
+#[cfg(any(feature = "read", feature = "write"))]
+use miniserde::{ Deserialize, Serialize };
 
 #[derive(Debug)]
 pub struct Packed {
     pub bundle: bundle::Bundle,
-    pub data_map: HashMap<asset::Metadata, Vec<u8>>
+    pub data_map: HashMap<asset::Metadata, Vec<u8>>,
+    /// The format the bundle was read in. Older formats can be rewritten with `migrate::upgrade_file`
+    pub format_version: u32,
+    /// Version of `avalon_asset` that packed the bundle, when known
+    pub engine_version: Option<String>,
+}
+
+/// Stored at the root of the archive, for anything that belongs to the bundle rather than one asset.
+/// Bundles from before format version 2 have no version or asset list, and the oldest no header at all
+#[cfg(any(feature = "read", feature = "write"))]
+#[derive(Debug, Serialize, Deserialize)]
+struct Header {
+    format_version: Option<u32>,
+    engine_version: Option<String>,
+    dependencies: Vec<String>,
+    assets: Option<Vec<HeaderEntry>>,
+}
+
+/// One asset, in the order the bundle lists them
+#[cfg(any(feature = "read", feature = "write"))]
+#[derive(Debug, Serialize, Deserialize)]
+struct HeaderEntry {
+    uuid: String,
+    /// `content_hash` of the stored data, as hex
+    hash: String,
 }
 
 impl Packed {
     pub const EXTENSION: &str = "bundle";
+    /// 1: no header or a header with only dependencies. 2: versioned header with per-asset hashes
+    pub const FORMAT_VERSION: u32 = 2;
+    pub const ENGINE_VERSION: &str = env!("CARGO_PKG_VERSION");
+    #[cfg(any(feature = "read", feature = "write"))]
+    const HEADER: &str = "bundle.json";
+}
+
+/// FNV-1a over the stored data. Catches corruption, not tampering
+pub fn content_hash(data: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in data {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
 }
 
 #[cfg(feature = "write")]
 pub mod write {
-    use super::Packed;
+    use super::{ Header, HeaderEntry, Packed };
     use crate::model::packed::PackedModel;
-    use crate::model::ngon;
-    use crate::{ error, asset };
+    use crate::model::{ self, gltf, ngon, optimise };
+    use crate::texture::packed::PackedTexture;
+    use std::collections::HashMap;
+    use crate::{ error, asset, config, text, texture };
     use std::io::Write;
     use miniserde::json;
     use obj;
     use zip;
 
+    /// What packing did to the assets it reworks, to show whoever ran it
+    #[derive(Debug, Default)]
+    pub struct Report {
+        /// Tag and optimisation stats of every model
+        pub models: Vec<(String, optimise::Stats)>,
+    }
+
     struct Packer {
         original_data: Vec<u8>,
         write_data: Vec<u8>,
@@ -41,7 +91,7 @@ pub mod write {
             std::io::Cursor::new(self.write_data)
         }
 
-        fn pack_model_data(mut self) -> Result<std::io::Cursor<Vec<u8>>, error::ModelUnpackError> {
+        fn pack_model_data(self) -> Result<PackedModel, error::ModelUnpackError> {
             let cursor = std::io::Cursor::new(&self.original_data);
             let raw_model = obj::raw::parse_obj(cursor).map_err(error::ModelUnpackError::ObjError)?;
             let vertices: Vec<_> = raw_model.positions.iter()
@@ -53,54 +103,15 @@ pub mod write {
                 let obj::raw::object::Polygon::PTN(polygon) = polygon else {
                     return Err(error::ModelUnpackError::InvalidFormat);
                 };
-                if polygon.len() > 4 {
-                    return Err(error::ModelUnpackError::TooManyVertices);
-                } else if polygon.len() == 3 {
-                    let (index0, uv_idx0, normal_idx0) = polygon[0];
-                    let (index1, uv_idx1, normal_idx1) = polygon[1];
-                    let (index2, uv_idx2, normal_idx2) = polygon[2];
-
-                    let normal_0 = raw_model.normals[normal_idx0];
-                    let normal_1 = raw_model.normals[normal_idx1];
-                    let normal_2 = raw_model.normals[normal_idx2];
-
-                    let uv_0 = raw_model.tex_coords[uv_idx0]; let uv_0 = (uv_0.0, uv_0.1);
-                    let uv_1 = raw_model.tex_coords[uv_idx1]; let uv_1 = (uv_1.0, uv_1.1);
-                    let uv_2 = raw_model.tex_coords[uv_idx2]; let uv_2 = (uv_2.0, uv_2.1);
-
-                    triangles.push(ngon::Triangle::new(
-                        &vertices,
-                        [
-                            (index0 as u32, normal_0.into(), uv_0.into()),
-                            (index1 as u32, normal_1.into(), uv_1.into()),
-                            (index2 as u32, normal_2.into(), uv_2.into()),
-                        ]
-                    ));
-                } else {
-                    let (index0, uv_idx0, normal_idx0) = polygon[0];
-                    let (index1, uv_idx1, normal_idx1) = polygon[1];
-                    let (index2, uv_idx2, normal_idx2) = polygon[2];
-                    let (index3, uv_idx3, normal_idx3) = polygon[3];
-
-                    let normal_0 = raw_model.normals[normal_idx0];
-                    let normal_1 = raw_model.normals[normal_idx1];
-                    let normal_2 = raw_model.normals[normal_idx2];
-                    let normal_3 = raw_model.normals[normal_idx3];
-
-                    let uv_0 = raw_model.tex_coords[uv_idx0]; let uv_0 = (uv_0.0, uv_0.1);
-                    let uv_1 = raw_model.tex_coords[uv_idx1]; let uv_1 = (uv_1.0, uv_1.1);
-                    let uv_2 = raw_model.tex_coords[uv_idx2]; let uv_2 = (uv_2.0, uv_2.1);
-                    let uv_3 = raw_model.tex_coords[uv_idx3]; let uv_3 = (uv_3.0, uv_3.1);
-
-                    let quad = ngon::Quad::new([
-                        (index0 as u32, normal_0.into(), uv_0.into()),
-                        (index1 as u32, normal_1.into(), uv_1.into()),
-                        (index2 as u32, normal_2.into(), uv_2.into()),
-                        (index3 as u32, normal_3.into(), uv_3.into()),
-                    ]);
-                    let (t0, t1) = quad.to_triangles(&vertices)?;
-                    triangles.push(t0);
-                    triangles.push(t1);
+                let face = ngon::Polygon::new(polygon.iter().map(|&(index, uv_idx, normal_idx)| {
+                    let uv = raw_model.tex_coords[uv_idx];
+                    (index as u32, raw_model.normals[normal_idx].into(), [uv.0, uv.1])
+                }));
+                match face.to_triangles(&vertices) {
+                    Ok(face_triangles) => triangles.extend(face_triangles),
+                    // Zero-area faces draw nothing, so they are dropped rather than failing the model
+                    Err(error::NgonError::Degenerate) => continue,
+                    Err(err) => return Err(err.into()),
                 }
             }
 
@@ -121,7 +132,7 @@ pub mod write {
                 (min_bounds, max_bounds)
             };
 
-            let packed = PackedModel {
+            Ok(PackedModel {
                 index_count: 3 * triangles.len(),
                 min_bounds,
                 max_bounds,
@@ -129,20 +140,29 @@ pub mod write {
                 positions: raw_model.positions.iter()
                     .map(|v| [v.0, v.1, v.2])
                     .collect(),
-            };
+                lods: Vec::new(),
+            })
+        }
 
+        /// Decoded here so the mip chain and any compression are done once, ahead of time
+        fn pack_texture_data(mut self, texture: texture::Texture) -> Result<std::io::Cursor<Vec<u8>>, error::TextureError> {
+            let image = image::load_from_memory(&self.original_data)?.to_rgba8();
+            let packed = PackedTexture::new(image.as_raw(), image.width(), image.height(), texture);
             self.write_data = packed.to_buffer();
             Ok(self.cursor())
         }
 
-        fn pack_texture_data(mut self) -> std::io::Cursor<Vec<u8>> {
-            self.write_data = self.original_data.clone();
-            self.cursor()
+        fn pack_text_data(mut self) -> Result<std::io::Cursor<Vec<u8>>, error::TextError> {
+            let table = text::StringTable::from_bytes(&self.original_data)?;
+            self.write_data = table.to_string().into_bytes();
+            Ok(self.cursor())
         }
 
-        fn pack_text_data(mut self) -> std::io::Cursor<Vec<u8>> {
-            self.write_data = self.original_data.clone();
-            self.cursor()
+        /// Parsed here so mistakes show up when packing, and stored in a normalised form
+        fn pack_config_data(mut self) -> Result<std::io::Cursor<Vec<u8>>, error::ConfigError> {
+            let table = config::Table::from_bytes(&self.original_data)?;
+            self.write_data = table.to_string().into_bytes();
+            Ok(self.cursor())
         }
 
         fn pack_shader_data(mut self) -> std::io::Cursor<Vec<u8>> {
@@ -152,8 +172,87 @@ pub mod write {
     }
 
     impl Packed {
-        pub fn pack_to_file(&self, path: impl AsRef<std::path::Path>) -> Result<(), error::PackError> {
-            let path = path.as_ref();
+        /// Packs each asset from its source file
+        pub fn pack_to_file(&self, path: impl AsRef<std::path::Path>) -> Result<Report, error::PackError> {
+            if let Some(e) = self.bundle.validate().into_iter().next() {
+                return Err(e.into());
+            }
+
+            // every part of a glTF file is packed from the one import
+            let mut scenes: HashMap<std::path::PathBuf, gltf::Scene> = HashMap::new();
+            let mut report = Report::default();
+            self.write_archive(path.as_ref(), |asset| {
+                let filepath = asset.filepath.as_ref().expect("Need to have filepath to bundle");
+                if let Some(part) = asset.part {
+                    if !scenes.contains_key(filepath) {
+                        scenes.insert(filepath.clone(), gltf::Scene::open(filepath)?);
+                    }
+                    return Self::pack_part(&scenes[filepath], part, asset, &mut report);
+                }
+                if matches!(asset.unit, asset::Unit::Model(_)) && gltf::is_gltf(filepath) {
+                    return Err(error::ModelUnpackError::Unexpanded.into());
+                }
+
+                let packer = Packer::new(
+                    std::fs::OpenOptions::new()
+                        .read(true)
+                        .open(filepath)?
+                );
+                let cursor = match asset.unit {
+                    asset::Unit::Shader(_) => packer.pack_shader_data(),
+                    asset::Unit::Texture(texture) => packer.pack_texture_data(texture)?,
+                    asset::Unit::Model(model) => {
+                        let packed = packer.pack_model_data()?;
+                        return Ok(Self::optimise_model(packed, model, &asset.tag, &mut report));
+                    },
+                    asset::Unit::Text(_) => packer.pack_text_data()?,
+                    asset::Unit::Config(_) => packer.pack_config_data()?,
+                };
+                Ok(cursor.into_inner())
+            })?;
+            Ok(report)
+        }
+
+        /// Every model is welded and ordered for drawing here, whatever format it came from
+        fn optimise_model(mut packed: PackedModel, model: model::Model, tag: &str, report: &mut Report) -> Vec<u8> {
+            let stats = optimise::optimise(&mut packed, model.lods.unwrap_or(0) as usize);
+            report.models.push((tag.to_string(), stats));
+            packed.to_buffer()
+        }
+
+        fn pack_part(
+            scene: &gltf::Scene,
+            part: asset::Part,
+            asset: &asset::Metadata,
+            report: &mut Report
+        ) -> Result<Vec<u8>, error::PackError> {
+            match (part, asset.unit) {
+                (asset::Part::Primitive { mesh, primitive }, asset::Unit::Model(model)) => {
+                    let packed = scene.pack_primitive(mesh, primitive)?;
+                    Ok(Self::optimise_model(packed, model, &asset.tag, report))
+                },
+                (asset::Part::Image(image), asset::Unit::Texture(texture)) => {
+                    let image = scene.image(image)?;
+                    Ok(PackedTexture::new(image.as_raw(), image.width(), image.height(), texture).to_buffer())
+                },
+                _ => Err(error::ModelUnpackError::PartMismatch(part).into()),
+            }
+        }
+
+        /// Writes data that is already packed, such as a bundle read from disk, at the current format version
+        pub fn write_to_file(&self, path: impl AsRef<std::path::Path>) -> Result<(), error::PackError> {
+            self.write_archive(path.as_ref(), |asset| {
+                self.data_map.get(asset)
+                    .cloned()
+                    .ok_or_else(|| error::PackError::MissingData(asset.tag.clone()))
+            })
+        }
+
+        fn write_archive(
+            &self,
+            path: &std::path::Path,
+            mut stored: impl FnMut(&asset::Metadata) -> Result<Vec<u8>, error::PackError>
+        ) -> Result<(), error::PackError> {
             let mut file = std::fs::File::options()
                 .read(true)
                 .write(true)
@@ -171,6 +270,7 @@ pub mod write {
             archive.set_flush_on_finish_file(true);
             archive.set_comment(format!("Bundled asset generated on {}", chrono::Utc::now()));
 
+            let mut entries = Vec::new();
             for asset in self.bundle.group.iter() {
                 let uuid_path = asset.uuid.to_string();
                 let directory = std::path::Path::new(&uuid_path);
@@ -186,24 +286,36 @@ pub mod write {
                 let meta_json = json::to_string(&asset);
                 archive.write_all(meta_json.as_bytes())?;
 
+                // block compressed textures gain little from zip compression, and stored as is they can be
+                // mapped when read
+                let stored_options = match asset.unit {
+                    asset::Unit::Texture(texture::Texture { compression: Some(_), .. }) => zip::write::SimpleFileOptions::default()
+                        .compression_method(zip::CompressionMethod::Stored),
+                    _ => zip::write::SimpleFileOptions::default(),
+                };
                 archive.start_file_from_path(
                     directory.join(".stored"),
-                    zip::write::SimpleFileOptions::default()
+                    stored_options
                 )?;
 
-                let packer = Packer::new(
-                    std::fs::OpenOptions::new()
-                        .read(true)
-                        .open(asset.filepath.as_ref().expect("Need to have filepath to bundle"))?
-                );
-                let mut cursor = match asset::Type::from(asset.unit) {
-                    asset::Type::Shader => packer.pack_shader_data(),
-                    asset::Type::Texture => packer.pack_texture_data(),
-                    asset::Type::Model => packer.pack_model_data()?,
-                    asset::Type::Text => packer.pack_text_data(),
-                };
-                std::io::copy(&mut cursor, &mut archive)?;
+                let data = stored(asset)?;
+                archive.write_all(&data)?;
+                entries.push(HeaderEntry {
+                    uuid: uuid_path,
+                    hash: format!("{:016x}", super::content_hash(&data)),
+                });
             }
+
+            // written last so it can hold every hash. Reads look it up by name
+            archive.start_file(Self::HEADER, options)?;
+            let header = Header {
+                format_version: Some(Self::FORMAT_VERSION),
+                engine_version: Some(Self::ENGINE_VERSION.to_string()),
+                dependencies: self.bundle.dependencies.clone(),
+                assets: Some(entries),
+            };
+            archive.write_all(json::to_string(&header).as_bytes())?;
+
             archive.finish()?;
             file.flush()?;
             Ok(())
@@ -215,75 +327,261 @@ pub mod write {
 pub mod read {
     use crate::asset;
     use crate::error;
-    use crate::packed::Packed;
+    use crate::packed::{ Header, Packed };
     use crate::bundle::Bundle;
+    use std::borrow::Cow;
     use std::collections::HashMap;
     use std::io::Read;
     use miniserde::json;
     use zip;
 
-    impl Packed {
-        pub fn read_from_file(path: impl AsRef<std::path::Path>) -> Result<Packed, error::UnpackError> {
+    fn read_entry<R: std::io::Read + std::io::Seek>(
+        reader: &mut zip::ZipArchive<R>,
+        name: &str
+    ) -> Result<Option<Vec<u8>>, error::UnpackError> {
+        let mut file = match reader.by_name(name) {
+            Ok(file) => file,
+            Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let mut buffer = Vec::new();
+        let read_bytes = file.read_to_end(&mut buffer)?;
+        if file.size() as usize != read_bytes {
+            return Err(error::UnpackError::SizeMismatch(read_bytes, file.size() as usize));
+        }
+        Ok(Some(buffer))
+    }
+
+    fn read_json<R: std::io::Read + std::io::Seek, T: miniserde::Deserialize>(
+        reader: &mut zip::ZipArchive<R>,
+        name: &str
+    ) -> Result<Option<T>, error::UnpackError> {
+        let Some(buffer) = read_entry(reader, name)? else {
+            return Ok(None);
+        };
+        let text = String::from_utf8(buffer).map_err(|_| error::UnpackError::UnexpectedFileStructure)?;
+        Ok(Some(json::from_str(&text)?))
+    }
+
+    /// Where one asset's data lives in the archive
+    #[derive(Debug)]
+    struct Entry {
+        tag: String,
+        hash: Option<u64>,
+        /// Byte range in the file, when the data is stored uncompressed and can be read from the map
+        mapped: Option<std::ops::Range<usize>>,
+    }
+
+    /// A bundle opened for reading. Only the header and metadata are read up front, each asset's data
+    /// is read and verified when asked for
+    #[derive(Debug)]
+    pub struct Reader {
+        bundle: Bundle,
+        format_version: u32,
+        engine_version: Option<String>,
+        archive: zip::ZipArchive<std::fs::File>,
+        map: Option<memmap2::Mmap>,
+        entries: HashMap<uuid::Uuid, Entry>,
+    }
+
+    impl Reader {
+        /// Entries are looked up by name, so their order in the archive does not matter
+        pub fn open(path: impl AsRef<std::path::Path>) -> Result<Reader, error::UnpackError> {
             let path = path.as_ref();
             let packed_file = std::fs::File::options()
                 .read(true)
                 .open(path)?;
+            // bundles are replaced rather than written over when rebuilt, so the map stays valid.
+            // Falls back to reading through the archive when the file cannot be mapped
+            let map = unsafe { memmap2::Mmap::map(&packed_file) }.ok();
+            let mut archive = zip::ZipArchive::new(packed_file)?;
+
+            for name in archive.file_names() {
+                let parts: Vec<_> = name.split('/').collect();
+                let expected = match parts.as_slice() {
+                    [file] => *file == Packed::HEADER,
+                    [_, _] => true,
+                    _ => false,
+                };
+                if !expected {
+                    return Err(error::UnpackError::UnexpectedFileStructure);
+                }
+            }
 
-            let mut data_map: HashMap<asset::Metadata, Vec<u8>> = HashMap::new();
-            let mut bundle = Bundle {
-                group: Vec::new(),
-                name: path.file_stem().unwrap().to_string_lossy().to_string()
+            let header: Option<Header> = read_json(&mut archive, Packed::HEADER)?;
+            let format_version = header.as_ref()
+                .and_then(|header| header.format_version)
+                .unwrap_or(1);
+            if format_version > Packed::FORMAT_VERSION {
+                return Err(error::UnpackError::UnsupportedVersion {
+                    found: format_version,
+                    supported: Packed::FORMAT_VERSION
+                });
+            }
+
+            // version 1 bundles list no assets, so take them in archive order and skip verification
+            let listed: Vec<(String, Option<u64>)> = match header.as_ref().and_then(|header| header.assets.as_ref()) {
+                Some(assets) => assets.iter()
+                    .map(|entry| {
+                        let hash = u64::from_str_radix(&entry.hash, 16)
+                            .map_err(|_| error::UnpackError::UnexpectedFileStructure)?;
+                        Ok((entry.uuid.clone(), Some(hash)))
+                    })
+                    .collect::<Result<_, error::UnpackError>>()?,
+                None => archive.file_names()
+                    .filter_map(|name| name.strip_suffix("/metadata.json"))
+                    .map(|uuid| (uuid.to_string(), None))
+                    .collect(),
             };
 
-            let mut reader = zip::ZipArchive::new(packed_file)?;
-            for i in 0..reader.len() {
-                let mut file = reader.by_index(i)?;
-                if file.is_file() {
-                    let split_path = file.name().split('/');
-                    let path: Vec<_> = split_path.collect();
-                    if path.len() != 2 {
-                        return Err(error::UnpackError::UnexpectedFileStructure);
-                    }
-                    let directory = path[0];
-                    let filename = path[1];
-                    match filename {
-                        "metadata.json" => {
-                            let mut buffer = String::new();
-                            file.read_to_string(&mut buffer)?;
-                            let metadata: asset::Metadata = json::from_str(buffer.as_str())?;
-                            data_map.insert(metadata.clone(), Vec::new());
-                            bundle.group.push(metadata);
-                        },
-                        ".stored" => {
-                            if let Some((_, stored)) = data_map.iter_mut().find(|(metadata, _)| metadata.uuid.to_string() == directory) {
-                                let read_bytes = file.read_to_end(stored)?;
-                                if file.size() as usize != read_bytes {
-                                    return Err(error::UnpackError::SizeMismatch(read_bytes, file.size() as usize));
-                                }
-                            } else {
-                                todo!("zip file not reading in order; we need to store this data for later reads");
-                            }
-                        },
-                        _ => {}
-                    }
-                }
+            let mut bundle = Bundle::new(path.file_stem().unwrap().to_string_lossy());
+            let mut engine_version = None;
+            if let Some(header) = header {
+                bundle.dependencies = header.dependencies;
+                engine_version = header.engine_version;
+            }
+
+            let mut entries = HashMap::new();
+            for (uuid, hash) in listed {
+                let metadata: asset::Metadata = read_json(&mut archive, &format!("{uuid}/metadata.json"))?
+                    .ok_or_else(|| error::UnpackError::MissingAsset(uuid.clone()))?;
+                let stored = match archive.by_name(&format!("{uuid}/.stored")) {
+                    Ok(stored) => stored,
+                    Err(zip::result::ZipError::FileNotFound) => return Err(error::UnpackError::MissingAsset(uuid)),
+                    Err(e) => return Err(e.into()),
+                };
+                let start = stored.data_start() as usize;
+                let end = start + stored.size() as usize;
+                let mapped = (stored.compression() == zip::CompressionMethod::Stored
+                    && !stored.encrypted()
+                    && map.as_ref().is_some_and(|map| end <= map.len()))
+                    .then_some(start..end);
+                drop(stored);
+
+                entries.insert(metadata.uuid, Entry {
+                    tag: metadata.tag.clone(),
+                    hash,
+                    mapped
+                });
+                bundle.group.push(metadata);
+            }
+
+            Ok(Reader {
+                bundle,
+                format_version,
+                engine_version,
+                archive,
+                map,
+                entries
+            })
+        }
+
+        pub fn bundle(&self) -> &Bundle {
+            &self.bundle
+        }
+
+        pub fn into_bundle(self) -> Bundle {
+            self.bundle
+        }
+
+        pub fn format_version(&self) -> u32 {
+            self.format_version
+        }
+
+        pub fn engine_version(&self) -> Option<&str> {
+            self.engine_version.as_deref()
+        }
+
+        /// The content hash the header lists for an asset. Bundles from before version 2 have none
+        pub fn hash(&self, uuid: &uuid::Uuid) -> Option<u64> {
+            self.entries.get(uuid)?.hash
+        }
+
+        /// Borrowed from the map when stored uncompressed, otherwise decompressed into a new buffer
+        pub fn read(&mut self, uuid: &uuid::Uuid) -> Result<Cow<'_, [u8]>, error::UnpackError> {
+            let entry = self.entries.get(uuid)
+                .ok_or_else(|| error::UnpackError::MissingAsset(uuid.to_string()))?;
+            let data = match (self.map.as_ref(), entry.mapped.as_ref()) {
+                (Some(map), Some(range)) => Cow::Borrowed(&map[range.clone()]),
+                _ => Cow::Owned(
+                    read_entry(&mut self.archive, &format!("{uuid}/.stored"))?
+                        .ok_or_else(|| error::UnpackError::MissingAsset(uuid.to_string()))?
+                ),
             };
 
+            if let Some(expected) = entry.hash {
+                let found = super::content_hash(&data);
+                if found != expected {
+                    return Err(error::UnpackError::HashMismatch {
+                        asset: entry.tag.clone(),
+                        expected: format!("{expected:016x}"),
+                        found: format!("{found:016x}")
+                    });
+                }
+            }
+            Ok(data)
+        }
+
+        /// Reads the first asset with the tag
+        pub fn read_tag(&mut self, tag: &str) -> Result<Cow<'_, [u8]>, error::UnpackError> {
+            let uuid = self.bundle.asset(tag)
+                .ok_or_else(|| error::UnpackError::MissingAsset(tag.to_string()))?
+                .uuid;
+            self.read(&uuid)
+        }
+    }
+
+    impl Packed {
+        /// Reads every asset into `data_map`. Use `Reader` to read only what is needed
+        pub fn read_from_file(path: impl AsRef<std::path::Path>) -> Result<Packed, error::UnpackError> {
+            let mut reader = Reader::open(path)?;
+            let mut data_map = HashMap::new();
+            for metadata in reader.bundle.group.clone() {
+                let data = reader.read(&metadata.uuid)?.into_owned();
+                data_map.insert(metadata, data);
+            }
+
             Ok(Packed {
-                bundle,
-                data_map: data_map.iter()
-                    .map(|(key, value)| (key.clone(), value.clone()))
-                    .collect()
+                bundle: reader.bundle,
+                data_map,
+                format_version: reader.format_version,
+                engine_version: reader.engine_version
             })
         }
     }
 }
 
+#[cfg(all(feature = "read", feature = "write"))]
+pub mod migrate {
+    use super::Packed;
+    use crate::error;
+
+    /// Rewrites a bundle packed in an older format at the current version, keeping its data as stored.
+    /// Returns false when it was already current
+    pub fn upgrade_file(path: impl AsRef<std::path::Path>) -> Result<bool, error::MigrationError> {
+        let path = path.as_ref();
+        let packed = Packed::read_from_file(path)?;
+        if packed.format_version == Packed::FORMAT_VERSION {
+            return Ok(false);
+        }
+
+        let temporary = path.with_extension(format!("{}.upgrade", Packed::EXTENSION));
+        if temporary.is_file() {
+            std::fs::remove_file(&temporary)?;
+        }
+        packed.write_to_file(&temporary)?;
+        std::fs::rename(&temporary, path)?;
+        Ok(true)
+    }
+}
+
 impl From<bundle::Bundle> for Packed {
     fn from(bundle: bundle::Bundle) -> Packed {
         Packed {
             bundle,
-            data_map: HashMap::new()
+            data_map: HashMap::new(),
+            format_version: Packed::FORMAT_VERSION,
+            engine_version: Some(Packed::ENGINE_VERSION.to_string())
         }
     }
 }
@@ -1,13 +1,13 @@
 use miniserde::{ Serialize, Deserialize };
 
-#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Stage {
     Compute,
     Vertex,
     Fragment
 }
 
-#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Shader {
     pub stage: Stage,
 }
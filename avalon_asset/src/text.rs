@@ -1,19 +1,313 @@
+use crate::error;
 use miniserde::{ Deserialize, Serialize };
+use std::collections::HashMap;
 
-#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Language {
-    English
+    English,
+    French,
+    German,
+    Spanish,
+    Italian,
+    Portuguese,
+    Russian,
+    Japanese,
+    Korean,
+    Chinese,
 }
 
-#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Text {
     pub language: Language
 }
 
+/// Strings in a single language, read from lines of `key = text`
+///
+/// ```text
+/// # comments run to the end of the line
+/// menu.start = Start
+/// subtitle.intro = Welcome back, {name}.\nIt has been {days} days.
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct StringTable {
+    entries: HashMap<String, String>,
+}
+
+/// Every language loaded for one text asset. Keys missing from a language fall back to `default`
+#[derive(Debug, Clone)]
+pub struct Strings {
+    default: Language,
+    tables: HashMap<Language, StringTable>,
+}
+
+impl Language {
+    pub const ALL: [Language; 10] = [
+        Language::English,
+        Language::French,
+        Language::German,
+        Language::Spanish,
+        Language::Italian,
+        Language::Portuguese,
+        Language::Russian,
+        Language::Japanese,
+        Language::Korean,
+        Language::Chinese,
+    ];
+
+    /// ISO 639-1 code
+    pub fn code(&self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::French => "fr",
+            Language::German => "de",
+            Language::Spanish => "es",
+            Language::Italian => "it",
+            Language::Portuguese => "pt",
+            Language::Russian => "ru",
+            Language::Japanese => "ja",
+            Language::Korean => "ko",
+            Language::Chinese => "zh",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Language> {
+        Language::ALL.into_iter().find(|language| language.code().eq_ignore_ascii_case(code))
+    }
+}
+
+impl StringTable {
+    pub fn from_bytes(bytes: &[u8]) -> Result<StringTable, error::TextError> {
+        let source = std::str::from_utf8(bytes).map_err(|_| error::TextError::InvalidUtf8)?;
+        source.parse()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries.get(key).map(String::as_str)
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.entries.keys().map(String::as_str)
+    }
+
+    pub fn insert(&mut self, key: impl Into<String>, text: impl Into<String>) -> Option<String> {
+        self.entries.insert(key.into(), text.into())
+    }
+}
+
+impl Strings {
+    pub fn new(default: Language) -> Strings {
+        Strings {
+            default,
+            tables: HashMap::new(),
+        }
+    }
+
+    pub fn default_language(&self) -> Language {
+        self.default
+    }
+
+    pub fn set_default_language(&mut self, language: Language) {
+        self.default = language;
+    }
+
+    pub fn languages(&self) -> impl Iterator<Item = Language> + '_ {
+        self.tables.keys().copied()
+    }
+
+    pub fn insert(&mut self, language: Language, table: StringTable) -> Option<StringTable> {
+        self.tables.insert(language, table)
+    }
+
+    pub fn table(&self, language: Language) -> Option<&StringTable> {
+        self.tables.get(&language)
+    }
+
+    /// The text in `language`, or in the default language if it has not been translated
+    pub fn get(&self, key: &str, language: Language) -> Option<&str> {
+        self.tables.get(&language)
+            .and_then(|table| table.get(key))
+            .or_else(|| self.tables.get(&self.default)?.get(key))
+    }
+
+    /// Like `get`, with each `{name}` replaced by the matching argument
+    pub fn format(&self, key: &str, language: Language, arguments: &[(&str, &dyn std::fmt::Display)]) -> Option<String> {
+        self.get(key, language).map(|template| substitute(template, arguments))
+    }
+}
+
+/// Replaces `{name}` with the matching argument. Unknown names are left as they are, and `{{` and `}}` are literal braces
+pub fn substitute(template: &str, arguments: &[(&str, &dyn std::fmt::Display)]) -> String {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(idx) = rest.find(['{', '}']) {
+        output.push_str(&rest[..idx]);
+        rest = &rest[idx..];
+        if rest.starts_with("{{") || rest.starts_with("}}") {
+            output.push_str(&rest[..1]);
+            rest = &rest[2..];
+            continue;
+        }
+
+        let argument = rest.strip_prefix('{')
+            .and_then(|inner| Some((inner, inner.find('}')?)))
+            .and_then(|(inner, end)| {
+                let name = &inner[..end];
+                arguments.iter()
+                    .find(|(argument, _)| *argument == name)
+                    .map(|(_, value)| (value, end + 2))
+            });
+        match argument {
+            Some((value, length)) => {
+                output.push_str(&value.to_string());
+                rest = &rest[length..];
+            },
+            None => {
+                output.push_str(&rest[..1]);
+                rest = &rest[1..];
+            },
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+fn unescape(line: usize, text: &str) -> Result<String, error::TextError> {
+    let mut output = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            output.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => output.push('\n'),
+            Some('t') => output.push('\t'),
+            Some('#') => output.push('#'),
+            Some('\\') => output.push('\\'),
+            _ => return Err(error::TextError::Syntax(line, "unknown escape".to_string())),
+        }
+    }
+    Ok(output)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('#', "\\#")
+        .replace('\n', "\\n")
+        .replace('\t', "\\t")
+}
+
+/// Drops a trailing comment, ignoring an escaped `\#`
+fn strip_comment(line: &str) -> &str {
+    let mut escaped = false;
+    for (idx, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '#' => return &line[..idx],
+            _ => {},
+        }
+    }
+    line
+}
+
+impl std::str::FromStr for StringTable {
+    type Err = error::TextError;
+    fn from_str(source: &str) -> Result<StringTable, error::TextError> {
+        let mut table = StringTable::default();
+        for (idx, line) in source.lines().enumerate() {
+            let line_number = idx + 1;
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let Some((key, text)) = line.split_once('=') else {
+                return Err(error::TextError::Syntax(line_number, "expected `key = text`".to_string()));
+            };
+            let key = key.trim();
+            if key.is_empty() || key.contains(char::is_whitespace) {
+                return Err(error::TextError::Syntax(line_number, format!("`{key}` is not a valid key")));
+            }
+            if table.entries.contains_key(key) {
+                return Err(error::TextError::DuplicateKey(line_number, key.to_string()));
+            }
+            table.entries.insert(key.to_string(), unescape(line_number, text.trim())?);
+        }
+        Ok(table)
+    }
+}
+
+impl std::fmt::Display for StringTable {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let mut keys: Vec<&String> = self.entries.keys().collect();
+        keys.sort();
+        for key in keys {
+            writeln!(formatter, "{key} = {}", escape(&self.entries[key]))?;
+        }
+        Ok(())
+    }
+}
+
 impl std::fmt::Display for Language {
     fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
-            Language::English => "English".fmt(formatter)
+            Language::English => "English".fmt(formatter),
+            Language::French => "French".fmt(formatter),
+            Language::German => "German".fmt(formatter),
+            Language::Spanish => "Spanish".fmt(formatter),
+            Language::Italian => "Italian".fmt(formatter),
+            Language::Portuguese => "Portuguese".fmt(formatter),
+            Language::Russian => "Russian".fmt(formatter),
+            Language::Japanese => "Japanese".fmt(formatter),
+            Language::Korean => "Korean".fmt(formatter),
+            Language::Chinese => "Chinese".fmt(formatter),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ Language, StringTable, Strings, substitute };
+
+    #[test]
+    fn test_parse_table() {
+        let table: StringTable = "# ui\nmenu.start = Start   \nsubtitle = Line one\\nLine two \\# not a comment # comment\n"
+            .parse()
+            .unwrap();
+        assert_eq!(table.get("menu.start"), Some("Start"));
+        assert_eq!(table.get("subtitle"), Some("Line one\nLine two # not a comment"));
+
+        let reread: StringTable = table.to_string().parse().unwrap();
+        assert_eq!(table, reread);
+
+        assert!(matches!("a = 1\na = 2".parse::<StringTable>(), Err(crate::error::TextError::DuplicateKey(2, _))));
+        assert!(matches!("no separator".parse::<StringTable>(), Err(crate::error::TextError::Syntax(1, _))));
+    }
+
+    #[test]
+    fn test_fallback_to_default() {
+        let mut strings = Strings::new(Language::English);
+        strings.insert(Language::English, "greeting = Hello\nfarewell = Goodbye".parse().unwrap());
+        strings.insert(Language::French, "greeting = Bonjour".parse().unwrap());
+
+        assert_eq!(strings.get("greeting", Language::French), Some("Bonjour"));
+        assert_eq!(strings.get("farewell", Language::French), Some("Goodbye"));
+        assert_eq!(strings.get("greeting", Language::German), Some("Hello"));
+        assert_eq!(strings.get("missing", Language::English), None);
+    }
+
+    #[test]
+    fn test_substitute() {
+        let days = 3;
+        let text = substitute("Welcome back, {name}. {days} days {{unused}} {missing}", &[("name", &"Ada"), ("days", &days)]);
+        assert_eq!(text, "Welcome back, Ada. 3 days {unused} {missing}");
+    }
+
+    #[test]
+    fn test_language_codes() {
+        for language in Language::ALL {
+            assert_eq!(Language::from_code(language.code()), Some(language));
         }
     }
 }
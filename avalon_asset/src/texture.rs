@@ -1,6 +1,9 @@
+pub mod packed;
+pub mod compress;
+
 use miniserde::{ Serialize, Deserialize };
 
-#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ColourSpace {
     RGB,
     SRGB,
@@ -8,9 +11,26 @@ pub enum ColourSpace {
     SRGBA,
 }
 
-#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+/// Block compression applied when packing. BC5 keeps only red and green, for normal maps
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Compression {
+    BC1,
+    BC3,
+    BC5,
+    BC7,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Texture {
     pub colour_space: ColourSpace,
+    /// Stored uncompressed when `None`
+    pub compression: Option<Compression>,
+}
+
+impl ColourSpace {
+    pub fn is_srgb(&self) -> bool {
+        matches!(self, ColourSpace::SRGB | ColourSpace::SRGBA)
+    }
 }
 
 impl std::fmt::Display for ColourSpace {
@@ -23,3 +43,23 @@ impl std::fmt::Display for ColourSpace {
         }
     }
 }
+
+impl Compression {
+    pub const ALL: [Compression; 4] = [
+        Compression::BC1,
+        Compression::BC3,
+        Compression::BC5,
+        Compression::BC7,
+    ];
+}
+
+impl std::fmt::Display for Compression {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Compression::BC1 => "BC1 (RGB)".fmt(formatter),
+            Compression::BC3 => "BC3 (RGBA)".fmt(formatter),
+            Compression::BC5 => "BC5 (RG, normal maps)".fmt(formatter),
+            Compression::BC7 => "BC7 (RGBA, high quality)".fmt(formatter),
+        }
+    }
+}
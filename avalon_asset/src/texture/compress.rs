@@ -0,0 +1,346 @@
+use crate::texture::Compression;
+
+type Block = [[u8; 4]; 16];
+
+/// BC7 interpolation weights for 4-bit indices, out of 64
+const BC7_WEIGHTS: [u32; 16] = [0, 4, 9, 13, 17, 21, 26, 30, 34, 38, 43, 47, 51, 55, 60, 64];
+
+/// Bytes per 4x4 block
+pub fn block_bytes(compression: Compression) -> usize {
+    match compression {
+        Compression::BC1 => 8,
+        Compression::BC3 | Compression::BC5 | Compression::BC7 => 16,
+    }
+}
+
+/// Size of a level once compressed. Levels smaller than a block still take a whole one
+pub fn compressed_size(compression: Compression, width: u32, height: u32) -> usize {
+    width.div_ceil(4) as usize * height.div_ceil(4) as usize * block_bytes(compression)
+}
+
+/// Encodes tightly packed RGBA8 texels into 4x4 blocks, left to right then top to bottom. Blocks past the
+/// edge of the image repeat its last row and column
+pub fn encode(compression: Compression, rgba: &[u8], width: u32, height: u32) -> Vec<u8> {
+    assert_eq!(rgba.len(), width as usize * height as usize * 4, "texels do not match the dimensions");
+    let mut encoded = Vec::with_capacity(compressed_size(compression, width, height));
+    for block_y in 0..height.div_ceil(4) {
+        for block_x in 0..width.div_ceil(4) {
+            let block = fetch_block(rgba, width, height, block_x * 4, block_y * 4);
+            match compression {
+                Compression::BC1 => encoded.extend(bc1_block(&block)),
+                Compression::BC3 => {
+                    encoded.extend(bc4_block(block.map(|texel| texel[3])));
+                    encoded.extend(bc1_block(&block));
+                },
+                Compression::BC5 => {
+                    encoded.extend(bc4_block(block.map(|texel| texel[0])));
+                    encoded.extend(bc4_block(block.map(|texel| texel[1])));
+                },
+                Compression::BC7 => encoded.extend(bc7_block(&block)),
+            }
+        }
+    }
+    encoded
+}
+
+fn fetch_block(rgba: &[u8], width: u32, height: u32, left: u32, top: u32) -> Block {
+    std::array::from_fn(|idx| {
+        let x = (left + idx as u32 % 4).min(width - 1) as usize;
+        let y = (top + idx as u32 / 4).min(height - 1) as usize;
+        let offset = (y * width as usize + x) * 4;
+        [rgba[offset], rgba[offset + 1], rgba[offset + 2], rgba[offset + 3]]
+    })
+}
+
+/// The line through the texels that best fits them, as its centre and direction
+fn principal_axis<const N: usize>(points: &[[f32; N]; 16]) -> ([f32; N], [f32; N]) {
+    let mut mean = [0.0; N];
+    for point in points.iter() {
+        for (total, value) in mean.iter_mut().zip(point) {
+            *total += value / 16.0;
+        }
+    }
+
+    let mut covariance = [[0.0f32; N]; N];
+    for point in points.iter() {
+        for row in 0..N {
+            for column in 0..N {
+                covariance[row][column] += (point[row] - mean[row]) * (point[column] - mean[column]);
+            }
+        }
+    }
+
+    // power iteration converges on the largest eigenvector quickly enough for 16 points. Starting from the
+    // row with the most variance keeps the start from being perpendicular to it
+    let widest = (0..N).max_by(|lhs, rhs| covariance[*lhs][*lhs].total_cmp(&covariance[*rhs][*rhs])).unwrap_or(0);
+    let mut axis = covariance[widest];
+    let length = axis.iter().map(|value| value * value).sum::<f32>().sqrt();
+    if length < f32::EPSILON {
+        return (mean, [0.0; N]);
+    }
+    axis = axis.map(|value| value / length);
+    for _ in 0..8 {
+        let mut next = [0.0; N];
+        for row in 0..N {
+            next[row] = (0..N).map(|column| covariance[row][column] * axis[column]).sum();
+        }
+        let length = next.iter().map(|value| value * value).sum::<f32>().sqrt();
+        if length < f32::EPSILON {
+            break;
+        }
+        axis = next.map(|value| value / length);
+    }
+    (mean, axis)
+}
+
+/// The furthest points along the principal axis, lowest first
+fn endpoints<const N: usize>(points: &[[f32; N]; 16]) -> ([f32; N], [f32; N]) {
+    let (mean, axis) = principal_axis(points);
+    let project = |point: &[f32; N]| (0..N).map(|idx| (point[idx] - mean[idx]) * axis[idx]).sum::<f32>();
+    let (low, high) = points.iter()
+        .map(project)
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(low, high), t| (low.min(t), high.max(t)));
+    let along = |t: f32| std::array::from_fn(|idx| (mean[idx] + axis[idx] * t).clamp(0.0, 255.0));
+    (along(low), along(high))
+}
+
+fn distance<const N: usize>(lhs: &[f32; N], rhs: &[f32; N]) -> f32 {
+    lhs.iter().zip(rhs).map(|(lhs, rhs)| (lhs - rhs) * (lhs - rhs)).sum()
+}
+
+fn nearest<const N: usize>(palette: &[[f32; N]], point: &[f32; N]) -> usize {
+    palette.iter()
+        .enumerate()
+        .min_by(|(_, lhs), (_, rhs)| distance(lhs, point).total_cmp(&distance(rhs, point)))
+        .map(|(idx, _)| idx)
+        .unwrap_or(0)
+}
+
+fn to_565(colour: &[f32; 3]) -> u16 {
+    let r = (colour[0] * 31.0 / 255.0).round() as u16;
+    let g = (colour[1] * 63.0 / 255.0).round() as u16;
+    let b = (colour[2] * 31.0 / 255.0).round() as u16;
+    (r << 11) | (g << 5) | b
+}
+
+fn from_565(colour: u16) -> [f32; 3] {
+    let r = (colour >> 11) & 31;
+    let g = (colour >> 5) & 63;
+    let b = colour & 31;
+    [((r << 3) | (r >> 2)) as f32, ((g << 2) | (g >> 4)) as f32, ((b << 3) | (b >> 2)) as f32]
+}
+
+/// Always in four colour mode, as BC3 requires, so alpha is left to the caller
+fn bc1_block(block: &Block) -> [u8; 8] {
+    let points = block.map(|texel| [texel[0] as f32, texel[1] as f32, texel[2] as f32]);
+    let (low, high) = endpoints(&points);
+    let (mut colour0, mut colour1) = (to_565(&high), to_565(&low));
+    if colour0 < colour1 {
+        std::mem::swap(&mut colour0, &mut colour1);
+    }
+
+    let mut indices = 0u32;
+    if colour0 != colour1 {
+        let (c0, c1) = (from_565(colour0), from_565(colour1));
+        let palette = [
+            c0,
+            c1,
+            std::array::from_fn(|idx| (2.0 * c0[idx] + c1[idx]) / 3.0),
+            std::array::from_fn(|idx| (c0[idx] + 2.0 * c1[idx]) / 3.0),
+        ];
+        for (idx, point) in points.iter().enumerate() {
+            indices |= (nearest(&palette, point) as u32) << (2 * idx);
+        }
+    }
+
+    let mut encoded = [0; 8];
+    encoded[0..2].copy_from_slice(&colour0.to_le_bytes());
+    encoded[2..4].copy_from_slice(&colour1.to_le_bytes());
+    encoded[4..8].copy_from_slice(&indices.to_le_bytes());
+    encoded
+}
+
+/// One channel, in eight value mode
+fn bc4_block(values: [u8; 16]) -> [u8; 8] {
+    let high = values.iter().copied().max().unwrap_or(0);
+    let low = values.iter().copied().min().unwrap_or(0);
+    let mut encoded = [0; 8];
+    encoded[0] = high;
+    encoded[1] = low;
+    if high == low {
+        return encoded;
+    }
+
+    let palette: [[f32; 1]; 8] = std::array::from_fn(|idx| match idx {
+        0 => [high as f32],
+        1 => [low as f32],
+        _ => [((8 - idx) as f32 * high as f32 + (idx - 1) as f32 * low as f32) / 7.0],
+    });
+    let mut indices = 0u64;
+    for (idx, value) in values.iter().enumerate() {
+        indices |= (nearest(&palette, &[*value as f32]) as u64) << (3 * idx);
+    }
+    encoded[2..8].copy_from_slice(&indices.to_le_bytes()[0..6]);
+    encoded
+}
+
+/// Seven bits per channel plus a bit shared by the endpoint, choosing whichever shared bit fits closer
+fn bc7_endpoint(endpoint: &[f32; 4]) -> ([u8; 4], u8) {
+    (0..2u8)
+        .map(|p_bit| {
+            let quantised = endpoint.map(|value| ((value - p_bit as f32) / 2.0).round().clamp(0.0, 127.0) as u8);
+            let error = distance(&quantised.map(|value| ((value << 1) | p_bit) as f32), endpoint);
+            (quantised, p_bit, error)
+        })
+        .min_by(|lhs, rhs| lhs.2.total_cmp(&rhs.2))
+        .map(|(quantised, p_bit, _)| (quantised, p_bit))
+        .unwrap()
+}
+
+struct Bits {
+    value: u128,
+    written: u32,
+}
+
+impl Bits {
+    fn push(&mut self, value: u32, count: u32) {
+        self.value |= ((value & ((1 << count) - 1)) as u128) << self.written;
+        self.written += count;
+    }
+}
+
+/// Mode 6: one subset, RGBA endpoints and 4-bit indices
+fn bc7_block(block: &Block) -> [u8; 16] {
+    let points = block.map(|texel| texel.map(|channel| channel as f32));
+    let (low, high) = endpoints(&points);
+    let (mut endpoint0, mut p_bit0) = bc7_endpoint(&low);
+    let (mut endpoint1, mut p_bit1) = bc7_endpoint(&high);
+
+    let expand = |endpoint: [u8; 4], p_bit: u8| endpoint.map(|value| ((value << 1) | p_bit) as u32);
+    let (e0, e1) = (expand(endpoint0, p_bit0), expand(endpoint1, p_bit1));
+    let palette: [[f32; 4]; 16] = BC7_WEIGHTS.map(|weight| {
+        std::array::from_fn(|idx| (((64 - weight) * e0[idx] + weight * e1[idx] + 32) >> 6) as f32)
+    });
+    let mut indices = points.map(|point| nearest(&palette, &point) as u32);
+
+    // the first index is stored without its top bit, so it has to be in the lower half
+    if indices[0] >= 8 {
+        std::mem::swap(&mut endpoint0, &mut endpoint1);
+        std::mem::swap(&mut p_bit0, &mut p_bit1);
+        indices = indices.map(|idx| 15 - idx);
+    }
+
+    let mut bits = Bits { value: 0, written: 0 };
+    bits.push(1 << 6, 7);
+    for channel in 0..4 {
+        bits.push(endpoint0[channel] as u32, 7);
+        bits.push(endpoint1[channel] as u32, 7);
+    }
+    bits.push(p_bit0 as u32, 1);
+    bits.push(p_bit1 as u32, 1);
+    for (idx, index) in indices.iter().enumerate() {
+        bits.push(*index, if idx == 0 { 3 } else { 4 });
+    }
+    debug_assert_eq!(bits.written, 128);
+    bits.value.to_le_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ encode, from_565, BC7_WEIGHTS };
+    use crate::texture::Compression;
+
+    fn decode_bc1(block: &[u8]) -> [[u8; 3]; 16] {
+        let c0 = from_565(u16::from_le_bytes([block[0], block[1]]));
+        let c1 = from_565(u16::from_le_bytes([block[2], block[3]]));
+        let indices = u32::from_le_bytes([block[4], block[5], block[6], block[7]]);
+        std::array::from_fn(|idx| {
+            let (w0, w1) = [(3.0, 0.0), (0.0, 3.0), (2.0, 1.0), (1.0, 2.0)][((indices >> (2 * idx)) & 3) as usize];
+            std::array::from_fn(|channel| ((w0 * c0[channel] + w1 * c1[channel]) / 3.0) as u8)
+        })
+    }
+
+    fn decode_bc4(block: &[u8]) -> [u8; 16] {
+        let (high, low) = (block[0] as f32, block[1] as f32);
+        let mut bytes = [0; 8];
+        bytes[0..6].copy_from_slice(&block[2..8]);
+        let indices = u64::from_le_bytes(bytes);
+        std::array::from_fn(|idx| match (indices >> (3 * idx)) & 7 {
+            0 => high as u8,
+            1 => low as u8,
+            i => (((8 - i) as f32 * high + (i - 1) as f32 * low) / 7.0) as u8,
+        })
+    }
+
+    fn decode_bc7(block: &[u8]) -> [[u8; 4]; 16] {
+        let bits = u128::from_le_bytes(block.try_into().unwrap());
+        let read = |offset: u32, count: u32| ((bits >> offset) & ((1 << count) - 1)) as u32;
+        assert_eq!(read(0, 7), 1 << 6);
+        let (p0, p1) = (read(63, 1), read(64, 1));
+        let e0: [u32; 4] = std::array::from_fn(|channel| (read(7 + 14 * channel as u32, 7) << 1) | p0);
+        let e1: [u32; 4] = std::array::from_fn(|channel| (read(14 + 14 * channel as u32, 7) << 1) | p1);
+        std::array::from_fn(|idx| {
+            let index = if idx == 0 { read(65, 3) } else { read(64 + 4 * idx as u32, 4) };
+            let weight = BC7_WEIGHTS[index as usize];
+            std::array::from_fn(|channel| (((64 - weight) * e0[channel] + weight * e1[channel] + 32) >> 6) as u8)
+        })
+    }
+
+    fn gradient() -> Vec<u8> {
+        (0..16u8).flat_map(|idx| [idx * 16, 255 - idx * 16, 128, idx * 8 + 64]).collect()
+    }
+
+    fn close(lhs: u8, rhs: u8, tolerance: u8) -> bool {
+        lhs.abs_diff(rhs) <= tolerance
+    }
+
+    #[test]
+    fn test_bc1_gradient() {
+        let texels = gradient();
+        let encoded = encode(Compression::BC1, &texels, 4, 4);
+        assert_eq!(encoded.len(), 8);
+        for (idx, decoded) in decode_bc1(&encoded).iter().enumerate() {
+            for channel in 0..3 {
+                assert!(close(decoded[channel], texels[idx * 4 + channel], 40), "texel {idx} channel {channel}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_bc3_and_bc5_channels() {
+        let texels = gradient();
+        let bc3 = encode(Compression::BC3, &texels, 4, 4);
+        assert_eq!(bc3.len(), 16);
+        for (idx, alpha) in decode_bc4(&bc3[0..8]).iter().enumerate() {
+            assert!(close(*alpha, texels[idx * 4 + 3], 10));
+        }
+
+        let bc5 = encode(Compression::BC5, &texels, 4, 4);
+        let (red, green) = (decode_bc4(&bc5[0..8]), decode_bc4(&bc5[8..16]));
+        for idx in 0..16 {
+            assert!(close(red[idx], texels[idx * 4], 20));
+            assert!(close(green[idx], texels[idx * 4 + 1], 20));
+        }
+    }
+
+    #[test]
+    fn test_bc7_gradient() {
+        let texels = gradient();
+        let encoded = encode(Compression::BC7, &texels, 4, 4);
+        for (idx, decoded) in decode_bc7(&encoded).iter().enumerate() {
+            for channel in 0..4 {
+                assert!(close(decoded[channel], texels[idx * 4 + channel], 12), "texel {idx} channel {channel}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_partial_blocks() {
+        let texels = [255, 0, 0, 255].repeat(5 * 3);
+        let encoded = encode(Compression::BC7, &texels, 5, 3);
+        assert_eq!(encoded.len(), 2 * 16);
+        for decoded in decode_bc7(&encoded[16..32]) {
+            assert!(close(decoded[0], 255, 2) && close(decoded[1], 0, 2) && close(decoded[3], 255, 2));
+        }
+    }
+}
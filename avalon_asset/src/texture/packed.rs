@@ -0,0 +1,202 @@
+use crate::texture::{ compress, Compression, Texture };
+
+/// A texture ready to upload: every mip level, largest first, as RGBA8 texels or compressed blocks
+#[derive(Debug, Clone, PartialEq)]
+pub struct PackedTexture {
+    pub compression: Option<Compression>,
+    pub width: u32,
+    pub height: u32,
+    pub levels: Vec<Vec<u8>>,
+}
+
+impl PackedTexture {
+    /// Tells a packed texture apart from the source images stored by older bundles
+    const MAGIC: &[u8; 4] = b"AVTX";
+
+    /// Generates the mip chain from tightly packed RGBA8 texels, then compresses each level
+    pub fn new(rgba: &[u8], width: u32, height: u32, texture: Texture) -> PackedTexture {
+        let mut levels = vec![rgba.to_vec()];
+        let (mut level_width, mut level_height) = (width, height);
+        while level_width > 1 || level_height > 1 {
+            let next = downsample(levels.last().unwrap(), level_width, level_height, texture.colour_space.is_srgb());
+            levels.push(next);
+            level_width = (level_width / 2).max(1);
+            level_height = (level_height / 2).max(1);
+        }
+
+        if let Some(compression) = texture.compression {
+            levels = levels.iter()
+                .enumerate()
+                .map(|(level, texels)| {
+                    let (level_width, level_height) = level_dimensions(width, height, level);
+                    compress::encode(compression, texels, level_width, level_height)
+                })
+                .collect();
+        }
+
+        PackedTexture {
+            compression: texture.compression,
+            width,
+            height,
+            levels,
+        }
+    }
+
+    pub fn level_dimensions(&self, level: usize) -> (u32, u32) {
+        level_dimensions(self.width, self.height, level)
+    }
+
+    pub fn to_buffer(&self) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        buffer.extend_from_slice(Self::MAGIC);
+        let compression: u32 = match self.compression {
+            None => 0,
+            Some(Compression::BC1) => 1,
+            Some(Compression::BC3) => 3,
+            Some(Compression::BC5) => 5,
+            Some(Compression::BC7) => 7,
+        };
+        buffer.extend_from_slice(&compression.to_be_bytes());
+        buffer.extend_from_slice(&self.width.to_be_bytes());
+        buffer.extend_from_slice(&self.height.to_be_bytes());
+        buffer.extend_from_slice(&(self.levels.len() as u32).to_be_bytes());
+        for level in self.levels.iter() {
+            buffer.extend_from_slice(&(level.len() as u32).to_be_bytes());
+            buffer.extend_from_slice(level);
+        }
+        buffer
+    }
+
+    /// `None` when the buffer is not a packed texture, such as the source image stored by bundles packed
+    /// before textures were processed
+    pub fn from_buffer(buffer: &[u8]) -> Option<PackedTexture> {
+        let mut words = buffer.strip_prefix(Self::MAGIC)?;
+        let compression = match next_word(&mut words)? {
+            0 => None,
+            1 => Some(Compression::BC1),
+            3 => Some(Compression::BC3),
+            5 => Some(Compression::BC5),
+            7 => Some(Compression::BC7),
+            _ => return None,
+        };
+        let width = next_word(&mut words)?;
+        let height = next_word(&mut words)?;
+        let level_count = next_word(&mut words)?;
+
+        let mut levels = Vec::new();
+        for _ in 0..level_count {
+            let length = next_word(&mut words)? as usize;
+            if words.len() < length {
+                return None;
+            }
+            let (level, rest) = words.split_at(length);
+            levels.push(level.to_vec());
+            words = rest;
+        }
+
+        Some(PackedTexture {
+            compression,
+            width,
+            height,
+            levels,
+        })
+    }
+}
+
+fn next_word(words: &mut &[u8]) -> Option<u32> {
+    let (word, rest) = words.split_first_chunk::<4>()?;
+    *words = rest;
+    Some(u32::from_be_bytes(*word))
+}
+
+fn level_dimensions(width: u32, height: u32, level: usize) -> (u32, u32) {
+    ((width >> level).max(1), (height >> level).max(1))
+}
+
+fn to_linear(value: u8) -> f32 {
+    let value = value as f32 / 255.0;
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn from_linear(value: f32) -> u8 {
+    let value = if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    };
+    (value * 255.0).round().clamp(0.0, 255.0) as u8
+}
+
+/// Halves each side with a box filter, averaging sRGB colour in linear space. Odd sides fold their last
+/// texel into the one before
+fn downsample(rgba: &[u8], width: u32, height: u32, srgb: bool) -> Vec<u8> {
+    let (next_width, next_height) = ((width / 2).max(1), (height / 2).max(1));
+    let mut next = Vec::with_capacity(next_width as usize * next_height as usize * 4);
+    for y in 0..next_height {
+        for x in 0..next_width {
+            let columns = (x * 2)..if x == next_width - 1 { width } else { x * 2 + 2 };
+            let rows = (y * 2)..if y == next_height - 1 { height } else { y * 2 + 2 };
+            let mut total = [0.0f32; 4];
+            let mut count = 0.0;
+            for source_y in rows.clone() {
+                for source_x in columns.clone() {
+                    let offset = (source_y as usize * width as usize + source_x as usize) * 4;
+                    for channel in 0..4 {
+                        let value = rgba[offset + channel];
+                        total[channel] += if srgb && channel < 3 { to_linear(value) } else { value as f32 / 255.0 };
+                    }
+                    count += 1.0;
+                }
+            }
+            for (channel, total) in total.iter().enumerate() {
+                let average = total / count;
+                next.push(if srgb && channel < 3 {
+                    from_linear(average)
+                } else {
+                    (average * 255.0).round() as u8
+                });
+            }
+        }
+    }
+    next
+}
+
+#[cfg(test)]
+mod packed_texture_tests {
+    use super::PackedTexture;
+    use crate::texture::{ ColourSpace, Compression, Texture };
+
+    #[test]
+    fn test_pack_equal_unpack() {
+        let texels = [10, 20, 30, 255].repeat(8 * 4);
+        for compression in [None, Some(Compression::BC1), Some(Compression::BC7)] {
+            let packed = PackedTexture::new(&texels, 8, 4, Texture { colour_space: ColourSpace::RGBA, compression });
+            assert_eq!(PackedTexture::from_buffer(&packed.to_buffer()), Some(packed));
+        }
+        assert_eq!(PackedTexture::from_buffer(b"\x89PNG\r\n"), None);
+    }
+
+    #[test]
+    fn test_mip_chain() {
+        let mut texels = [0, 0, 0, 255].repeat(5 * 3);
+        texels[0..4].copy_from_slice(&[255, 255, 255, 255]);
+        let packed = PackedTexture::new(&texels, 5, 3, Texture { colour_space: ColourSpace::RGBA, compression: None });
+
+        let sizes: Vec<_> = (0..packed.levels.len()).map(|level| packed.level_dimensions(level)).collect();
+        assert_eq!(sizes, vec![(5, 3), (2, 1), (1, 1)]);
+        for (level, texels) in packed.levels.iter().enumerate() {
+            let (width, height) = packed.level_dimensions(level);
+            assert_eq!(texels.len(), (width * height * 4) as usize);
+        }
+        // the white texel is one of the six folded into the first texel of the second level
+        assert_eq!(packed.levels[1][0], (255.0f32 / 6.0).round() as u8);
+        assert_eq!(packed.levels[2][3], 255);
+
+        let compressed = PackedTexture::new(&texels, 5, 3, Texture { colour_space: ColourSpace::SRGBA, compression: Some(Compression::BC3) });
+        assert_eq!(compressed.levels.iter().map(Vec::len).collect::<Vec<_>>(), vec![2 * 16, 16, 16]);
+    }
+}
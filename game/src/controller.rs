@@ -5,7 +5,7 @@ use avalon::ecs::system::System;
 use avalon::input::layer::Layer;
 use avalon::input::action;
 use avalon::event::Channel;
-use nalgebra_glm::{ vec3, vec2 };
+use nalgebra_glm::vec3;
 
 pub struct PlayerControllerSystem {
     input: Channel<action::Action, &'static str>
@@ -26,10 +26,7 @@ impl PlayerControllerSystem {
         PlayerControllerSystem {
             input: layer.context_handler()
                 .name("flycamera")
-                .action("move_forward")
-                .action("move_backward")
-                .action("strafe_left")
-                .action("strafe_right")
+                .action("move")
                 .action("jump")
                 .action("look")
                 .build()
@@ -59,18 +56,15 @@ impl PlayerControllerSystem {
 
             while let Some(action) = self.input.pop() {
                 match action.id.name.as_str() {
-                    "move_forward" => move_direction += forward_2d,
-                    "move_backward" => move_direction -= forward_2d,
-                    "strafe_left" => move_direction += left_2d,
-                    "strafe_right" => move_direction -= left_2d,
+                    "move" => {
+                        let axis = action.id.value.axis_2d();
+                        move_direction += forward_2d * axis.y - left_2d * axis.x;
+                    },
                     "jump" => jump += 1.0,
                     "look" => {
-                        let direction = vec2(
-                            action.data.retrieve::<f32>("axis_x").unwrap(),
-                            action.data.retrieve::<f32>("axis_y").unwrap()
-                        );
-                        camera_euler.pitch += direction.y * 0.05;
-                        camera_euler.yaw += -direction.x * 0.05;
+                        let direction = action.id.value.axis_2d();
+                        camera_euler.pitch += direction.y;
+                        camera_euler.yaw += -direction.x;
                     },
                     _ => {},
                 }
@@ -136,11 +130,12 @@ impl PlayerControllerSystem {
                 particle.velocity = vec3(0.0, 0.0, 0.0);
                 continue;
             }
+            let move_amount = move_direction.magnitude().min(1.0);
             let mut move_direction = move_direction.normalize();
 
             let cell = grid.cell_at_position(position);
             if let Some(cell) = cell {
-                if !cell.is_empty() && cell.cell_id() == voxel::CellType::SpaceTimeFus.into() {
+                if !cell.is_empty() && cell.cell_id() == u32::from(voxel::CellType::SpaceTimeFus) {
                     let move_direction_2d = vec3(
                         move_direction.x,
                         0.0,
@@ -150,7 +145,7 @@ impl PlayerControllerSystem {
                         position,
                         move_direction_2d,
                         algorithms::Condition::cell(|cell| {
-                            !cell.is_empty() && cell.cell_id() == voxel::CellType::SpaceTimeFus.into()
+                            !cell.is_empty() && cell.cell_id() == u32::from(voxel::CellType::SpaceTimeFus)
                         })
                     );
                     if raycast.distance.is_finite() {
@@ -161,7 +156,7 @@ impl PlayerControllerSystem {
             }
 
             let particle = entity.get_mut::<Particle>();
-            particle.velocity = move_direction * controller.max_speed;
+            particle.velocity = move_direction * move_amount * controller.max_speed;
         }
     }
 }
@@ -109,7 +109,8 @@ impl Stores {
 fn main() {
     let mut engine = avalon::engine();
 
-    let asset_library = avalon::asset_library::Library::new_with_scan("./assets/bins/");
+    let mut asset_library = avalon::asset_library::Library::new_with_scan("./assets/bins/");
+    let mut last_reload = std::time::Instant::now();
 
     let mut grid: voxel::Grid<32, 1> = voxel::Grid::new();
     for x in 0..20 {
@@ -151,20 +152,20 @@ fn main() {
             *grid.cell_mut(vec3(x, 3, z)) = voxel::CellType::SpaceTimeFus.into();
         }
     }
-    grid.bake();
+    let shaders = asset_library.bundle("shaders").expect("shaders bundle is built from assets/shaders/manifest.json");
+    grid.bake(&shaders.tag::<avalon::shader::Program>("bake_distance_field").unwrap().get().unwrap());
 
     let mut action_map = input::action::Map::new()
-        .map("move_forward")
-            .key(input::action::Keyboard::Hold(input::action::Key::Scancode(input::keyboard::Scancode::W)))
+        .map("move")
+            .axis_2d(input::action::Axis2d::Buttons {
+                up: input::action::Source::Key(input::action::Key::Scancode(input::keyboard::Scancode::W)),
+                down: input::action::Source::Key(input::action::Key::Scancode(input::keyboard::Scancode::S)),
+                left: input::action::Source::Key(input::action::Key::Scancode(input::keyboard::Scancode::A)),
+                right: input::action::Source::Key(input::action::Key::Scancode(input::keyboard::Scancode::D)),
+            })
             .finish()
-        .map("move_backward")
-            .key(input::action::Keyboard::Hold(input::action::Key::Scancode(input::keyboard::Scancode::S)))
-            .finish()
-        .map("strafe_left")
-            .key(input::action::Keyboard::Hold(input::action::Key::Scancode(input::keyboard::Scancode::A)))
-            .finish()
-        .map("strafe_right")
-            .key(input::action::Keyboard::Hold(input::action::Key::Scancode(input::keyboard::Scancode::D)))
+        .map("move")
+            .axis_2d(input::action::Axis2d::LeftStick)
             .finish()
         .map("jump")
             .key(input::action::Keyboard::Hold(input::action::Key::Scancode(input::keyboard::Scancode::Space)))
@@ -174,6 +175,11 @@ fn main() {
             .finish()
         .build();
     let mut inputs = input::Engine::new(&mut engine, action_map);
+    inputs.set_mouse_settings(input::mouse::Settings {
+        sensitivity: vec2(0.0025, 0.0025),
+        ..input::mouse::Settings::default()
+    });
+    inputs.set_relative_mouse(true);
     inputs.push_layer("test_layer");
 
 
@@ -203,8 +209,8 @@ fn main() {
     stores.camera_store.allocate(player);
 
 
-    let mut render_pass = render::RenderPass::new();
-    let mut debug_render_pass = render::DebugRenderPass::new();
+    let mut render_pass = render::RenderPass::new(&shaders);
+    let mut debug_render_pass = render::DebugRenderPass::new(&shaders);
 
     let mut accumulator = std::time::Duration::ZERO;
     let update_rate: std::time::Duration = std::time::Duration::from_secs_f64(1.0 / 60.0);
@@ -217,6 +223,13 @@ fn main() {
         inputs.poll();
         inputs.dispatch();
 
+        // pick up bundles rebuilt by asset_generator while iterating
+        if cfg!(debug_assertions) && last_reload.elapsed() > std::time::Duration::from_secs(1) {
+            asset_library.reload_changed();
+            last_reload = std::time::Instant::now();
+        }
+        asset_library.update();
+
         accumulator += frame_start.elapsed();
         frame_start = std::time::Instant::now();
 
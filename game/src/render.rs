@@ -137,7 +137,8 @@ pub struct RenderPass {
 }
 
 impl RenderPass {
-    pub fn new() -> RenderPass {
+    /// Programs come from `shaders`, the bundle built from assets/shaders/manifest.json
+    pub fn new(shaders: &avalon::asset_library::BundleView) -> RenderPass {
         let mut lights = vec![
             Light::Directional {
                 colour: vec3(1.0, 0.50, 0.55),
@@ -181,14 +182,14 @@ impl RenderPass {
             ao_halves: 2
         };
 
-        let pass_raytrace = pass_raytrace::PassRaytrace::new(options);
-        let pass_geometry = pass_geometry::PassGeometry::new(options);
-        let pass_lighting = pass_lighting::PassLighting::new(options);
-        let pass_lighting_combine = pass_lighting_combine::PassLightingCombine::new(options);
-        let pass_ao = pass_lighting_ao::PassLightingAo::new(options, 32);
-        let pass_ao_combine = pass_lighting_ao_combine::PassLightingAoCombine::new(options);
-        let pass_skybox = pass_skybox::PassSkybox::new(options);
-        let pass_post_process = pass_post_process::PassPostProcess::new(options);
+        let pass_raytrace = pass_raytrace::PassRaytrace::new(options, shaders);
+        let pass_geometry = pass_geometry::PassGeometry::new(options, shaders);
+        let pass_lighting = pass_lighting::PassLighting::new(options, shaders);
+        let pass_lighting_combine = pass_lighting_combine::PassLightingCombine::new(options, shaders);
+        let pass_ao = pass_lighting_ao::PassLightingAo::new(options, 32, shaders);
+        let pass_ao_combine = pass_lighting_ao_combine::PassLightingAoCombine::new(options, shaders);
+        let pass_skybox = pass_skybox::PassSkybox::new(options, shaders);
+        let pass_post_process = pass_post_process::PassPostProcess::new(options, shaders);
         RenderPass {
             options,
             pass_raytrace,
@@ -199,7 +200,7 @@ impl RenderPass {
             pass_ao_combine,
             pass_skybox,
             pass_post_process,
-            rescaler: algorithms::Rescaler::new(),
+            rescaler: algorithms::Rescaler::new(shaders),
             lights
         }
     }
@@ -212,13 +213,14 @@ impl RenderPass {
     ) where
     [(); SIDE_LENGTH * SIDE_LENGTH * SIDE_LENGTH]:, {
         let _annotation = GpuAnnotation::push("Game Render Pass");
-        let albedo_raw = assets.bundle("voxel-textures").unwrap().tag("albedo").unwrap();
-        let normal_raw = assets.bundle("voxel-textures").unwrap().tag("normal").unwrap();
+        let voxel_textures = assets.bundle("voxel-textures").unwrap();
+        let albedo_raw = voxel_textures.tag::<gpu::ManagedTexture<GpuTexture2d>>("albedo").unwrap();
+        let normal_raw = voxel_textures.tag::<gpu::ManagedTexture<GpuTexture2d>>("normal").unwrap();
         self.pass_raytrace.execute(
             camera,
             grid,
-            *albedo_raw,
-            *normal_raw,
+            **albedo_raw.get().unwrap(),
+            **normal_raw.get().unwrap(),
         );
 
         /*self.pass_geometry.execute(
@@ -296,8 +298,8 @@ pub struct DebugRenderPass {
 }
 
 impl DebugRenderPass {
-    pub fn new() -> DebugRenderPass {
-        let debug_lights = debug_pass_lights::DebugPassLights::new();
+    pub fn new(shaders: &avalon::asset_library::BundleView) -> DebugRenderPass {
+        let debug_lights = debug_pass_lights::DebugPassLights::new(shaders);
         DebugRenderPass {
             debug_lights
         }
@@ -1,4 +1,5 @@
-use avalon::shader::{ self, Source, Program, };
+use avalon::shader::Program;
+use avalon::asset_library::{ AssetView, BundleView };
 use avalon::viewport;
 use avalon::texture::{ Component, GpuTexture3d, GpuTexture2d };
 use avalon::texture::gpu::{ self, Arguments2d, UniqueTexture, Access, Sampler, Image };
@@ -10,18 +11,14 @@ use crate::render::{ Camera, PassOptions, Light, DebugLight };
 use nalgebra_glm::vec3;
 
 pub struct DebugPassLights {
-    shader: Program,
+    shader: AssetView<Program>,
     light_buffer: gpu_buffer::storage::Storage,
 }
 
 impl DebugPassLights {
-    pub fn new() -> DebugPassLights {
+    pub fn new(shaders: &BundleView) -> DebugPassLights {
         DebugPassLights {
-            shader: Program::new()
-                .vertex(shader::Vertex::load_from_path("assets/shaders/dev/light.vert").unwrap())
-                .fragment(shader::Fragment::load_from_path("assets/shaders/dev/light.frag").unwrap())
-                .build()
-                .unwrap(),
+            shader: shaders.tag::<Program>("light").unwrap(),
             light_buffer: gpu_buffer::storage::Storage::new()
         }
     }
@@ -40,9 +37,11 @@ impl DebugPassLights {
             .function(viewport::depth_options::Function::Less)
             .finish();
 
-        let icon_pointlight = icon_bundle.tag::<GpuTexture2d>("pointlight").unwrap();
-        let spotlight_on = icon_bundle.tag::<GpuTexture2d>("spotlight-off").unwrap();
-        let spotlight_off = icon_bundle.tag::<GpuTexture2d>("spotlight-off").unwrap();
+        let icon_pointlight = icon_bundle.tag::<gpu::ManagedTexture<GpuTexture2d>>("pointlight").unwrap();
+        let spotlight_on = icon_bundle.tag::<gpu::ManagedTexture<GpuTexture2d>>("spotlight-off").unwrap();
+        let spotlight_off = icon_bundle.tag::<gpu::ManagedTexture<GpuTexture2d>>("spotlight-off").unwrap();
+        let icon_pointlight = icon_pointlight.get().unwrap();
+        let spotlight_on = spotlight_on.get().unwrap();
 
         let point_lights = lights.iter().filter(|light| light.is_point());
         let spot_lights = lights.iter().filter(|light| light.is_spotlight());
@@ -58,7 +57,8 @@ impl DebugPassLights {
 
         let usage = gpu_buffer::storage::Usage::Dynamic(gpu_buffer::storage::Access::CpuWrite);
         {
-            let mut light_shader = self.shader.activate();
+            let program = self.shader.get().unwrap();
+            let mut light_shader = program.activate();
             light_shader.uniform("view").unwrap().set_mat4(camera.transform.matrix());
             light_shader.uniform("projection").unwrap().set_mat4(camera.projection);
             light_shader.sampler("icon", &*icon_pointlight).unwrap();
@@ -82,7 +82,8 @@ impl DebugPassLights {
             });
         }
         {
-            let mut light_shader = self.shader.activate();
+            let program = self.shader.get().unwrap();
+            let mut light_shader = program.activate();
             light_shader.uniform("view").unwrap().set_mat4(camera.transform.matrix());
             light_shader.uniform("projection").unwrap().set_mat4(camera.projection);
             light_shader.sampler("icon", &*spotlight_on).unwrap();
@@ -1,4 +1,5 @@
-use avalon::shader::{ self, Source, Program, };
+use avalon::shader::Program;
+use avalon::asset_library::{ AssetView, BundleView };
 use avalon::viewport;
 use avalon::texture::{ Component, GpuTexture3d, GpuTexture2d };
 use avalon::texture::gpu::{ self, Arguments2d, UniqueTexture, Access, Sampler, Image };
@@ -11,18 +12,14 @@ use crate::render::{ Camera, PassOptions, Light };
 use nalgebra_glm::vec3;
 
 pub struct PassGeometry {
-    shader: Program,
+    shader: AssetView<Program>,
     options: PassOptions
 }
 
 impl PassGeometry {
-    pub fn new(options: PassOptions) -> PassGeometry {
+    pub fn new(options: PassOptions, shaders: &BundleView) -> PassGeometry {
         PassGeometry {
-            shader: Program::new()
-                .vertex(shader::Vertex::load_from_path("assets/shaders/geo/star.vert").unwrap())
-                .fragment(shader::Fragment::load_from_path("assets/shaders/geo/star.frag").unwrap())
-                .build()
-                .unwrap(),
+            shader: shaders.tag::<Program>("star").unwrap(),
             options
         }
     }
@@ -50,7 +47,8 @@ impl PassGeometry {
                 .finish();
         }
 
-        let shader = self.shader.activate();
+        let program = self.shader.get().unwrap();
+        let shader = program.activate();
         shader.uniform("model").unwrap().set_mat4(transform.matrix());
         shader.uniform("view").unwrap().set_mat4(camera.transform.matrix());
         shader.uniform("projection").unwrap().set_mat4(camera.projection);
@@ -1,4 +1,5 @@
-use avalon::shader::{ self, Source, Program, };
+use avalon::shader::Program;
+use avalon::asset_library::{ AssetView, BundleView };
 use avalon::viewport;
 use avalon::texture::{ Component, GpuTexture3d, GpuTexture2d };
 use avalon::texture::gpu::{ self, Arguments2d, UniqueTexture, Access, Sampler, Image };
@@ -10,18 +11,15 @@ use crate::render::{ Camera, PassOptions, Light };
 use nalgebra_glm::vec3;
 
 pub struct PassLighting {
-    shader: Program,
+    shader: AssetView<Program>,
     pub lighting_buffer: GpuTexture2d,
     options: PassOptions,
 }
 
 impl PassLighting {
-    pub fn new(options: PassOptions) -> PassLighting {
+    pub fn new(options: PassOptions, shaders: &BundleView) -> PassLighting {
         PassLighting {
-            shader: Program::new()
-                .compute(shader::Compute::load_from_path("assets/shaders/voxel/lighting.comp").unwrap())
-                .build()
-                .unwrap(),
+            shader: shaders.tag::<Program>("lighting").unwrap(),
             lighting_buffer: GpuTexture2d::generate(Arguments2d {
                 data: None,
                 dimensions: options.lighting_resolution(),
@@ -44,7 +42,8 @@ impl PassLighting {
     [(); SIDE_LENGTH * SIDE_LENGTH * SIDE_LENGTH]:, {
         let _lighting_annotation = GpuAnnotation::push("Deferred Lighting");
         let grid_texture: &GpuTexture3d = grid.try_into().unwrap();
-        let mut bind = self.shader.activate();
+        let program = self.shader.get().unwrap();
+        let mut bind = program.activate();
 
         bind.sampler("grid", grid_texture).unwrap();
         bind.sampler("normalBuffer", normals).unwrap();
@@ -54,7 +53,7 @@ impl PassLighting {
         bind.uniform("halveCount").unwrap().set_i32(self.options.lighting_halves as i32);
         bind.uniform("gridSideLength").unwrap().set_i32(SIDE_LENGTH as i32);
 
-        let (dispatch_x, dispatch_y, dispatch_z) = self.shader.dispatch_counts(
+        let (dispatch_x, dispatch_y, dispatch_z) = program.dispatch_counts(
             self.options.lighting_resolution().x as usize,
             self.options.lighting_resolution().y as usize,
             1
@@ -1,4 +1,5 @@
-use avalon::shader::{ self, Source, Program, };
+use avalon::shader::Program;
+use avalon::asset_library::{ AssetView, BundleView };
 use avalon::viewport;
 use avalon::texture::{ Component, GpuTexture3d, GpuTexture2d };
 use avalon::texture::gpu::{ self, Arguments2d, UniqueTexture, Access, Sampler, Image };
@@ -10,30 +11,20 @@ use crate::render::{ Camera, PassOptions, Light };
 use nalgebra_glm::vec3;
 
 pub struct PassLightingAo {
-    shader_voxelize_light: Program,
-    shader_mipmap_light_voxels: Program,
-    shader_conetrace: Program,
+    shader_voxelize_light: AssetView<Program>,
+    shader_mipmap_light_voxels: AssetView<Program>,
+    shader_conetrace: AssetView<Program>,
     pub viewport: viewport::Viewport,
     light_voxels: GpuTexture3d,
     options: PassOptions,
 }
 
 impl PassLightingAo {
-    pub fn new(options: PassOptions, side_length: usize) -> PassLightingAo {
+    pub fn new(options: PassOptions, side_length: usize, shaders: &BundleView) -> PassLightingAo {
         PassLightingAo {
-            shader_voxelize_light: Program::new()
-                .compute(shader::Compute::load_from_path("assets/shaders/voxel/voxelize_light.comp").unwrap())
-                .build()
-                .unwrap(),
-            shader_mipmap_light_voxels: Program::new()
-                .compute(shader::Compute::load_from_path("assets/shaders/voxel/mipmap_light.comp").unwrap())
-                .build()
-                .unwrap(),
-            shader_conetrace: Program::new()
-                .vertex(shader::Vertex::load_from_path("assets/shaders/voxel/world.vert").unwrap())
-                .fragment(shader::Fragment::load_from_path("assets/shaders/voxel/cone_indirect.frag").unwrap())
-                .build()
-                .unwrap(),
+            shader_voxelize_light: shaders.tag::<Program>("voxelize_light").unwrap(),
+            shader_mipmap_light_voxels: shaders.tag::<Program>("mipmap_light").unwrap(),
+            shader_conetrace: shaders.tag::<Program>("cone_indirect").unwrap(),
             viewport: viewport::Viewport::new(options.ao_resolution())
                 .colour_attachment()
                     .format(gpu::SizedComponent::FloatRGBA32)
@@ -62,14 +53,15 @@ impl PassLightingAo {
         let _annotation = GpuAnnotation::push("Cone Trace AO");
         {
             let _voxelize_annotation = GpuAnnotation::push("Voxelize Light");
-            let mut bind = self.shader_voxelize_light.activate();
+            let program = self.shader_voxelize_light.get().unwrap();
+            let mut bind = program.activate();
             bind.sampler("lightedScene", lighted_scene).unwrap();
             bind.sampler("positions", positions).unwrap();
             bind.image("lightVoxel", &self.light_voxels, Access::ReadWrite(0)).unwrap();
             //bind.uniform("halvedCount").unwrap().set_i32(self.options.lighting_halves as i32);
             bind.uniform("deltaTime").unwrap().set_f32(delta_time);
 
-            let (dispatch_x, dispatch_y, dispatch_z) = self.shader_voxelize_light.dispatch_counts(
+            let (dispatch_x, dispatch_y, dispatch_z) = program.dispatch_counts(
                 self.options.lighting_resolution().x as usize,
                 self.options.lighting_resolution().y as usize,
                 1
@@ -80,7 +72,8 @@ impl PassLightingAo {
 
         {
             let _voxelize_annotation = GpuAnnotation::push("Mipmap Light Voxels");
-            let mut bind = self.shader_mipmap_light_voxels.activate();
+            let program = self.shader_mipmap_light_voxels.get().unwrap();
+            let mut bind = program.activate();
             bind.sampler("lightVoxels", &self.light_voxels).unwrap();
 
             for level in 1..self.light_voxels.levels() {
@@ -88,7 +81,7 @@ impl PassLightingAo {
                 bind.uniform("level").unwrap().set_i32(level as i32);
 
                 let dimension = SIDE_LENGTH / 2_usize.pow(level);
-                let (dispatch_x, dispatch_y, dispatch_z) = self.shader_mipmap_light_voxels.dispatch_counts(
+                let (dispatch_x, dispatch_y, dispatch_z) = program.dispatch_counts(
                     dimension,
                     dimension,
                     dimension
@@ -100,7 +93,8 @@ impl PassLightingAo {
         let grid_texture: &GpuTexture3d = grid.try_into().unwrap();
 
         let viewport = self.viewport.bind();
-        let mut bind = self.shader_conetrace.activate();
+        let program = self.shader_conetrace.get().unwrap();
+        let mut bind = program.activate();
         bind.sampler("positionBuffer", positions).unwrap();
         bind.sampler("normalBuffer", normals).unwrap();
         bind.sampler("tangentBuffer", tangents).unwrap();
@@ -1,4 +1,5 @@
-use avalon::shader::{ self, Source, Program, };
+use avalon::shader::Program;
+use avalon::asset_library::{ AssetView, BundleView };
 use avalon::viewport;
 use avalon::texture::{ Component, GpuTexture3d, GpuTexture2d };
 use avalon::texture::gpu::{ self, Arguments2d, UniqueTexture, Access, Sampler, Image };
@@ -10,19 +11,15 @@ use crate::render::{ Camera, PassOptions, Light };
 use nalgebra_glm::vec3;
 
 pub struct PassLightingAoCombine {
-    shader: Program,
+    shader: AssetView<Program>,
     pub viewport: viewport::Viewport,
     options: PassOptions,
 }
 
 impl PassLightingAoCombine {
-    pub fn new(options: PassOptions) -> PassLightingAoCombine {
+    pub fn new(options: PassOptions, shaders: &BundleView) -> PassLightingAoCombine {
         PassLightingAoCombine {
-            shader: Program::new()
-                .vertex(shader::Vertex::load_from_path("assets/shaders/voxel/world.vert").unwrap())
-                .fragment(shader::Fragment::load_from_path("assets/shaders/voxel/ao_combine.frag").unwrap())
-                .build()
-                .unwrap(),
+            shader: shaders.tag::<Program>("ao_combine").unwrap(),
             viewport: viewport::Viewport::new(options.raytrace_size)
                 .colour_attachment()
                     .format(gpu::SizedComponent::FloatRGBA32)
@@ -39,7 +36,8 @@ impl PassLightingAoCombine {
     ) {
         let _annotation = GpuAnnotation::push("Combine With AO");
         let viewport = self.viewport.bind();
-        let mut bind = self.shader.activate();
+        let program = self.shader.get().unwrap();
+        let mut bind = program.activate();
         bind.sampler("lightBuffer", scene_lighting).unwrap();
         bind.sampler("aoBuffer", scene_ao).unwrap();
         bind.sampler("albedoBuffer", albedo).unwrap();
@@ -1,4 +1,5 @@
-use avalon::shader::{ self, Source, Program, };
+use avalon::shader::Program;
+use avalon::asset_library::{ AssetView, BundleView };
 use avalon::viewport;
 use avalon::texture::{ Component, GpuTexture3d, GpuTexture2d };
 use avalon::texture::gpu::{ self, Arguments2d, UniqueTexture, Access, Sampler, Image };
@@ -10,39 +11,23 @@ use crate::render::{ Camera, PassOptions, Light };
 use nalgebra_glm::vec3;
 
 pub struct PassPostProcess {
-    combiner: Program,
+    combiner: AssetView<Program>,
     combine_viewport: viewport::Viewport,
-    bloom: Program,
+    bloom: AssetView<Program>,
     bloom_viewport: viewport::Viewport,
-    tone_mapping: Program,
-    gamma_correction: Program,
+    tone_mapping: AssetView<Program>,
+    gamma_correction: AssetView<Program>,
     pub viewport: viewport::Viewport,
     options: PassOptions,
 }
 
 impl PassPostProcess {
-    pub fn new(options: PassOptions) -> PassPostProcess {
+    pub fn new(options: PassOptions, shaders: &BundleView) -> PassPostProcess {
         PassPostProcess {
-            combiner: Program::new()
-                .vertex(shader::Vertex::load_from_path("assets/shaders/voxel/world.vert").unwrap())
-                .fragment(shader::Fragment::load_from_path("assets/shaders/pp_combiner.frag").unwrap())
-                .build()
-                .unwrap(),
-            bloom: Program::new()
-                .vertex(shader::Vertex::load_from_path("assets/shaders/voxel/world.vert").unwrap())
-                .fragment(shader::Fragment::load_from_path("assets/shaders/bloom.frag").unwrap())
-                .build()
-                .unwrap(),
-            tone_mapping: Program::new()
-                .vertex(shader::Vertex::load_from_path("assets/shaders/voxel/world.vert").unwrap())
-                .fragment(shader::Fragment::load_from_path("assets/shaders/reinhard_tonemap.frag").unwrap())
-                .build()
-                .unwrap(),
-            gamma_correction: Program::new()
-                .vertex(shader::Vertex::load_from_path("assets/shaders/voxel/world.vert").unwrap())
-                .fragment(shader::Fragment::load_from_path("assets/shaders/gamma_correction.frag").unwrap())
-                .build()
-                .unwrap(),
+            combiner: shaders.tag::<Program>("pp_combiner").unwrap(),
+            bloom: shaders.tag::<Program>("bloom").unwrap(),
+            tone_mapping: shaders.tag::<Program>("reinhard_tonemap").unwrap(),
+            gamma_correction: shaders.tag::<Program>("gamma_correction").unwrap(),
             combine_viewport: viewport::Viewport::new(options.final_size)
                 .colour_attachment()
                     .format(gpu::SizedComponent::FloatRGBA32)
@@ -68,7 +53,8 @@ impl PassPostProcess {
         {
             let _annotation = GpuAnnotation::push("Bloom");
             let viewport = self.bloom_viewport.bind();
-            let mut bind = self.bloom.activate();
+            let program = self.bloom.get().unwrap();
+            let mut bind = program.activate();
             bind.uniform("uScreenSize").unwrap().set_ivec2(self.options.final_size);
             bind.sampler("scene", skydome).unwrap();
             bind.sampler("bloom", bloom_map).unwrap();
@@ -78,7 +64,8 @@ impl PassPostProcess {
         {
             let _annotation = GpuAnnotation::push("Combine Renders");
             let viewport = self.combine_viewport.bind();
-            let mut bind = self.combiner.activate();
+            let program = self.combiner.get().unwrap();
+            let mut bind = program.activate();
             bind.uniform("uScreenSize").unwrap().set_ivec2(self.options.final_size);
             bind.sampler("colour", &self.bloom_viewport.colour_attachment(0).unwrap().colour).unwrap();
             gpu_buffer::State::degenerate().bind().draw(&bind);
@@ -89,7 +76,8 @@ impl PassPostProcess {
         {
             let _annotation = GpuAnnotation::push("Tone Mapping");
             let viewport = self.viewport.bind();
-            let mut bind = self.tone_mapping.activate();
+            let program = self.tone_mapping.get().unwrap();
+            let mut bind = program.activate();
             bind.sampler("texture", &self.combine_viewport.colour_attachment(0).unwrap().colour).unwrap();
             bind.uniform("white").unwrap().set_vec3(vec3(4.0, 4.0, 4.0));
 
@@ -97,7 +85,8 @@ impl PassPostProcess {
         }
 
         let _annotation = GpuAnnotation::push("Gamma Correction");
-        let mut bind = self.gamma_correction.activate();
+        let program = self.gamma_correction.get().unwrap();
+        let mut bind = program.activate();
         bind.sampler("texture", &self.viewport.colour_attachment(0).unwrap().colour).unwrap();
 
         gpu_buffer::State::degenerate().bind().draw(&bind);
@@ -1,4 +1,5 @@
-use avalon::shader::{ self, Source, Program, };
+use avalon::shader::Program;
+use avalon::asset_library::{ AssetView, BundleView };
 use avalon::viewport;
 use avalon::texture::{ Component, GpuTexture3d, GpuTexture2d };
 use avalon::texture::gpu::{ self, Arguments2d, UniqueTexture, Access, Sampler, Image };
@@ -10,13 +11,13 @@ use crate::render::{ Camera, PassOptions };
 use nalgebra_glm::vec3;
 
 pub struct PassRaytrace {
-    shader: Program,
+    shader: AssetView<Program>,
     pub viewport: viewport::Viewport,
     options: PassOptions,
 }
 
 impl PassRaytrace {
-    pub fn new(options: PassOptions) -> PassRaytrace {
+    pub fn new(options: PassOptions, shaders: &BundleView) -> PassRaytrace {
         let mut viewport = viewport::Viewport::new(options.raytrace_size)
             .colour_attachment()
                 .tag("albedo")
@@ -40,11 +41,7 @@ impl PassRaytrace {
             .finish()
         .set_clear_colour(vec3(0.0, 0.0, 0.0));
         PassRaytrace {
-            shader: Program::new()
-                .vertex(shader::Vertex::load_from_path("assets/shaders/voxel/world.vert").unwrap())
-                .fragment(shader::Fragment::load_from_path("assets/shaders/voxel/world.frag").unwrap())
-                .build()
-                .unwrap(),
+            shader: shaders.tag::<Program>("world").unwrap(),
             viewport,
             options
         }
@@ -60,7 +57,8 @@ impl PassRaytrace {
     [(); SIDE_LENGTH * SIDE_LENGTH * SIDE_LENGTH]:, {
         let _raytrace_annotation = GpuAnnotation::push("Raytrace Grid");
         let grid_texture: &GpuTexture3d = grid.try_into().unwrap();
-        let mut bind = self.shader.activate();
+        let program = self.shader.get().unwrap();
+        let mut bind = program.activate();
         bind.uniform("uScreenSize").unwrap().set_ivec2(self.options.raytrace_size);
 
         bind.sampler("grid", grid_texture).unwrap();
@@ -1,4 +1,5 @@
-use avalon::shader::{ self, Source, Program, };
+use avalon::shader::Program;
+use avalon::asset_library::{ AssetView, BundleView };
 use avalon::viewport;
 use avalon::texture::{ Component, GpuTexture3d, GpuTexture2d };
 use avalon::texture::gpu::{ self, Arguments2d, UniqueTexture, Access, Sampler, Image };
@@ -11,26 +12,18 @@ use crate::render::{ Camera, PassOptions, Light };
 use nalgebra_glm::vec3;
 
 pub struct PassSkybox {
-    skybox_shader: Program,
-    star_shader: Program,
+    skybox_shader: AssetView<Program>,
+    star_shader: AssetView<Program>,
     pub viewport: viewport::Viewport,
     clock: std::time::Instant,
     options: PassOptions
 }
 
 impl PassSkybox {
-    pub fn new(options: PassOptions) -> PassSkybox {
+    pub fn new(options: PassOptions, shaders: &BundleView) -> PassSkybox {
         PassSkybox {
-            skybox_shader: Program::new()
-                .vertex(shader::Vertex::load_from_path("assets/shaders/geo/skybox.vert").unwrap())
-                .fragment(shader::Fragment::load_from_path("assets/shaders/geo/skybox.frag").unwrap())
-                .build()
-                .unwrap(),
-            star_shader: Program::new()
-                .vertex(shader::Vertex::load_from_path("assets/shaders/geo/star.vert").unwrap())
-                .fragment(shader::Fragment::load_from_path("assets/shaders/geo/star.frag").unwrap())
-                .build()
-                .unwrap(),
+            skybox_shader: shaders.tag::<Program>("skybox").unwrap(),
+            star_shader: shaders.tag::<Program>("star").unwrap(),
             viewport: viewport::Viewport::new(options.final_size)
                 .colour_attachment()
                     .tag("albedo")
@@ -53,8 +46,10 @@ impl PassSkybox {
         let _bind = self.viewport.bind();
         {
             let dome = assets.bundle("default").unwrap().tag::<gpu::ManagedTexture<GpuTexture2d>>("default").unwrap();
+            let dome = dome.get().unwrap();
 
-            let mut shader = self.skybox_shader.activate();
+            let program = self.skybox_shader.get().unwrap();
+            let mut shader = program.activate();
             shader.uniform("uScreenSize").unwrap().set_ivec2(self.options.final_size);
             shader.uniform("view").unwrap().set_mat4(camera.transform.matrix());
             shader.uniform("projection").unwrap().set_mat4(camera.projection);
@@ -67,12 +62,13 @@ impl PassSkybox {
         {
             let star = assets.bundle("ambient-visuals").unwrap().tag::<model::Model>("star-model").unwrap();
 
-            let mut shader = self.star_shader.activate();
+            let program = self.star_shader.get().unwrap();
+            let mut shader = program.activate();
             shader.uniform("view").unwrap().set_mat4(camera.transform.matrix());
             shader.uniform("projection").unwrap().set_mat4(camera.projection);
             shader.uniform("time").unwrap().set_f32(self.clock.elapsed().as_secs_f32());
 
-            star.bind().draw_instanced(&shader, 7000);
+            star.get().unwrap().bind().draw_instanced(&shader, 7000);
         }
     }
 }
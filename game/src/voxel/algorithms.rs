@@ -1,6 +1,6 @@
 use std::vec;
 
-use avalon::shader::{ self, Source };
+use avalon::shader;
 use avalon::texture::{ Component, data::Data };
 use avalon::texture::gpu::{ UniqueTexture, Access, SizedComponent, Arguments3d, Texture3d, Mipmap };
 use crate::voxel::{ cells, Cell, Grid };
@@ -31,8 +31,9 @@ impl Condition {
 
 impl<const SIDE_LENGTH: usize, const VOXELS_PER_METER: u32> Grid<SIDE_LENGTH, VOXELS_PER_METER> where
     [(); SIDE_LENGTH * SIDE_LENGTH * SIDE_LENGTH]:, {
+    /// `oven` is the `bake_distance_field` compute program
     #[allow(clippy::identity_op)]
-    pub fn bake(&mut self) {
+    pub fn bake(&mut self, oven: &shader::Program) {
         #[allow(clippy::erasing_op)]
         if self.gpu_grid.is_none() {
             self.gpu_grid = Some(Texture3d::generate(Arguments3d {
@@ -48,11 +49,6 @@ impl<const SIDE_LENGTH: usize, const VOXELS_PER_METER: u32> Grid<SIDE_LENGTH, VO
         }
         let grid_texture = self.gpu_grid.as_ref().unwrap();
 
-        let oven = shader::Program::new()
-            .compute(shader::Compute::load_from_path("assets/shaders/voxel/bake_distance_field.comp").unwrap())
-            .build()
-            .unwrap();
-
         let distance_buffer = {
             let data = {
                 let mut data = Data::empty_u8(Component::IntRGBA, SIDE_LENGTH * SIDE_LENGTH * SIDE_LENGTH);